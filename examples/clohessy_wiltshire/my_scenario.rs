@@ -1,10 +1,11 @@
 #![allow(non_snake_case)]
 
 use std::collections::HashMap;
-use nalgebra::{DMatrix, DVector};
+use nalgebra::DVector;
 use legion::*;
 use uuid::Uuid;
 use rand::prelude::*;
+use mads::float::Float;
 
 use mads::scene::scenario::Scenario;
 use mads::ecs::systems::simple::*;
@@ -46,12 +47,12 @@ impl MyScenario {
             let sim_id = SimID { uuid: id, name };
 
             // Initial x,y position and velocity
-            let distr = rand::distributions::Uniform::new_inclusive(-10f32, 10f32);
-            let mut state_slice = [0f32; 6];
+            let distr = rand::distributions::Uniform::new_inclusive(-10.0 as Float, 10.0 as Float);
+            let mut state_slice = [0.0; 6];
             for x in &mut state_slice {
                 *x = rng.sample(distr);
             }
-            let state = DVector::<f32>::from_row_slice(&state_slice);
+            let state = DVector::<Float>::from_row_slice(&state_slice);
             let fullstate = FullState { data: state };
 
             // Define dynamics model component
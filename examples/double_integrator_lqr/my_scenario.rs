@@ -5,6 +5,7 @@ use nalgebra::{DMatrix, DVector};
 use legion::*;
 use uuid::Uuid;
 use rand::prelude::*;
+use mads::float::Float;
 
 use mads::scene::scenario::Scenario;
 use mads::ecs::systems::simple::*;
@@ -38,8 +39,8 @@ impl MyScenario {
     let double_integrator = DoubleIntegrator3DComponent::new();
     let A = double_integrator.dynamics().A.clone();
     let B = double_integrator.dynamics().B.clone();
-    let Q = DMatrix::<f32>::identity(6, 6);
-    let R = DMatrix::<f32>::identity(3, 3);
+    let Q = DMatrix::<Float>::identity(6, 6);
+    let R = DMatrix::<Float>::identity(3, 3);
 
     let mut rng = thread_rng();
 
@@ -53,19 +54,19 @@ impl MyScenario {
             let sim_id = SimID { uuid: id, name };
 
             // Initial x,y position and velocity
-            let distr = rand::distributions::Uniform::new_inclusive(-10f32, 10f32);
-            let mut state_slice = [0f32; 6];
+            let distr = rand::distributions::Uniform::new_inclusive(-10.0 as Float, 10.0 as Float);
+            let mut state_slice = [0.0; 6];
             for x in &mut state_slice {
                 *x = rng.sample(distr);
             }
-            let state = DVector::<f32>::from_row_slice(&state_slice);
+            let state = DVector::<Float>::from_row_slice(&state_slice);
             let fullstate = FullState { data: state };
 
             // Define dynamics model component
             let dynamics = DoubleIntegrator3DComponent::new();
 
             // Define controller component
-            let controller = LQRComponent::new(A.clone(), B.clone(), Q.clone(), R.clone());
+            let controller = LQRComponent::new(A.clone(), B.clone(), Q.clone(), R.clone()).unwrap();
 
             (fullstate, dynamics, controller, sim_id)
         })
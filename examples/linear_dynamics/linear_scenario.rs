@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use nalgebra::{DMatrix, DVector};
 use legion::*;
 use uuid::Uuid;
+use mads::float::Float;
 
 use mads::scene::scenario::Scenario;
 use mads::ecs::systems::simple::*;
@@ -45,8 +46,8 @@ impl LinearScenario {
         let inverted_pendulum = LinearInvertedPendulumComponent::new();
         let A = inverted_pendulum.dynamics().A.clone();
         let B = inverted_pendulum.dynamics().B.clone();
-        let Q = DMatrix::<f32>::identity(4, 4);
-        let R = DMatrix::<f32>::identity(1, 1);
+        let Q = DMatrix::<Float>::identity(4, 4);
+        let R = DMatrix::<Float>::identity(1, 1);
 
         // Define Components for "Agent" Entity
         let agents: Vec<(FullState, LinearInvertedPendulumComponent, LQRComponent, SimID)> = (0..self.num_agents).into_iter()
@@ -57,14 +58,14 @@ impl LinearScenario {
                 let sim_id = SimID { uuid: id, name };
 
                 // Initial conditions
-                let state = DVector::<f32>::from_vec(vec![2.0, -3.0, 5.0, 1.0]);
+                let state = DVector::<Float>::from_vec(vec![2.0, -3.0, 5.0, 1.0]);
                 let fullstate = FullState { data: state };
 
                 // Define dynamics model component
                 let dynamics = LinearInvertedPendulumComponent::new();
 
                 // Define controller component
-                let controller = LQRComponent::new(A.clone(), B.clone(), Q.clone(), R.clone());
+                let controller = LQRComponent::new(A.clone(), B.clone(), Q.clone(), R.clone()).unwrap();
 
                 (fullstate, dynamics, controller, sim_id)
             })
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use nalgebra::DVector;
 use legion::*;
 use uuid::Uuid;
+use mads::float::Float;
 
 use mads::scene::scenario::Scenario;
 use mads::ecs::systems::simple::*;
@@ -12,7 +13,7 @@ use mads::ecs::resources::*;
 
 use crate::resources::NumAgents;
 
-use std::f32::consts::FRAC_PI_4;
+use mads::float::consts::FRAC_PI_4;
 
 pub struct NonlinearScenario {
 
@@ -54,7 +55,7 @@ impl NonlinearScenario {
                 let sim_id = SimID { uuid: id, name };
 
                 // Initial conditions
-                let state = DVector::<f32>::from_vec(vec![FRAC_PI_4, 3.0, FRAC_PI_4, 3.0]);
+                let state = DVector::<Float>::from_vec(vec![FRAC_PI_4, 3.0, FRAC_PI_4, 3.0]);
                 let fullstate = FullState { data: state };
 
                 // Define dynamics model to simulate
@@ -0,0 +1,22 @@
+use na::DVector;
+use crate::controls::models::ControlError;
+use crate::float::Float;
+
+/// Defines an interface for feedback controllers driving a StateSpaceRepresentation
+///
+/// u(t) = control(t, x(t), r(t))
+///
+/// The reference r(t) is the desired state, or None when the state is regulated to the origin.
+/// Controllers with internal state (integrators, receding-horizon plans) advance it in update,
+/// which is called once at the start of every engine step before the dynamics are integrated.
+///
+pub trait Controller {
+    fn control(&self, t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float>;
+
+    /// Prepares the controller for the step [t, t + dt] from the sampled state and reference
+    ///
+    /// Does nothing unless overridden by the controller
+    fn update(&mut self, _t: Float, _x: &DVector<Float>, _reference: Option<&DVector<Float>>, _dt: Float) -> Result<(), ControlError> {
+        Ok(())
+    }
+}
@@ -1,2 +1,5 @@
+// Representations
+pub mod controller;
+
 // Models
 pub mod models;
@@ -0,0 +1,208 @@
+use na::{DMatrix, DVector};
+use std::sync::OnceLock;
+use crate::controls::models::lqr::ControlError;
+use crate::math::riccati::*;
+use crate::float::Float;
+
+// Gain and Riccati solution, computed on first use
+type CachedSolution = OnceLock<Result<(DMatrix<Float>, DMatrix<Float>), ControlError>>;
+
+/// Discrete Infinite-Horizon Linear Quadratic Regulator
+///
+/// Minimizes the sum of x[k]^TQx[k] + u[k]^TRu[k] + 2x[k]^TNu[k] for x[k+1] = Ax[k] + Bu[k]
+/// The gain and Riccati solution are cached after the first call to solve()
+#[derive(Debug, Clone)]
+pub struct DiscreteLinearQuadraticRegulator {
+    A: DMatrix<Float>,
+    B: DMatrix<Float>,
+    R: DMatrix<Float>,
+    Q: DMatrix<Float>,
+    N: DMatrix<Float>,
+    solution: CachedSolution,
+}
+
+// Regulators are equal if they solve the same problem, whether or not they have been solved
+impl PartialEq for DiscreteLinearQuadraticRegulator {
+    fn eq(&self, other: &Self) -> bool {
+        self.A == other.A && self.B == other.B && self.Q == other.Q && self.R == other.R && self.N == other.N
+    }
+}
+
+impl DiscreteLinearQuadraticRegulator {
+    pub fn new(
+        A: DMatrix<Float>,
+        B: DMatrix<Float>,
+        Q: DMatrix<Float>,
+        R: DMatrix<Float>,
+        N: Option<DMatrix<Float>>
+    ) -> Self {
+        assert_eq!(A.shape().0, A.shape().1);
+        assert_eq!(A.shape().0, Q.shape().0);
+        assert_eq!(A.shape().1, Q.shape().1);
+        assert_eq!(B.shape().0, A.shape().0);
+        assert_eq!(B.shape().1, R.shape().0);
+
+        let N = N.unwrap_or_else(|| DMatrix::<Float>::zeros(B.shape().0, B.shape().1));
+        assert_eq!(N.shape(), B.shape());
+
+        Self { A, B, Q, R, N, solution: OnceLock::new() }
+    }
+
+    /// Replaces the linear model, invalidating the cached solution
+    pub fn set_model(&mut self, A: DMatrix<Float>, B: DMatrix<Float>) {
+        assert_eq!(A.shape(), self.A.shape());
+        assert_eq!(B.shape(), self.B.shape());
+
+        self.A = A;
+        self.B = B;
+        self.solution = OnceLock::new();
+    }
+
+    /// Replaces the state, input and cross weights, invalidating the cached solution
+    pub fn set_weights(&mut self, Q: DMatrix<Float>, R: DMatrix<Float>, N: Option<DMatrix<Float>>) {
+        assert_eq!(Q.shape(), self.Q.shape());
+        assert_eq!(R.shape(), self.R.shape());
+
+        let N = N.unwrap_or_else(|| DMatrix::<Float>::zeros(self.B.shape().0, self.B.shape().1));
+        assert_eq!(N.shape(), self.B.shape());
+
+        self.Q = Q;
+        self.R = R;
+        self.N = N;
+        self.solution = OnceLock::new();
+    }
+
+    /// Returns LQR gain and solution to the Discrete Algebraic Riccati Equation
+    /// u[k] = -Kx[k], K = (R + B^TPB)^-1 (B^TPA + N^T)
+    pub fn solve(&self) -> Result<(DMatrix<Float>, DMatrix<Float>), ControlError> {
+
+        self.solution.get_or_init(|| self.solve_riccati()).clone()
+
+    }
+
+    /// Returns the cached LQR gain, solving the Riccati equation on first use
+    pub fn gain(&self) -> Result<&DMatrix<Float>, ControlError> {
+
+        match self.solution.get_or_init(|| self.solve_riccati()) {
+            Ok((K, _P)) => Ok(K),
+            Err(error) => Err(error.clone()),
+        }
+
+    }
+
+    // Solves the Discrete Algebraic Riccati Equation with the cross term removed
+    fn solve_riccati(&self) -> Result<(DMatrix<Float>, DMatrix<Float>), ControlError> {
+
+        let Rinv = match self.R.clone().try_inverse() {
+            Some(inverse) => inverse,
+            None => return Err(ControlError::RiccatiError(RiccatiError::SingularWeightError)),
+        };
+
+        // Remove the cross term with the change of input u = v - R^-1 N^T x
+        let A = &self.A - &self.B * &Rinv * self.N.transpose();
+        let Q = &self.Q - &self.N * &Rinv * self.N.transpose();
+
+        let P = solve_discrete_riccati_eigen(&A, &self.B, &Q, &self.R)?;
+
+        let BT = self.B.transpose();
+        let K = match (&self.R + &BT * &P * &self.B).lu().solve(&(&BT * &P * &self.A + self.N.transpose())) {
+            Some(gain) => gain,
+            None => return Err(ControlError::SingularMatrixError("R + B^T P B".to_string())),
+        };
+
+        Ok((K, P))
+
+    }
+
+
+    /// Returns the stage cost of the LQR
+    /// V = x^TQx + u^TRu + 2x^TNu
+    pub fn cost_to_go(&self, x: &DVector<Float>, u: &DVector<Float>) -> Float {
+
+        let xTQx = x.transpose()*(&self.Q*x);
+        let uTRu = u.transpose()*(&self.R*u);
+        let xTNu = x.transpose()*(&self.N*u);
+
+        let cost = xTQx + uTRu + xTNu * 2.0;
+
+        *cost.get(0).unwrap()
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_DiscreteLinearQuadraticRegulator_solve() {
+
+        // Scalar system, P satisfies P^2 = P + 1 and K = P / (1 + P)
+        let A = DMatrix::from_vec(1, 1, vec![1.]);
+        let B = DMatrix::from_vec(1, 1, vec![1.]);
+        let Q = DMatrix::from_vec(1, 1, vec![1.]);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        let controller = DiscreteLinearQuadraticRegulator::new(A, B, Q, R, None);
+        let (K, P) = controller.solve().unwrap();
+
+        let P_true = (1.0 + (5.0 as Float).sqrt()) / 2.0;
+
+        assert_relative_eq!(P[(0, 0)], P_true, epsilon = 1E-4);
+        assert_relative_eq!(K[(0, 0)], P_true / (1.0 + P_true), epsilon = 1E-4);
+
+    }
+
+    #[test]
+    fn test_DiscreteLinearQuadraticRegulator_cross_term() {
+
+        // Sampled double integrator
+        let dt = 0.1;
+        let A = DMatrix::from_row_slice(2, 2, &[1., dt, 0., 1.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0.5*dt*dt, dt]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+        let N = DMatrix::from_row_slice(2, 1, &[0.1, 0.2]);
+
+        let controller = DiscreteLinearQuadraticRegulator::new(A.clone(), B.clone(), Q.clone(), R.clone(), Some(N.clone()));
+        let (K, P) = controller.solve().unwrap();
+
+        // P satisfies the DARE with cross term
+        // P = A^TPA - (A^TPB + N)(R + B^TPB)^-1(B^TPA + N^T) + Q
+        let residual = A.transpose() * &P * &A
+            - (A.transpose() * &P * &B + &N) * &K
+            + &Q
+            - &P;
+
+        assert!(residual.amax() < 1E-3 * P.amax());
+
+        // Closed loop is stable
+        let eigenvalues = (&A - &B * &K).complex_eigenvalues();
+        assert!(eigenvalues.iter().all(|lambda| lambda.re.hypot(lambda.im) < 1.0));
+
+    }
+
+    #[test]
+    fn test_DiscreteLinearQuadraticRegulator_cached_solution() {
+        let A = DMatrix::from_vec(1, 1, vec![1.]);
+        let B = DMatrix::from_vec(1, 1, vec![1.]);
+        let Q = DMatrix::from_vec(1, 1, vec![1.]);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        let mut controller = DiscreteLinearQuadraticRegulator::new(A, B, Q.clone(), R, None);
+        let (K, _P) = controller.solve().unwrap();
+        assert_eq!(controller.gain().unwrap(), &K);
+
+        // Changing the weights invalidates the cached gain
+        // With A = B = Q = 1 and R = r, P^2 = P + r and K = P / (r + P)
+        controller.set_weights(Q, DMatrix::from_vec(1, 1, vec![2.]), None);
+        let P_true = 2.0;
+        assert_relative_eq!(controller.gain().unwrap()[(0, 0)], P_true / (2.0 + P_true), epsilon = 1E-4);
+
+        // A failed solve is cached as an error
+        controller.set_model(DMatrix::from_vec(1, 1, vec![2.]), DMatrix::from_vec(1, 1, vec![0.]));
+        assert!(controller.gain().is_err());
+        assert!(controller.solve().is_err());
+    }
+}
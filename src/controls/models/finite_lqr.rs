@@ -0,0 +1,195 @@
+use na::{DMatrix, DVector};
+use crate::controls::models::lqr::ControlError;
+use crate::controls::controller::Controller;
+use crate::math::integrate::{solve_ivp, IntegratorType, SolverOptions};
+use crate::math::riccati::RiccatiError;
+use crate::float::Float;
+
+/// Continuous Finite-Horizon Linear Quadratic Regulator
+///
+/// Minimizes x(tf)^TFx(tf) + the integral of x^TQx + u^TRu over t_span with the time-varying gain
+/// K(t) = R^-1 B^T P(t), where P(t) solves the Riccati differential equation backward from P(tf) = F
+///
+/// t_span: (t0, tf) horizon of the regulator \
+/// times: increasing times of the stored gain schedule \
+/// gains: gain K(t) at each time \
+/// solutions: Riccati solution P(t) at each time \
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiniteHorizonLinearQuadraticRegulator {
+    pub t_span: (Float, Float),
+    pub times: Vec<Float>,
+    pub gains: Vec<DMatrix<Float>>,
+    pub solutions: Vec<DMatrix<Float>>,
+}
+
+impl FiniteHorizonLinearQuadraticRegulator {
+
+    /// Solves the Riccati differential equation over t_span and stores the gain at samples + 1
+    /// evenly spaced times
+    pub fn new(
+        A: DMatrix<Float>,
+        B: DMatrix<Float>,
+        Q: DMatrix<Float>,
+        R: DMatrix<Float>,
+        F: DMatrix<Float>,
+        t_span: (Float, Float),
+        samples: usize
+    ) -> Result<Self, ControlError> {
+        assert_eq!(A.shape().0, A.shape().1);
+        assert_eq!(A.shape().0, Q.shape().0);
+        assert_eq!(A.shape().1, Q.shape().1);
+        assert_eq!(Q.shape(), F.shape());
+        assert_eq!(B.shape().0, A.shape().0);
+        assert_eq!(B.shape().1, R.shape().0);
+        assert!(t_span.1 > t_span.0);
+        assert!(samples > 0);
+
+        let dx = A.shape().0;
+        let (t0, tf) = t_span;
+
+        let Rinv = match R.clone().try_inverse() {
+            Some(inverse) => inverse,
+            None => return Err(ControlError::RiccatiError(RiccatiError::SingularWeightError)),
+        };
+        let BRinvBT = &B * &Rinv * B.transpose();
+        let AT = A.transpose();
+
+        // Integrate in reversed time s = tf - t, where dP/ds = A^T P + P A - P B R^-1 B^T P + Q
+        // P is stacked column-major into the state vector
+        let riccati = |_s: Float, p: &DVector<Float>| {
+            let P = DMatrix::from_column_slice(dx, dx, p.as_slice());
+            let dP = &AT * &P + &P * &A - &P * &BRinvBT * &P + &Q;
+            DVector::from_column_slice(dP.as_slice())
+        };
+
+        let horizon = tf - t0;
+        let s_eval: Vec<Float> = (0..=samples)
+            .map(|k| if k == samples { horizon } else { horizon * k as Float / samples as Float })
+            .collect();
+
+        let p0 = DVector::from_column_slice(F.as_slice());
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-8, t_eval: Some(s_eval.clone()), ..SolverOptions::default() };
+        let result = match solve_ivp(riccati, (0.0, horizon), p0, IntegratorType::DOPRI5, opts) {
+            Ok(result) => result,
+            Err(error) => return Err(ControlError::IntegrationError(Box::new(error))),
+        };
+
+        // Store the schedule in increasing time
+        let times: Vec<Float> = s_eval.iter().rev().map(|s| tf - s).collect();
+        let solutions: Vec<DMatrix<Float>> = result.y.iter().rev()
+            .map(|p| {
+                let P = DMatrix::from_column_slice(dx, dx, p.as_slice());
+                (&P + P.transpose()) * 0.5
+            })
+            .collect();
+        let gains = solutions.iter().map(|P| &Rinv * B.transpose() * P).collect();
+
+        Ok(Self { t_span, times, gains, solutions })
+    }
+
+    /// Returns the gain K(t), linearly interpolated from the gain schedule
+    /// Times outside of t_span are clamped to the horizon
+    pub fn gain(&self, t: Float) -> DMatrix<Float> {
+
+        interpolate(&self.times, &self.gains, t)
+
+    }
+
+    /// Returns the Riccati solution P(t), linearly interpolated from the stored solutions
+    pub fn riccati_solution(&self, t: Float) -> DMatrix<Float> {
+
+        interpolate(&self.times, &self.solutions, t)
+
+    }
+
+    /// Returns the optimal cost-to-go from state x at time t
+    /// V = x^TP(t)x
+    pub fn cost_to_go(&self, t: Float, x: &DVector<Float>) -> Float {
+
+        let cost = x.transpose() * (self.riccati_solution(t) * x);
+
+        *cost.get(0).unwrap()
+
+    }
+
+}
+
+impl Controller for FiniteHorizonLinearQuadraticRegulator {
+
+    /// u = -K(t)(x - x_ref)
+    fn control(&self, t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        match reference {
+            Some(reference) => -self.gain(t) * (x - reference),
+            None => -self.gain(t) * x,
+        }
+
+    }
+
+}
+
+// Piecewise linear interpolation of matrices sampled at increasing times
+fn interpolate(times: &[Float], values: &[DMatrix<Float>], t: Float) -> DMatrix<Float> {
+
+    let last = times.len() - 1;
+    if t <= times[0] {
+        return values[0].clone();
+    }
+    if t >= times[last] {
+        return values[last].clone();
+    }
+
+    // Index of the interval containing t
+    let i = times.partition_point(|ti| *ti <= t).min(last) - 1;
+    let alpha = (t - times[i]) / (times[i + 1] - times[i]);
+
+    &values[i] * (1.0 - alpha) + &values[i + 1] * alpha
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::models::LinearQuadraticRegulator;
+
+    #[test]
+    fn test_FiniteHorizonLinearQuadraticRegulator_scalar() {
+
+        // x_dot = u with only a terminal cost, P(t) = 1 / (1/f + tf - t)
+        let A = DMatrix::from_vec(1, 1, vec![0.]);
+        let B = DMatrix::from_vec(1, 1, vec![1.]);
+        let Q = DMatrix::from_vec(1, 1, vec![0.]);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+        let F = DMatrix::from_vec(1, 1, vec![10.]);
+
+        let controller = FiniteHorizonLinearQuadraticRegulator::new(A, B, Q, R, F, (0.0, 2.0), 40).unwrap();
+
+        for t in [0.0, 0.55, 1.0, 2.0] {
+            let P_true = 1.0 / (0.1 + 2.0 - t);
+            assert_relative_eq!(controller.gain(t)[(0, 0)], P_true, epsilon = 1E-3);
+        }
+
+        let x = DVector::from_vec(vec![2.0]);
+        assert_relative_eq!(controller.cost_to_go(0.0, &x), 4.0 / 2.1, epsilon = 1E-3);
+
+    }
+
+    #[test]
+    fn test_FiniteHorizonLinearQuadraticRegulator_long_horizon() {
+
+        // Far from the end of a long horizon the gain approaches the infinite-horizon gain
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+        let F = DMatrix::<Float>::zeros(2, 2);
+
+        let finite = FiniteHorizonLinearQuadraticRegulator::new(A.clone(), B.clone(), Q.clone(), R.clone(), F, (0.0, 20.0), 100).unwrap();
+        let infinite = LinearQuadraticRegulator::new(A, B, Q, R).unwrap();
+        let (K, _P) = infinite.solve();
+
+        assert_relative_eq!(finite.gain(0.0), K, epsilon = 1E-3);
+        assert_relative_eq!(finite.gain(20.0), DMatrix::<Float>::zeros(1, 2), epsilon = 1E-6);
+
+    }
+}
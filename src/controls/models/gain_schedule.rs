@@ -0,0 +1,229 @@
+use na::{DMatrix, DVector};
+use crate::controls::models::lqr::{LinearQuadraticRegulator, ControlError};
+use crate::controls::controller::Controller;
+use crate::dynamics::linear_system::LTISystem;
+use crate::dynamics::statespace::StateSpaceRepresentation;
+use crate::float::Float;
+
+/// Methods of blending the gains of neighbouring operating points
+///
+/// Nearest: selects the operating point closest to the scheduling variable \
+/// Linear: linearly interpolates the operating points bracketing the scheduling variable \
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Nearest,
+    Linear,
+}
+
+/// Linear model and feedback gain designed about an operating point
+///
+/// schedule: value of the scheduling variable at the operating point \
+/// state: trim state x0 \
+/// input: trim input u0 \
+/// system: model linearized about (x0, u0) \
+/// gain: feedback gain K designed for the linearized model \
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatingPoint {
+    pub schedule: Float,
+    pub state: DVector<Float>,
+    pub input: DVector<Float>,
+    pub system: LTISystem,
+    pub gain: DMatrix<Float>,
+}
+
+/// Gain-scheduled state feedback controller
+///
+/// u = u0(σ) - K(σ)(x - x0(σ)), σ = s^Tx
+///
+/// The trim state, trim input and gain are looked up from a table of operating points sorted by
+/// the scheduling variable σ, and held at the first or last operating point outside of the table.
+/// When a reference state r is given, the controller regulates to r with the trim input scheduled
+/// at s^Tr, while the gain remains scheduled on the state.
+///
+/// points: operating points sorted by increasing schedule \
+/// scheduling: weights s of the scheduling variable in the state \
+/// interpolation: method of blending the operating points \
+#[derive(Debug, Clone, PartialEq)]
+pub struct GainScheduledController {
+    pub points: Vec<OperatingPoint>,
+    pub scheduling: DVector<Float>,
+    pub interpolation: Interpolation,
+}
+
+impl GainScheduledController {
+
+    pub fn new(mut points: Vec<OperatingPoint>, scheduling: DVector<Float>, interpolation: Interpolation) -> Self {
+        assert!(!points.is_empty());
+        let (n, m) = (points[0].state.len(), points[0].input.len());
+        assert_eq!(scheduling.len(), n);
+        for point in points.iter() {
+            assert_eq!(point.state.len(), n);
+            assert_eq!(point.input.len(), m);
+            assert_eq!(point.gain.shape(), (m, n));
+        }
+
+        points.sort_by(|a, b| a.schedule.partial_cmp(&b.schedule).expect("Operating point schedules must be ordered"));
+
+        Self { points, scheduling, interpolation }
+    }
+
+    /// Linearizes the model about each trim point (x0, u0) and designs an LQR gain for each
+    /// Fails if any linearized model has no stabilizing gain
+    pub fn from_model<T: StateSpaceRepresentation>(
+        model: &T,
+        trim: Vec<(DVector<Float>, DVector<Float>)>,
+        Q: DMatrix<Float>,
+        R: DMatrix<Float>,
+        scheduling: DVector<Float>,
+        interpolation: Interpolation
+    ) -> Result<Self, ControlError> {
+
+        let mut points = Vec::with_capacity(trim.len());
+        for (state, input) in trim {
+            let system = model.linearize(0.0, &state, &input);
+            let lqr = LinearQuadraticRegulator::new(system.A.clone(), system.B.clone(), Q.clone(), R.clone())?;
+            let gain = lqr.gain().clone();
+            let schedule = scheduling.dot(&state);
+            points.push(OperatingPoint { schedule, state, input, system, gain });
+        }
+
+        Ok(Self::new(points, scheduling, interpolation))
+
+    }
+
+    /// Scheduling variable σ = s^Tx
+    pub fn schedule(&self, x: &DVector<Float>) -> Float {
+
+        self.scheduling.dot(x)
+
+    }
+
+    /// Returns the trim state, trim input and gain scheduled at σ
+    pub fn interpolate(&self, sigma: Float) -> (DVector<Float>, DVector<Float>, DMatrix<Float>) {
+
+        let first = &self.points[0];
+        let last = &self.points[self.points.len() - 1];
+
+        // Hold the end points outside of the table
+        if sigma <= first.schedule {
+            return (first.state.clone(), first.input.clone(), first.gain.clone());
+        }
+        if sigma >= last.schedule {
+            return (last.state.clone(), last.input.clone(), last.gain.clone());
+        }
+
+        let index = self.points.partition_point(|point| point.schedule <= sigma);
+        let (lower, upper) = (&self.points[index - 1], &self.points[index]);
+        let s = (sigma - lower.schedule) / (upper.schedule - lower.schedule);
+
+        match self.interpolation {
+            Interpolation::Nearest => {
+                let point = if s < 0.5 { lower } else { upper };
+                (point.state.clone(), point.input.clone(), point.gain.clone())
+            },
+            Interpolation::Linear => (
+                &lower.state * (1.0 - s) + &upper.state * s,
+                &lower.input * (1.0 - s) + &upper.input * s,
+                &lower.gain * (1.0 - s) + &upper.gain * s,
+            ),
+        }
+
+    }
+
+}
+
+impl Controller for GainScheduledController {
+
+    /// u = u0(σ) - K(σ)(x - x0(σ)), or u = u0(s^Tr) - K(σ)(x - r) with a reference
+    fn control(&self, _t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        let (state, input, gain) = self.interpolate(self.schedule(x));
+
+        match reference {
+            Some(reference) => {
+                let (_, input, _) = self.interpolate(self.schedule(reference));
+                input - gain * (x - reference)
+            },
+            None => input - gain * (x - state),
+        }
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamics::models::HardeningSpring;
+
+    fn trim(positions: &[Float]) -> Vec<(DVector<Float>, DVector<Float>)> {
+        positions.iter().map(|p| HardeningSpring::trim(*p)).collect()
+    }
+
+    #[test]
+    fn test_GainScheduledController_from_model() {
+
+        let controller = GainScheduledController::from_model(
+            &HardeningSpring::new(),
+            trim(&[2.0, 0.0, 1.0]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::identity(1, 1),
+            DVector::from_vec(vec![1.0, 0.0]),
+            Interpolation::Linear
+        ).unwrap();
+
+        // Sorted by schedule, with the local stiffness 1 + 3p^2 in each linearized model
+        let schedules: Vec<Float> = controller.points.iter().map(|point| point.schedule).collect();
+        assert_eq!(schedules, vec![0.0, 1.0, 2.0]);
+        for point in controller.points.iter() {
+            let p = point.state[0];
+            assert_relative_eq!(point.system.A[(1, 0)], -1.0 - 3.0 * p * p, epsilon = 1E-2);
+        }
+        assert!(controller.points[2].gain[(0, 0)] < controller.points[0].gain[(0, 0)]);
+
+        // Holds the trim input at an operating point
+        let x = DVector::from_vec(vec![1.0, 0.0]);
+        assert_relative_eq!(controller.control(0.0, &x, None)[0], 2.0, epsilon = 1E-4);
+
+    }
+
+    #[test]
+    fn test_GainScheduledController_interpolate() {
+
+        let point = |schedule: Float, gain: Float| OperatingPoint {
+            schedule,
+            state: DVector::from_vec(vec![schedule, 0.0]),
+            input: DVector::from_vec(vec![2.0 * schedule]),
+            system: LTISystem::new(
+                DMatrix::<Float>::zeros(2, 2),
+                DMatrix::<Float>::zeros(2, 1),
+                DMatrix::<Float>::identity(2, 2),
+                DMatrix::<Float>::zeros(2, 1)
+            ),
+            gain: DMatrix::from_row_slice(1, 2, &[gain, 1.0]),
+        };
+        let points = vec![point(1.0, 4.0), point(0.0, 2.0)];
+        let scheduling = DVector::from_vec(vec![1.0, 0.0]);
+
+        let linear = GainScheduledController::new(points.clone(), scheduling.clone(), Interpolation::Linear);
+        let (state, input, gain) = linear.interpolate(0.25);
+        assert_relative_eq!(state, DVector::from_vec(vec![0.25, 0.0]));
+        assert_relative_eq!(input[0], 0.5);
+        assert_relative_eq!(gain[(0, 0)], 2.5);
+
+        // Held outside of the table
+        assert_relative_eq!(linear.interpolate(-1.0).2[(0, 0)], 2.0);
+        assert_relative_eq!(linear.interpolate(3.0).2[(0, 0)], 4.0);
+
+        let nearest = GainScheduledController::new(points, scheduling, Interpolation::Nearest);
+        assert_relative_eq!(nearest.interpolate(0.25).2[(0, 0)], 2.0);
+        assert_relative_eq!(nearest.interpolate(0.75).2[(0, 0)], 4.0);
+
+        // Regulates to the reference with its trim input and the gain scheduled on the state
+        let x = DVector::from_vec(vec![0.25, 0.0]);
+        let reference = DVector::from_vec(vec![0.5, 0.0]);
+        assert_relative_eq!(linear.control(0.0, &x, None)[0], 0.5);
+        assert_relative_eq!(linear.control(0.0, &x, Some(&reference))[0], 1.0 + 2.5 * 0.25);
+
+    }
+}
@@ -0,0 +1,440 @@
+use na::{DMatrix, DVector};
+use crate::controls::models::lqr::ControlError;
+use crate::controls::controller::Controller;
+use crate::dynamics::statespace::StateSpaceRepresentation;
+use crate::math::integrate::runge_kutta::rk4_step;
+use crate::math::jacobian::finite_difference;
+use crate::float::Float;
+
+/// Defines options for the iterative LQR solver
+///
+/// max_iterations: largest number of backward/forward passes \
+/// tolerance: relative cost reduction below which the solver has converged \
+/// regularization: initial Levenberg-Marquardt regularization of the value function Hessian \
+/// receding_horizon: re-plan from the sampled state every engine step, otherwise the plan from the first sample is tracked \
+#[derive(Debug, Clone, PartialEq)]
+pub struct ILQROptions {
+    pub max_iterations: usize,
+    pub tolerance: Float,
+    pub regularization: Float,
+    pub receding_horizon: bool,
+}
+
+impl Default for ILQROptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 100,
+            tolerance: 1E-6,
+            regularization: 1E-6,
+            receding_horizon: true,
+        }
+    }
+}
+
+/// Locally optimal trajectory from the iterative LQR
+///
+/// states: state trajectory [x[0], ..., x[N]] \
+/// inputs: input sequence [u[0], ..., u[N-1]] \
+/// gains: feedback gains K[k] about the trajectory, u = u[k] + K[k](x - x[k]) \
+/// cost: total cost of the trajectory \
+/// iterations: number of iterations taken \
+/// converged: false if the solver stopped at max_iterations or the regularization diverged \
+#[derive(Debug, Clone, PartialEq)]
+pub struct ILQRSolution {
+    pub states: Vec<DVector<Float>>,
+    pub inputs: Vec<DVector<Float>>,
+    pub gains: Vec<DMatrix<Float>>,
+    pub cost: Float,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Iterative Linear Quadratic Regulator for nonlinear dynamics
+///
+/// Minimizes (x[N] - r)^TQf(x[N] - r) + the sum of (x[k] - r)^TQ(x[k] - r) + u[k]^TRu[k] over
+/// the dynamics sampled with a zero-order hold, x[k+1] = F(x[k], u[k]). Each iteration solves
+/// the LQR of the dynamics linearized about the current trajectory backward in time, then rolls
+/// out the updated inputs with a line search.
+///
+/// As a Controller, u = u[k] + K[k](x - x[k]) tracks the plan from the last sample.
+///
+/// Reference: "Synthesis and Stabilization of Complex Behaviors through Online Trajectory
+/// Optimization" - Y. Tassa, T. Erez, E. Todorov
+///
+/// dynamics: continuous model of the system \
+/// dt: sample period of the plan \
+/// inputs: initial guess of the input sequence, warm started from the last plan \
+/// solution: last optimized trajectory \
+#[derive(Debug, Clone)]
+pub struct IterativeLinearQuadraticRegulator<T> {
+    pub dynamics: T,
+    pub dt: Float,
+    pub inputs: Vec<DVector<Float>>,
+    pub options: ILQROptions,
+    pub solution: Option<ILQRSolution>,
+    Q: DMatrix<Float>,
+    R: DMatrix<Float>,
+    Qf: DMatrix<Float>,
+    time: Float,
+}
+
+impl<T: StateSpaceRepresentation> IterativeLinearQuadraticRegulator<T> {
+
+    pub fn new(
+        dynamics: T,
+        dt: Float,
+        Q: DMatrix<Float>,
+        R: DMatrix<Float>,
+        Qf: DMatrix<Float>,
+        inputs: Vec<DVector<Float>>,
+        options: ILQROptions
+    ) -> Self {
+        assert!(dt > 0.0);
+        assert!(!inputs.is_empty());
+        assert_eq!(Q.shape().0, Q.shape().1);
+        assert_eq!(Q.shape(), Qf.shape());
+        assert_eq!(R.shape().0, R.shape().1);
+        assert!(inputs.iter().all(|u| u.len() == R.shape().0));
+
+        Self { dynamics, dt, inputs, options, solution: None, Q, R, Qf, time: 0.0 }
+    }
+
+    /// Number of samples N in the horizon
+    pub fn horizon(&self) -> usize { self.inputs.len() }
+
+    /// Optimizes the trajectory from x0 towards the reference state, starting from the input sequence
+    pub fn solve(&self, x0: &DVector<Float>, reference: Option<&DVector<Float>>, inputs: &[DVector<Float>]) -> Result<ILQRSolution, ControlError> {
+
+        let dx = x0.len();
+        assert_eq!(dx, self.Q.shape().0);
+        let reference = reference.cloned().unwrap_or_else(|| DVector::<Float>::zeros(dx));
+
+        let mut inputs = inputs.to_vec();
+        let mut states = self.rollout(x0, &inputs);
+        let mut cost = self.cost(&states, &inputs, &reference);
+        if !cost.is_finite() {
+            return Err(ControlError::NonFiniteError("cost of the initial trajectory".to_string()));
+        }
+
+        let mut mu = self.options.regularization;
+        let mut iterations = 0;
+        let mut converged = false;
+
+        while iterations < self.options.max_iterations {
+            iterations += 1;
+
+            let (feedforward, gains, expected) = match self.backward_pass(&states, &inputs, &reference, mu) {
+                Some(pass) => pass,
+                None => {
+                    // Hessian of the input is not positive definite, increase the regularization
+                    mu = (mu * 10.0).max(1E-6);
+                    if mu > 1E10 {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            // Backtracking line search on the feedforward step
+            let mut accepted = None;
+            let mut alpha = 1.0;
+            for _ in 0..10 {
+                let (new_states, new_inputs) = self.forward_pass(&states, &inputs, &feedforward, &gains, alpha);
+                let new_cost = self.cost(&new_states, &new_inputs, &reference);
+                let expected_reduction = -(alpha * expected.0 + alpha * alpha * expected.1);
+                if new_cost.is_finite() && new_cost < cost && (cost - new_cost) >= 1E-4 * expected_reduction {
+                    accepted = Some((new_states, new_inputs, new_cost));
+                    break;
+                }
+                alpha *= 0.5;
+            }
+
+            match accepted {
+                Some((new_states, new_inputs, new_cost)) => {
+                    let reduction = cost - new_cost;
+                    states = new_states;
+                    inputs = new_inputs;
+                    cost = new_cost;
+                    mu = (mu / 10.0).max(self.options.regularization);
+                    if reduction <= self.options.tolerance * cost.abs().max(1.0) {
+                        converged = true;
+                        break;
+                    }
+                },
+                None => {
+                    // No improvement along the step, the trajectory is locally optimal
+                    if expected.0.abs() <= self.options.tolerance * cost.abs().max(1.0) {
+                        converged = true;
+                        break;
+                    }
+                    mu = (mu * 10.0).max(1E-6);
+                    if mu > 1E10 {
+                        break;
+                    }
+                },
+            }
+        }
+
+        // Feedback gains about the final trajectory, with the regularization it was accepted at
+        let gains = match self.backward_pass(&states, &inputs, &reference, mu) {
+            Some((_feedforward, gains, _expected)) => gains,
+            None => return Err(ControlError::SingularMatrixError("input Hessian of the final trajectory".to_string())),
+        };
+
+        Ok(ILQRSolution { states, inputs, gains, cost, iterations, converged })
+
+    }
+
+    // Advances the state over one sample with the input held constant
+    fn step(&self, x: &DVector<Float>, u: &DVector<Float>) -> DVector<Float> {
+
+        let f = |t: Float, x: &DVector<Float>| self.dynamics.f(t, x, Some(u));
+
+        rk4_step(&f, 0.0, x, self.dt)
+
+    }
+
+    // State trajectory of the input sequence from x0
+    fn rollout(&self, x0: &DVector<Float>, inputs: &[DVector<Float>]) -> Vec<DVector<Float>> {
+
+        let mut states = vec![x0.clone()];
+        for u in inputs.iter() {
+            let x = self.step(&states[states.len() - 1], u);
+            states.push(x);
+        }
+
+        states
+
+    }
+
+    // Total cost of the trajectory
+    fn cost(&self, states: &[DVector<Float>], inputs: &[DVector<Float>], reference: &DVector<Float>) -> Float {
+
+        let N = inputs.len();
+        let mut cost = 0.0;
+        for k in 0..N {
+            let error = &states[k] - reference;
+            cost += error.dot(&(&self.Q * &error)) + inputs[k].dot(&(&self.R * &inputs[k]));
+        }
+        let error = &states[N] - reference;
+
+        cost + error.dot(&(&self.Qf * &error))
+
+    }
+
+    // Solves the LQR of the linearized dynamics backward in time
+    // Returns the feedforward steps, feedback gains and the terms of the expected cost change
+    #[allow(clippy::type_complexity)]
+    fn backward_pass(
+        &self,
+        states: &[DVector<Float>],
+        inputs: &[DVector<Float>],
+        reference: &DVector<Float>,
+        mu: Float
+    ) -> Option<(Vec<DVector<Float>>, Vec<DMatrix<Float>>, (Float, Float))> {
+
+        let N = inputs.len();
+        let dx = reference.len();
+        let mut feedforward = vec![DVector::<Float>::zeros(0); N];
+        let mut gains = vec![DMatrix::<Float>::zeros(0, 0); N];
+        let mut expected = (0.0, 0.0);
+
+        // Value function V = Vx^T dx + 1/2 dx^T Vxx dx
+        let mut Vx = &self.Qf * (&states[N] - reference) * 2.0;
+        let mut Vxx = &self.Qf * 2.0;
+        let regularization = DMatrix::<Float>::identity(dx, dx) * mu;
+
+        for k in (0..N).rev() {
+            let x = &states[k];
+            let u = &inputs[k];
+            let A = finite_difference(|x: &DVector<Float>| self.step(x, u), x);
+            let B = finite_difference(|u: &DVector<Float>| self.step(x, u), u);
+
+            let Qx = &self.Q * (x - reference) * 2.0 + A.transpose() * &Vx;
+            let Qu = &self.R * u * 2.0 + B.transpose() * &Vx;
+            let Qxx = &self.Q * 2.0 + A.transpose() * &Vxx * &A;
+            let Vxx_reg = &Vxx + &regularization;
+            let Quu = &self.R * 2.0 + B.transpose() * &Vxx_reg * &B;
+            let Qux = B.transpose() * &Vxx_reg * &A;
+
+            let Quu = (&Quu + Quu.transpose()) * 0.5;
+            let cholesky = Quu.clone().cholesky()?;
+            let kff = -cholesky.solve(&Qu);
+            let K = -cholesky.solve(&Qux);
+
+            expected.0 += kff.dot(&Qu);
+            expected.1 += 0.5 * kff.dot(&(&Quu * &kff));
+
+            Vx = &Qx + K.transpose() * &Quu * &kff + K.transpose() * &Qu + Qux.transpose() * &kff;
+            Vxx = &Qxx + K.transpose() * &Quu * &K + K.transpose() * &Qux + Qux.transpose() * &K;
+            Vxx = (&Vxx + Vxx.transpose()) * 0.5;
+
+            feedforward[k] = kff;
+            gains[k] = K;
+        }
+
+        Some((feedforward, gains, expected))
+
+    }
+
+    // Rolls out the updated inputs u = u[k] + alpha k[k] + K[k](x - x[k])
+    fn forward_pass(
+        &self,
+        states: &[DVector<Float>],
+        inputs: &[DVector<Float>],
+        feedforward: &[DVector<Float>],
+        gains: &[DMatrix<Float>],
+        alpha: Float
+    ) -> (Vec<DVector<Float>>, Vec<DVector<Float>>) {
+
+        let mut new_states = vec![states[0].clone()];
+        let mut new_inputs = Vec::with_capacity(inputs.len());
+        for k in 0..inputs.len() {
+            let x = &new_states[k];
+            let u = &inputs[k] + &feedforward[k] * alpha + &gains[k] * (x - &states[k]);
+            new_states.push(self.step(x, &u));
+            new_inputs.push(u);
+        }
+
+        (new_states, new_inputs)
+
+    }
+
+}
+
+impl<T: StateSpaceRepresentation> Controller for IterativeLinearQuadraticRegulator<T> {
+
+    /// Tracks the plan from the last sample, holding the final input and gain past the horizon
+    fn control(&self, t: Float, x: &DVector<Float>, _reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        match &self.solution {
+            Some(solution) => {
+                let N = solution.inputs.len();
+                // Sample k covers (t_k, t_k+1], so the end of an engine step does not switch inputs
+                let samples = (t - self.time) / self.dt;
+                let nearest = samples.round();
+                let k = if (samples - nearest).abs() <= Float::EPSILON.sqrt() * nearest.max(1.0) {
+                    nearest - 1.0
+                } else {
+                    samples.floor()
+                };
+                let k = (k.max(0.0) as usize).min(N - 1);
+                &solution.inputs[k] + &solution.gains[k] * (x - &solution.states[k])
+            },
+            None => self.inputs[0].clone(),
+        }
+
+    }
+
+    /// Optimizes the plan from the sampled state, warm started from the previous plan
+    fn update(&mut self, t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>, _dt: Float) -> Result<(), ControlError> {
+
+        if self.solution.is_some() && !self.options.receding_horizon {
+            return Ok(());
+        }
+
+        // Shift the previous plan to the current sample
+        if let Some(solution) = &self.solution {
+            let shift = (((t - self.time) / self.dt).round().max(0.0) as usize).min(solution.inputs.len());
+            let last = solution.inputs[solution.inputs.len() - 1].clone();
+            self.inputs = solution.inputs[shift..].iter().cloned()
+                .chain(std::iter::repeat_n(last, shift))
+                .collect();
+        }
+
+        let solution = self.solve(x, reference, &self.inputs)?;
+        self.inputs = solution.inputs.clone();
+        self.solution = Some(solution);
+        self.time = t;
+
+        Ok(())
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamics::models::{DoubleIntegrator1D, NonlinearInvertedPendulum};
+    use crate::float::consts::PI;
+
+    #[test]
+    fn test_IterativeLinearQuadraticRegulator_linear() {
+
+        // Linear dynamics with a quadratic cost converge in a single Newton step
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![0.1]);
+        let Qf = DMatrix::<Float>::identity(2, 2) * 10.0;
+        let inputs = vec![DVector::<Float>::zeros(1); 50];
+        let ilqr = IterativeLinearQuadraticRegulator::new(DoubleIntegrator1D::new(), 0.1, Q, R, Qf, inputs.clone(), ILQROptions::default());
+
+        let x0 = DVector::from_vec(vec![1.0, 0.0]);
+        let solution = ilqr.solve(&x0, None, &inputs).unwrap();
+
+        assert!(solution.iterations <= 3);
+        assert!(solution.converged);
+        assert_eq!(solution.states.len(), 51);
+        assert_eq!(solution.gains[0].shape(), (1, 2));
+        assert!(solution.states[50].amax() < 0.1);
+
+        // The solution satisfies the dynamics and a perturbed input sequence costs more
+        let states = ilqr.rollout(&x0, &solution.inputs);
+        assert_relative_eq!(states[50], solution.states[50], epsilon = 1E-5);
+        let mut perturbed = solution.inputs.clone();
+        perturbed[10][0] += 0.1;
+        let reference = DVector::<Float>::zeros(2);
+        assert!(ilqr.cost(&ilqr.rollout(&x0, &perturbed), &perturbed, &reference) > solution.cost);
+
+        // Sample k covers (t_k, t_k+1], so the input switches just after each sample time
+        let mut ilqr = ilqr;
+        ilqr.update(0.0, &x0, None, 0.1).unwrap();
+        let plan = ilqr.solution.clone().unwrap();
+        let x = &plan.states[1];
+        assert_eq!(ilqr.control(0.0, &plan.states[0], None), plan.inputs[0]);
+        assert_eq!(ilqr.control(0.1, x, None), &plan.inputs[0] + &plan.gains[0] * (x - &plan.states[0]));
+        assert_eq!(ilqr.control(0.15, x, None), plan.inputs[1]);
+        assert_eq!(ilqr.control(0.2, x, None), plan.inputs[1]);
+
+    }
+
+    #[test]
+    fn test_IterativeLinearQuadraticRegulator_max_iterations() {
+
+        // A single iteration cannot swing the pendulum up
+        let Q = DMatrix::from_diagonal(&DVector::from_vec(vec![1.0, 0.1, 0.1, 0.1]));
+        let R = DMatrix::from_vec(1, 1, vec![1E-3]);
+        let Qf = DMatrix::from_diagonal(&DVector::from_vec(vec![1000.0, 100.0, 10.0, 10.0]));
+        let inputs = vec![DVector::<Float>::zeros(1); 80];
+        let options = ILQROptions { max_iterations: 1, ..ILQROptions::default() };
+        let ilqr = IterativeLinearQuadraticRegulator::new(NonlinearInvertedPendulum::new(), 0.1, Q, R, Qf, inputs.clone(), options);
+
+        let x0 = DVector::from_vec(vec![PI, 0.0, 0.0, 0.0]);
+        let solution = ilqr.solve(&x0, None, &inputs).unwrap();
+
+        assert_eq!(solution.iterations, 1);
+        assert!(!solution.converged);
+
+    }
+
+    #[test]
+    fn test_IterativeLinearQuadraticRegulator_swing_up() {
+
+        // Swing the pendulum up from hanging, theta = pi, to inverted, theta = 0
+        let Q = DMatrix::from_diagonal(&DVector::from_vec(vec![1.0, 0.1, 0.1, 0.1]));
+        let R = DMatrix::from_vec(1, 1, vec![1E-3]);
+        let Qf = DMatrix::from_diagonal(&DVector::from_vec(vec![1000.0, 100.0, 10.0, 10.0]));
+        let inputs = vec![DVector::<Float>::zeros(1); 80];
+        let options = ILQROptions { max_iterations: 200, ..ILQROptions::default() };
+        let ilqr = IterativeLinearQuadraticRegulator::new(NonlinearInvertedPendulum::new(), 0.1, Q, R, Qf, inputs.clone(), options);
+
+        let x0 = DVector::from_vec(vec![PI, 0.0, 0.0, 0.0]);
+        let solution = ilqr.solve(&x0, None, &inputs).unwrap();
+
+        let xf = &solution.states[80];
+        assert!(xf[0].abs() < 0.05, "{:?}", xf);
+        assert!(xf[1].abs() < 0.05, "{:?}", xf);
+        assert!(solution.converged);
+
+    }
+}
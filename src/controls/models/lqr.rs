@@ -1,27 +1,57 @@
 use na::{DMatrix, DVector};
-use std::fmt;
+use thiserror::Error;
+use crate::controls::controller::Controller;
+use crate::math::integrate::IntegrateError;
+use crate::math::lyapunov::LyapunovError;
+use crate::math::qp::QPError;
 use crate::math::riccati::*;
+use crate::float::Float;
 
-#[derive(Clone, Debug)]
-pub struct ControlError;
+/// Failure of a controller to compute its gains or input, carrying the underlying solver error
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ControlError {
+    #[error("Improper argument: {0}")]
+    ArgError(String),
 
-impl fmt::Display for ControlError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Control error")
-    }
+    #[error("Riccati equation failed: {0}")]
+    RiccatiError(#[from] RiccatiError),
+
+    #[error("Quadratic program failed: {0}")]
+    QPError(#[from] QPError),
+
+    #[error("Lyapunov equation failed: {0}")]
+    LyapunovError(#[from] LyapunovError),
+
+    #[error("Integration failed: {0}")]
+    IntegrationError(#[source] Box<IntegrateError>),
+
+    #[error("Singular matrix: {0}")]
+    SingularMatrixError(String),
+
+    #[error("System is not controllable")]
+    NotControllableError,
+
+    #[error("Non-finite {0}")]
+    NonFiniteError(String),
 }
 
 /// Continuous Infinite-Horizon Linear Quadratic Regulator
+///
+/// The gain and Riccati solution are computed on construction and recomputed when the model or
+/// weights are changed
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinearQuadraticRegulator {
-    A: DMatrix<f32>,
-    B: DMatrix<f32>,
-    R: DMatrix<f32>,
-    Q: DMatrix<f32>,
+    A: DMatrix<Float>,
+    B: DMatrix<Float>,
+    R: DMatrix<Float>,
+    Q: DMatrix<Float>,
+    K: DMatrix<Float>,
+    P: DMatrix<Float>,
 }
 
 impl LinearQuadraticRegulator {
-    pub fn new(A: DMatrix<f32>, B: DMatrix<f32>, Q: DMatrix<f32>, R: DMatrix<f32>) -> Self {
+    /// Fails if the Riccati equation has no stabilizing solution
+    pub fn new(A: DMatrix<Float>, B: DMatrix<Float>, Q: DMatrix<Float>, R: DMatrix<Float>) -> Result<Self, ControlError> {
         // TODO assert correct sizes
         assert_eq!(A.shape().0, A.shape().1);
         assert_eq!(A.shape().0, Q.shape().0);
@@ -29,32 +59,48 @@ impl LinearQuadraticRegulator {
         assert_eq!(B.shape().0, A.shape().0);
         assert_eq!(B.shape().1, R.shape().0);
 
-        Self { A, B, Q, R }
+        let (K, P) = solve_riccati(&A, &B, &Q, &R)?;
+
+        Ok(Self { A, B, Q, R, K, P })
     }
 
-    /// Returns LQR gain and solution to the Continuous Algebraic Riccati Equation
-    pub fn solve(&self) -> Result<(DMatrix<f32>, DMatrix<f32>), ControlError> {
-        let K: DMatrix<f32>;
-        let P: DMatrix<f32>;
+    /// Replaces the linear model and its solution, keeping the previous model on failure
+    pub fn set_model(&mut self, A: DMatrix<Float>, B: DMatrix<Float>) -> Result<(), ControlError> {
+        assert_eq!(A.shape(), self.A.shape());
+        assert_eq!(B.shape(), self.B.shape());
+
+        (self.K, self.P) = solve_riccati(&A, &B, &self.Q, &self.R)?;
+        self.A = A;
+        self.B = B;
 
-        P = match solve_continuous_riccati_eigen(&self.A, &self.B, &self.Q, &self.R) {
-            Ok(result) => result,
-            Err(LinAlgError) => return Err(ControlError),
-        };
+        Ok(())
+    }
 
-        if let Some(Rinv) = &self.R.clone().try_inverse() {
-            K = Rinv * &self.B.transpose() * &P;
+    /// Replaces the state and input weights and the solution, keeping the previous weights on failure
+    pub fn set_weights(&mut self, Q: DMatrix<Float>, R: DMatrix<Float>) -> Result<(), ControlError> {
+        assert_eq!(Q.shape(), self.Q.shape());
+        assert_eq!(R.shape(), self.R.shape());
+
+        (self.K, self.P) = solve_riccati(&self.A, &self.B, &Q, &R)?;
+        self.Q = Q;
+        self.R = R;
+
+        Ok(())
+    }
+
+    /// Returns LQR gain and solution to the Continuous Algebraic Riccati Equation
+    pub fn solve(&self) -> (DMatrix<Float>, DMatrix<Float>) {
+
+        (self.K.clone(), self.P.clone())
 
-            Ok((K, P))
-        } else {
-            return Err(ControlError);
-        }
     }
 
+    /// Returns the LQR gain
+    pub fn gain(&self) -> &DMatrix<Float> { &self.K }
 
     /// Returns the Cost-to-go for the LQR
     /// V = x^TQx + u^TRu
-    pub fn cost_to_go(&self, x: &DVector<f32>, u: &DVector<f32>) -> f32 {
+    pub fn cost_to_go(&self, x: &DVector<Float>, u: &DVector<Float>) -> Float {
 
         let xTQx = x.transpose()*(&self.Q*x);
         let uTRu = u.transpose()*(&self.R*u);
@@ -67,6 +113,36 @@ impl LinearQuadraticRegulator {
 
 }
 
+// Solves the Continuous Algebraic Riccati Equation, returning the gain and solution
+fn solve_riccati(A: &DMatrix<Float>, B: &DMatrix<Float>, Q: &DMatrix<Float>, R: &DMatrix<Float>) -> Result<(DMatrix<Float>, DMatrix<Float>), ControlError> {
+    let K: DMatrix<Float>;
+    let P: DMatrix<Float>;
+
+    P = solve_continuous_riccati_eigen(A, B, Q, R)?;
+
+    if let Some(Rinv) = &R.clone().try_inverse() {
+        K = Rinv * &B.transpose() * &P;
+
+        Ok((K, P))
+    } else {
+        Err(ControlError::RiccatiError(RiccatiError::SingularWeightError))
+    }
+}
+
+impl Controller for LinearQuadraticRegulator {
+
+    /// u = -K(x - x_ref)
+    fn control(&self, _t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        match reference {
+            Some(reference) => -&self.K * (x - reference),
+            None => -&self.K * x,
+        }
+
+    }
+
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,19 +154,16 @@ mod tests {
 
         let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
 
-        let Q = DMatrix::<f32>::identity(2, 2);
+        let Q = DMatrix::<Float>::identity(2, 2);
         let R = DMatrix::from_vec(1, 1, vec![1.]);
 
-        let controller = LinearQuadraticRegulator::new(A, B, Q, R);
+        let controller = LinearQuadraticRegulator::new(A, B, Q, R).unwrap();
 
-        let (K, _P) = match controller.solve() {
-            Ok((value1, value2)) => (value1, value2),
-            _ => (DMatrix::<f32>::zeros(2, 2), DMatrix::<f32>::zeros(2, 2)),
-        };
+        let (K, _P) = controller.solve();
 
-        let _P_true = DMatrix::from_row_slice(2, 2, &[3.0_f32.sqrt(), 1., 1., 3.0_f32.sqrt()]);
+        let _P_true = DMatrix::from_row_slice(2, 2, &[(3.0 as Float).sqrt(), 1., 1., (3.0 as Float).sqrt()]);
 
-        let K_true = DMatrix::from_row_slice(1, 2, &[1., 3.0_f32.sqrt()]);
+        let K_true = DMatrix::from_row_slice(1, 2, &[1., (3.0 as Float).sqrt()]);
 
     //     println!("P: {:?}", P);
     //     println!("P_true: {:?}", P_true);
@@ -107,26 +180,50 @@ mod tests {
 
         let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
 
-        let Q = DMatrix::<f32>::identity(2, 2);
+        let Q = DMatrix::<Float>::identity(2, 2);
         let R = DMatrix::from_vec(1, 1, vec![1.]);
 
-        let controller = LinearQuadraticRegulator::new(A, B, Q, R);
+        let controller = LinearQuadraticRegulator::new(A, B, Q, R).unwrap();
 
         // Solve the LQR controller
-        let (K, _P) = match controller.solve() {
-            Ok((value1, value2)) => (value1, value2),
-            Err(_) => (DMatrix::<f32>::zeros(1, 1), DMatrix::<f32>::zeros(1, 1)),
-        };
+        let (K, _P) = controller.solve();
 
         let x = DVector::from_vec(vec![10., 10.]);
         let u = -&K * &x;
 
         let result = controller.cost_to_go(&x, &u);
-        let correct = 946.41016;
+        let correct = 946.4101615137769;
         // println!("{:?}", result);
 
         // let _ = relative_eq!(result, correct);
         assert_relative_eq!(result, correct);
 
     }
+
+    #[test]
+    fn test_LinearQuadraticRegulator_cached_solution() {
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        let mut controller = LinearQuadraticRegulator::new(A, B, Q.clone(), R.clone()).unwrap();
+        let (K, _P) = controller.solve();
+        assert_eq!(controller.gain(), &K);
+
+        // Changing the weights replaces the gain
+        // With Q = I and R = r, K = [1/sqrt(r), sqrt(2 sqrt(r) + 1)/sqrt(r)]
+        controller.set_weights(Q.clone(), DMatrix::from_vec(1, 1, vec![4.])).unwrap();
+        let K_true = DMatrix::from_row_slice(1, 2, &[0.5, (5.0 as Float).sqrt() / 2.0]);
+        assert_relative_eq!(controller.gain(), &K_true, epsilon = 1E-3);
+
+        // A failed solve is an error, keeping the previous model and gain
+        let unstabilizable = controller.set_model(DMatrix::<Float>::identity(2, 2), DMatrix::<Float>::zeros(2, 1));
+        assert!(matches!(unstabilizable, Err(ControlError::RiccatiError(RiccatiError::NotStabilizableError))));
+        assert_relative_eq!(controller.gain(), &K_true, epsilon = 1E-3);
+        assert!(matches!(
+            LinearQuadraticRegulator::new(DMatrix::<Float>::identity(2, 2), DMatrix::<Float>::zeros(2, 1), Q, R),
+            Err(ControlError::RiccatiError(RiccatiError::NotStabilizableError))
+        ));
+    }
 }
@@ -1,3 +1,17 @@
 mod lqr;
+mod dlqr;
+mod finite_lqr;
+mod pid;
+mod mpc;
+mod ilqr;
+mod gain_schedule;
+mod pole_placement;
 
-pub use self::lqr::LinearQuadraticRegulator;
+pub use self::lqr::{LinearQuadraticRegulator, ControlError};
+pub use self::dlqr::DiscreteLinearQuadraticRegulator;
+pub use self::finite_lqr::FiniteHorizonLinearQuadraticRegulator;
+pub use self::pid::{PIDController, PIDOptions, AntiWindup};
+pub use self::mpc::{ModelPredictiveController, MPCOptions};
+pub use self::ilqr::{IterativeLinearQuadraticRegulator, ILQROptions, ILQRSolution};
+pub use self::gain_schedule::{GainScheduledController, OperatingPoint, Interpolation};
+pub use self::pole_placement::{PolePlacementController, PolePlacementMethod, place_poles};
@@ -0,0 +1,334 @@
+use na::{DMatrix, DVector};
+use crate::controls::models::lqr::ControlError;
+use crate::controls::controller::Controller;
+use crate::dynamics::linear_system::DiscreteLTISystem;
+use crate::math::qp::{QuadraticProgram, QPOptions};
+use crate::math::riccati::solve_discrete_riccati_eigen;
+use crate::float::Float;
+
+/// Defines options for the model predictive controller
+///
+/// terminal: terminal state weight, the solution to the Discrete Algebraic Riccati Equation if None \
+/// input_limits: lower and upper bounds of the input at each sample \
+/// state_limits: lower and upper bounds of the predicted state at each sample \
+/// qp: options of the quadratic program solver \
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MPCOptions {
+    pub terminal: Option<DMatrix<Float>>,
+    pub input_limits: Option<(DVector<Float>, DVector<Float>)>,
+    pub state_limits: Option<(DVector<Float>, DVector<Float>)>,
+    pub qp: QPOptions,
+}
+
+/// Linear Model Predictive Controller
+///
+/// Minimizes (x[N] - r)^TP(x[N] - r) + the sum of (x[k] - r)^TQ(x[k] - r) + u[k]^TRu[k] over a
+/// receding horizon of N samples of a discrete linear model, subject to box constraints on the
+/// inputs and predicted states. The states are eliminated so each sample solves a dense QP in the
+/// input sequence, and the first input is held until the next sample.
+///
+/// The controller is sampled once per engine step, which must match the model sample period.
+/// Each sample is warm started from the previous plan and multipliers, shifted by one sample.
+///
+/// system: discrete linear model used for prediction \
+/// horizon: number of samples N in the prediction horizon \
+/// plan: optimal input sequence [u[0], ..., u[N-1]] from the last sample \
+/// input: input u[0] held until the next sample \
+#[derive(Debug, Clone)]
+pub struct ModelPredictiveController {
+    pub system: DiscreteLTISystem,
+    pub horizon: usize,
+    pub plan: DVector<Float>,
+    pub input: DVector<Float>,
+    options: MPCOptions,
+    prediction: DMatrix<Float>,
+    convolution: DMatrix<Float>,
+    weights: DMatrix<Float>,
+    qp: QuadraticProgram,
+    multipliers: DVector<Float>,
+}
+
+impl ModelPredictiveController {
+
+    pub fn new(
+        system: DiscreteLTISystem,
+        horizon: usize,
+        Q: DMatrix<Float>,
+        R: DMatrix<Float>,
+        options: MPCOptions
+    ) -> Result<Self, ControlError> {
+        let (n, m) = (system.dx, system.du);
+        assert!(horizon > 0);
+        assert_eq!(Q.shape(), (n, n));
+        assert_eq!(R.shape(), (m, m));
+        if let Some((lower, upper)) = &options.input_limits {
+            assert_eq!(lower.len(), m);
+            assert_eq!(upper.len(), m);
+        }
+        if let Some((lower, upper)) = &options.state_limits {
+            assert_eq!(lower.len(), n);
+            assert_eq!(upper.len(), n);
+        }
+
+        let P = match &options.terminal {
+            Some(P) => {
+                assert_eq!(P.shape(), (n, n));
+                P.clone()
+            },
+            None => solve_discrete_riccati_eigen(&system.A, &system.B, &Q, &R)?,
+        };
+
+        // Predicted states [x[1], ..., x[N]] = prediction x[0] + convolution [u[0], ..., u[N-1]]
+        let mut prediction = DMatrix::<Float>::zeros(horizon * n, n);
+        let mut convolution = DMatrix::<Float>::zeros(horizon * n, horizon * m);
+        let mut Ak = DMatrix::<Float>::identity(n, n);
+        for k in 0..horizon {
+            let AkB = &Ak * &system.B;
+            for j in 0..(horizon - k) {
+                convolution.slice_mut(((k + j) * n, j * m), (n, m)).copy_from(&AkB);
+            }
+            Ak = &system.A * Ak;
+            prediction.slice_mut((k * n, 0), (n, n)).copy_from(&Ak);
+        }
+
+        // Halved cost in the input sequence, 1/2 U^THU + f^TU
+        let mut weights = DMatrix::<Float>::zeros(horizon * n, horizon * n);
+        let mut H = DMatrix::<Float>::zeros(horizon * m, horizon * m);
+        for k in 0..horizon {
+            let weight = if k + 1 == horizon { &P } else { &Q };
+            weights.slice_mut((k * n, k * n), (n, n)).copy_from(weight);
+            H.slice_mut((k * m, k * m), (m, m)).copy_from(&R);
+        }
+        H += convolution.transpose() * &weights * &convolution;
+
+        // Constraints on the inputs, then on the predicted states
+        let mut rows: Vec<DMatrix<Float>> = Vec::new();
+        if options.input_limits.is_some() {
+            rows.push(DMatrix::<Float>::identity(horizon * m, horizon * m));
+        }
+        if options.state_limits.is_some() {
+            rows.push(convolution.clone());
+        }
+        let constraints = rows.iter().map(|G| G.shape().0).sum();
+        let mut G = DMatrix::<Float>::zeros(constraints, horizon * m);
+        let mut row = 0;
+        for block in rows.iter() {
+            G.slice_mut((row, 0), block.shape()).copy_from(block);
+            row += block.shape().0;
+        }
+
+        let qp = QuadraticProgram::new(H, G, options.qp.clone())?;
+
+        let plan = DVector::<Float>::zeros(horizon * m);
+        let input = DVector::<Float>::zeros(m);
+        let multipliers = DVector::<Float>::zeros(constraints);
+
+        Ok(Self { system, horizon, plan, input, options, prediction, convolution, weights, qp, multipliers })
+    }
+
+    /// Solves for the optimal input sequence from state x towards the reference state
+    /// Returns the first input of the sequence, failing if x or the reference is not the size of
+    /// the state
+    pub fn solve(&mut self, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> Result<DVector<Float>, ControlError> {
+
+        let (n, m) = (self.system.dx, self.system.du);
+        let N = self.horizon;
+
+        if x.len() != n {
+            return Err(ControlError::ArgError(format!("state has {} components, the model has {} states", x.len(), n)));
+        }
+        if let Some(reference) = reference {
+            if reference.len() != n {
+                return Err(ControlError::ArgError(format!("reference has {} components, the model has {} states", reference.len(), n)));
+            }
+        }
+
+        let free = &self.prediction * x;
+        let error = match reference {
+            Some(reference) => &free - DVector::from_iterator(N * n, reference.as_slice().iter().cycle().take(N * n).cloned()),
+            None => free.clone(),
+        };
+        let f = self.convolution.transpose() * (&self.weights * error);
+
+        // Stack the bounds in the same order as the constraint rows
+        let mut lower: Vec<Float> = Vec::new();
+        let mut upper: Vec<Float> = Vec::new();
+        if let Some((u_min, u_max)) = &self.options.input_limits {
+            lower.extend(u_min.as_slice().iter().cycle().take(N * m));
+            upper.extend(u_max.as_slice().iter().cycle().take(N * m));
+        }
+        if let Some((x_min, x_max)) = &self.options.state_limits {
+            lower.extend(x_min.as_slice().iter().cycle().zip(free.iter()).map(|(bound, x)| bound - x));
+            upper.extend(x_max.as_slice().iter().cycle().zip(free.iter()).map(|(bound, x)| bound - x));
+        }
+        let lower = DVector::from_vec(lower);
+        let upper = DVector::from_vec(upper);
+
+        let solution = self.qp.solve(&f, &lower, &upper, Some((&self.plan, &self.multipliers)))?;
+
+        self.plan = solution.x;
+        self.multipliers = solution.y;
+        self.input = self.plan.rows(0, m).clone_owned();
+
+        Ok(self.input.clone())
+
+    }
+
+    // Advances the warm start by one sample, repeating the last input and multipliers
+    fn shift_plan(&mut self) {
+
+        let (n, m) = (self.system.dx, self.system.du);
+        let N = self.horizon;
+
+        self.plan = shift(&self.plan, m);
+
+        // Multipliers are stacked as the input then the state constraints, each ordered by sample
+        let mut multipliers: Vec<Float> = Vec::with_capacity(self.multipliers.len());
+        let mut row = 0;
+        if self.options.input_limits.is_some() {
+            multipliers.extend(shift(&self.multipliers.rows(row, N * m).clone_owned(), m).iter());
+            row += N * m;
+        }
+        if self.options.state_limits.is_some() {
+            multipliers.extend(shift(&self.multipliers.rows(row, N * n).clone_owned(), n).iter());
+        }
+        self.multipliers = DVector::from_vec(multipliers);
+
+    }
+
+    /// Returns the states [x[1], ..., x[N]] predicted from x by the current plan
+    pub fn predicted_states(&self, x: &DVector<Float>) -> Vec<DVector<Float>> {
+
+        let n = self.system.dx;
+        let states = &self.prediction * x + &self.convolution * &self.plan;
+
+        (0..self.horizon).map(|k| states.rows(k * n, n).clone_owned()).collect()
+
+    }
+
+}
+
+impl Controller for ModelPredictiveController {
+
+    /// Holds the first input of the plan computed at the last sample
+    fn control(&self, _t: Float, _x: &DVector<Float>, _reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        self.input.clone()
+
+    }
+
+    /// Re-plans over the horizon from the sampled state, failing if the controller is not sampled
+    /// at the model sample period
+    fn update(&mut self, _t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>, dt: Float) -> Result<(), ControlError> {
+
+        if (dt - self.system.dt).abs() > 1E-6 * self.system.dt {
+            return Err(ControlError::ArgError(format!("sample period {} does not match the model sample period {}", dt, self.system.dt)));
+        }
+
+        self.shift_plan();
+        self.solve(x, reference).map(|_| ())
+
+    }
+
+}
+
+// Drops the first block of a sequence stacked in blocks of the given width, repeating the last block
+fn shift(sequence: &DVector<Float>, width: usize) -> DVector<Float> {
+
+    let len = sequence.len();
+    if len <= width {
+        return sequence.clone();
+    }
+
+    DVector::from_iterator(len, sequence.iter().skip(width).chain(sequence.rows(len - width, width).iter()).cloned())
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::models::DiscreteLinearQuadraticRegulator;
+
+    // Double integrator sampled with a zero-order hold
+    fn sampled_double_integrator(dt: Float) -> DiscreteLTISystem {
+        DiscreteLTISystem::new(
+            DMatrix::from_row_slice(2, 2, &[1., dt, 0., 1.]),
+            DMatrix::from_row_slice(2, 1, &[0.5*dt*dt, dt]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::zeros(2, 1),
+            dt
+        )
+    }
+
+    #[test]
+    fn test_ModelPredictiveController_unconstrained() {
+
+        // With the DARE terminal weight and no active constraints, MPC recovers the discrete LQR
+        let system = sampled_double_integrator(0.1);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        let mut controller = ModelPredictiveController::new(system.clone(), 10, Q.clone(), R.clone(), MPCOptions::default()).unwrap();
+        let lqr = DiscreteLinearQuadraticRegulator::new(system.A, system.B, Q, R, None);
+        let K = lqr.gain().unwrap();
+
+        let x = DVector::from_vec(vec![1.0, -0.5]);
+        let u = controller.solve(&x, None).unwrap();
+        assert_relative_eq!(u, -K * &x, epsilon = 1E-3);
+
+        // Tracking a reference shifts the regulated state
+        let reference = DVector::from_vec(vec![2.0, 0.0]);
+        let u = controller.solve(&x, Some(&reference)).unwrap();
+        assert_relative_eq!(u, -K * (&x - &reference), epsilon = 1E-3);
+
+        // States and references must be the size of the model state
+        let short = DVector::from_vec(vec![2.0]);
+        assert!(matches!(controller.solve(&x, Some(&short)), Err(ControlError::ArgError(_))));
+        assert!(matches!(controller.solve(&short, None), Err(ControlError::ArgError(_))));
+
+    }
+
+    #[test]
+    fn test_ModelPredictiveController_constrained() {
+
+        let system = sampled_double_integrator(0.1);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![0.01]);
+        let options = MPCOptions {
+            input_limits: Some((DVector::from_vec(vec![-0.5]), DVector::from_vec(vec![0.5]))),
+            state_limits: Some((DVector::from_vec(vec![-10.0, -1.0]), DVector::from_vec(vec![10.0, 1.0]))),
+            ..MPCOptions::default()
+        };
+        let mut controller = ModelPredictiveController::new(system, 20, Q, R, options).unwrap();
+
+        let x = DVector::from_vec(vec![-5.0, 0.9]);
+        let u = controller.solve(&x, None).unwrap();
+
+        // Accelerating towards the origin is limited by the thrust and speed limits
+        assert!(u[0] <= 0.5 + 1E-3);
+        assert!(controller.plan.iter().all(|u| u.abs() <= 0.5 + 1E-3));
+        assert!(controller.predicted_states(&x).iter().all(|x| x[1].abs() <= 1.0 + 1E-3));
+        assert!(controller.plan.iter().any(|u| u.abs() > 0.5 - 1E-3));
+
+        // The warm start advances the plan by one sample
+        let plan = controller.plan.clone();
+        controller.shift_plan();
+        assert_eq!(controller.plan.rows(0, 19), plan.rows(1, 19));
+        assert_eq!(controller.plan[19], plan[19]);
+
+    }
+
+    #[test]
+    fn test_ModelPredictiveController_sample_period() {
+
+        let system = sampled_double_integrator(0.1);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+        let mut controller = ModelPredictiveController::new(system, 10, Q, R, MPCOptions::default()).unwrap();
+
+        let x = DVector::from_vec(vec![1.0, -0.5]);
+        assert!(controller.update(0.0, &x, None, 0.1).is_ok());
+        assert!(matches!(controller.update(0.1, &x, None, 0.05), Err(ControlError::ArgError(_))));
+
+    }
+}
@@ -0,0 +1,261 @@
+use na::{DMatrix, DVector};
+use crate::controls::models::lqr::ControlError;
+use crate::controls::controller::Controller;
+use crate::float::Float;
+
+/// Integrator anti-windup schemes applied while the output is saturated
+///
+/// None: the integrator always accumulates the error \
+/// Clamping: the integrator holds whenever accumulating would drive the output further into saturation \
+/// BackCalculation(kb): the integrator is bled by kb (u_sat - u) \
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiWindup {
+    None,
+    Clamping,
+    BackCalculation(Float),
+}
+
+/// Defines options for the PID controller
+///
+/// filter: bandwidth N of the first order derivative filter, in rad/s \
+/// limits: lower and upper saturation limits of each channel output \
+/// anti_windup: integrator anti-windup scheme \
+#[derive(Debug, Clone, PartialEq)]
+pub struct PIDOptions {
+    pub filter: Float,
+    pub limits: Option<(DVector<Float>, DVector<Float>)>,
+    pub anti_windup: AntiWindup,
+}
+
+impl Default for PIDOptions {
+    fn default() -> Self {
+        Self {
+            filter: 10.0,
+            limits: None,
+            anti_windup: AntiWindup::Clamping,
+        }
+    }
+}
+
+/// Multi-channel Proportional-Integral-Derivative controller
+///
+/// Channel i drives input u_i from the error e_i = r_i - y_i of the measurement y = Cx
+/// u = sat(Kp e + Ki integral(e) + Kd N (-y - w)), w_dot = N (-y - w)
+///
+/// The derivative acts on the filtered measurement so setpoint changes do not kick the output.
+/// The integrator and filter states are advanced once per engine step, so the filter bandwidth
+/// should be comparable to or below the engine rate.
+///
+/// kp: proportional gain of each channel \
+/// ki: integral gain of each channel \
+/// kd: derivative gain of each channel \
+/// measurement: matrix C selecting the measured output of each channel \
+/// options: derivative filter, saturation and anti-windup \
+/// integral: integral term Ki integral(e) of each channel \
+/// filter_state: derivative filter state w of each channel \
+#[derive(Debug, Clone, PartialEq)]
+pub struct PIDController {
+    pub kp: DVector<Float>,
+    pub ki: DVector<Float>,
+    pub kd: DVector<Float>,
+    pub measurement: DMatrix<Float>,
+    pub options: PIDOptions,
+    pub integral: DVector<Float>,
+    pub filter_state: DVector<Float>,
+    time: Option<Float>,
+}
+
+impl PIDController {
+
+    pub fn new(
+        kp: DVector<Float>,
+        ki: DVector<Float>,
+        kd: DVector<Float>,
+        measurement: DMatrix<Float>,
+        options: PIDOptions
+    ) -> Self {
+        let channels = measurement.shape().0;
+        assert_eq!(kp.len(), channels);
+        assert_eq!(ki.len(), channels);
+        assert_eq!(kd.len(), channels);
+        assert!(options.filter > 0.0);
+        if let Some((lower, upper)) = &options.limits {
+            assert_eq!(lower.len(), channels);
+            assert_eq!(upper.len(), channels);
+            assert!(lower.iter().zip(upper.iter()).all(|(lower, upper)| lower <= upper));
+        }
+
+        let integral = DVector::<Float>::zeros(channels);
+        let filter_state = DVector::<Float>::zeros(channels);
+
+        Self { kp, ki, kd, measurement, options, integral, filter_state, time: None }
+    }
+
+    /// Clears the integrator and derivative filter
+    pub fn reset(&mut self) {
+
+        self.integral.fill(0.0);
+        self.filter_state.fill(0.0);
+        self.time = None;
+
+    }
+
+    /// Returns the output before saturation
+    pub fn unsaturated(&self, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        let y = &self.measurement * x;
+        let error = match reference {
+            Some(reference) => &self.measurement * reference - &y,
+            None => -&y,
+        };
+        let derivative = (-&y - &self.filter_state) * self.options.filter;
+
+        self.kp.component_mul(&error) + &self.integral + self.kd.component_mul(&derivative)
+
+    }
+
+    /// Clamps the output to the saturation limits
+    pub fn saturate(&self, u: &DVector<Float>) -> DVector<Float> {
+
+        match &self.options.limits {
+            Some((lower, upper)) => u.zip_zip_map(lower, upper, |u, lower, upper| u.clamp(lower, upper)),
+            None => u.clone(),
+        }
+
+    }
+
+}
+
+impl Controller for PIDController {
+
+    fn control(&self, _t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        self.saturate(&self.unsaturated(x, reference))
+
+    }
+
+    /// Advances the integrator and derivative filter to time t from the sampled measurement
+    fn update(&mut self, t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>, _dt: Float) -> Result<(), ControlError> {
+
+        let y = &self.measurement * x;
+
+        // Start the filter at the current measurement so the first output has no derivative kick
+        let h = match self.time {
+            Some(time) => t - time,
+            None => {
+                self.filter_state = -&y;
+                self.time = Some(t);
+                return Ok(());
+            }
+        };
+
+        let error = match reference {
+            Some(reference) => &self.measurement * reference - &y,
+            None => -&y,
+        };
+        let u = self.unsaturated(x, reference);
+        let u_sat = self.saturate(&u);
+
+        for i in 0..self.integral.len() {
+            let rate = self.ki[i] * error[i];
+            let rate = match self.options.anti_windup {
+                AntiWindup::None => rate,
+                AntiWindup::Clamping if u[i] != u_sat[i] && rate * (u[i] - u_sat[i]) > 0.0 => 0.0,
+                AntiWindup::Clamping => rate,
+                AntiWindup::BackCalculation(kb) => rate + kb * (u_sat[i] - u[i]),
+            };
+            self.integral[i] += rate * h;
+        }
+
+        // Exact update of the first order filter with the measurement held over the interval
+        let decay = (-self.options.filter * h).exp();
+        self.filter_state = -&y + (&self.filter_state + &y) * decay;
+        self.time = Some(t);
+
+        if self.integral.iter().chain(self.filter_state.iter()).all(|value| value.is_finite()) {
+            Ok(())
+        } else {
+            Err(ControlError::NonFiniteError("integral or derivative filter state".to_string()))
+        }
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Single channel measuring the first of two states
+    fn channel(kp: Float, ki: Float, kd: Float, options: PIDOptions) -> PIDController {
+        PIDController::new(
+            DVector::from_vec(vec![kp]),
+            DVector::from_vec(vec![ki]),
+            DVector::from_vec(vec![kd]),
+            DMatrix::from_row_slice(1, 2, &[1., 0.]),
+            options
+        )
+    }
+
+    #[test]
+    fn test_PIDController_proportional_saturation() {
+
+        let limits = Some((DVector::from_vec(vec![-1.0]), DVector::from_vec(vec![1.0])));
+        let mut controller = channel(2.0, 0.0, 0.0, PIDOptions { limits, ..PIDOptions::default() });
+
+        let x = DVector::from_vec(vec![0.25, 5.0]);
+        let reference = DVector::from_vec(vec![0.5, 0.0]);
+        controller.update(0.0, &x, Some(&reference), 0.1).unwrap();
+
+        assert_relative_eq!(controller.control(0.0, &x, Some(&reference))[0], 0.5);
+        assert_relative_eq!(controller.control(0.0, &x, None)[0], -0.5);
+        assert_relative_eq!(controller.control(0.0, &DVector::from_vec(vec![-3.0, 0.0]), None)[0], 1.0);
+
+    }
+
+    #[test]
+    fn test_PIDController_anti_windup() {
+
+        // Constant error of 1 with the output saturated at 1
+        let limits = Some((DVector::from_vec(vec![-1.0]), DVector::from_vec(vec![1.0])));
+        let x = DVector::from_vec(vec![-1.0, 0.0]);
+
+        let run = |anti_windup: AntiWindup| {
+            let options = PIDOptions { limits: limits.clone(), anti_windup, ..PIDOptions::default() };
+            let mut controller = channel(2.0, 1.0, 0.0, options);
+            for k in 0..=100 {
+                controller.update(k as Float * 0.1, &x, None, 0.1).unwrap();
+            }
+            controller.integral[0]
+        };
+
+        // Unbounded growth without anti-windup
+        assert_relative_eq!(run(AntiWindup::None), 10.0, epsilon = 1E-4);
+        // Clamping stops integrating once the output is saturated
+        assert_relative_eq!(run(AntiWindup::Clamping), 0.0);
+        // Back-calculation settles where ki e = kb (u - u_sat), i.e. 1 = 2 (2 + I - 1)
+        assert_relative_eq!(run(AntiWindup::BackCalculation(2.0)), -0.5, epsilon = 1E-3);
+
+    }
+
+    #[test]
+    fn test_PIDController_filtered_derivative() {
+
+        // Ramp y = 2t, the filtered derivative of -y settles at -2
+        let mut controller = channel(0.0, 0.0, 1.0, PIDOptions::default());
+
+        for k in 0..=100 {
+            let t = k as Float * 0.05;
+            controller.update(t, &DVector::from_vec(vec![2.0 * t, 0.0]), None, 0.05).unwrap();
+        }
+
+        let x = DVector::from_vec(vec![10.0, 0.0]);
+        let u = controller.control(5.0, &x, None);
+
+        // Driven by the sampled ramp, the filter settles at w = -y + 2h e^-Nh / (1 - e^-Nh)
+        let decay = (-0.5 as Float).exp();
+        assert_relative_eq!(u[0], -2.0 * 0.5 * decay / (1.0 - decay), epsilon = 1E-3);
+        assert!((u[0] + 2.0).abs() < 0.5);
+
+    }
+}
@@ -0,0 +1,504 @@
+use na::{Complex, DMatrix, DVector};
+use crate::controls::models::lqr::ControlError;
+use crate::controls::controller::Controller;
+use crate::dynamics::linear_system::LTISystem;
+use crate::float::Float;
+use crate::float::consts::PI;
+
+/// Methods of computing a state feedback gain placing the closed-loop poles
+///
+/// Ackermann: closed form for single-input systems, poorly conditioned for larger systems \
+/// KautskyNichols: iteratively conditions the closed-loop eigenvectors, for any number of inputs \
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolePlacementMethod {
+    Ackermann,
+    KautskyNichols,
+}
+
+/// Computes the gain K placing the eigenvalues of A - BK at the desired poles
+///
+/// Complex poles must come in conjugate pairs so K is real. Fails if there is not one pole for
+/// each state, if Ackermann's formula is used with more than one input, if the system is not
+/// controllable, or if the Kautsky-Nichols method is used with a pole repeated more times than there
+/// are inputs. Ackermann's formula places repeated poles of single-input systems.
+pub fn place_poles(system: &LTISystem, poles: &[Complex<Float>], method: PolePlacementMethod) -> Result<DMatrix<Float>, ControlError> {
+
+    let poles = validate(system, poles, method)?;
+
+    if !system.is_controllable() {
+        return Err(ControlError::NotControllableError);
+    }
+
+    match method {
+        PolePlacementMethod::Ackermann => ackermann(system, &poles),
+        PolePlacementMethod::KautskyNichols => kautsky_nichols(system, &poles),
+    }
+
+}
+
+/// Pole placement state feedback controller
+///
+/// u = -K(x - x_ref), with the eigenvalues of A - BK at the desired poles
+///
+/// The gain is computed on construction and recomputed when the model or poles are changed
+///
+/// system: linear model the poles are placed for \
+/// poles: desired closed-loop poles \
+/// method: pole placement method \
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolePlacementController {
+    system: LTISystem,
+    poles: Vec<Complex<Float>>,
+    method: PolePlacementMethod,
+    gain: DMatrix<Float>,
+}
+
+impl PolePlacementController {
+    /// Fails if the poles cannot be placed with the method, see place_poles()
+    pub fn new(system: LTISystem, poles: Vec<Complex<Float>>, method: PolePlacementMethod) -> Result<Self, ControlError> {
+        let gain = place_poles(&system, &poles, method)?;
+
+        Ok(Self { system, poles, method, gain })
+    }
+
+    /// Replaces the desired poles and their gain, keeping the previous poles on failure
+    pub fn set_poles(&mut self, poles: Vec<Complex<Float>>) -> Result<(), ControlError> {
+        self.gain = place_poles(&self.system, &poles, self.method)?;
+        self.poles = poles;
+
+        Ok(())
+    }
+
+    /// Replaces the linear model and its gain, keeping the previous model on failure
+    pub fn set_model(&mut self, system: LTISystem) -> Result<(), ControlError> {
+        assert_eq!(system.dx, self.system.dx);
+        assert_eq!(system.du, self.system.du);
+
+        self.gain = place_poles(&system, &self.poles, self.method)?;
+        self.system = system;
+
+        Ok(())
+    }
+
+    pub fn gain(&self) -> &DMatrix<Float> { &self.gain }
+
+    pub fn poles(&self) -> &[Complex<Float>] { &self.poles }
+
+    pub fn system(&self) -> &LTISystem { &self.system }
+}
+
+impl Controller for PolePlacementController {
+
+    /// u = -K(x - x_ref)
+    fn control(&self, _t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+
+        match reference {
+            Some(reference) => -&self.gain * (x - reference),
+            None => -&self.gain * x,
+        }
+
+    }
+
+}
+
+// Largest number of sweeps over the eigenvectors in the Kautsky-Nichols method
+const MAX_ITERATIONS: usize = 100;
+
+// Stop the Kautsky-Nichols sweeps once no eigenvector turns by more than this
+const TOLERANCE: Float = 1E-4;
+
+// Checks the poles and method suit the system, returning the poles ordered in conjugate pairs
+fn validate(system: &LTISystem, poles: &[Complex<Float>], method: PolePlacementMethod) -> Result<Vec<Complex<Float>>, ControlError> {
+
+    if poles.len() != system.dx {
+        return Err(ControlError::ArgError(format!("{} poles given for {} states, one pole must be placed for each state", poles.len(), system.dx)));
+    }
+
+    if method == PolePlacementMethod::Ackermann && system.du != 1 {
+        return Err(ControlError::ArgError(format!("Ackermann's formula requires a single input, the system has {}", system.du)));
+    }
+
+    if poles.iter().any(|pole| !pole.re.is_finite() || !pole.im.is_finite()) {
+        return Err(ControlError::ArgError("poles must be finite".to_string()));
+    }
+
+    // Each closed-loop eigenvector of a repeated pole needs its own input direction
+    if method == PolePlacementMethod::KautskyNichols {
+        let tolerance = pole_tolerance(poles);
+        for pole in poles.iter() {
+            let multiplicity = poles.iter().filter(|other| (*other - pole).norm_sqr().sqrt() < tolerance).count();
+            if multiplicity > system.du {
+                return Err(ControlError::ArgError(format!("pole {} is repeated {} times, more than the {} inputs", pole, multiplicity, system.du)));
+            }
+        }
+    }
+
+    conjugate_pairs(poles)
+
+}
+
+// Distance within which two poles are the same
+fn pole_tolerance(poles: &[Complex<Float>]) -> Float {
+
+    Float::EPSILON.sqrt() * poles.iter().map(|pole| pole.re.hypot(pole.im)).fold(1.0, Float::max)
+
+}
+
+// Orders the poles so each complex pole with a positive imaginary part is followed by its conjugate
+fn conjugate_pairs(poles: &[Complex<Float>]) -> Result<Vec<Complex<Float>>, ControlError> {
+
+    let tolerance = pole_tolerance(poles);
+
+    let mut ordered = Vec::with_capacity(poles.len());
+    let mut lower: Vec<Complex<Float>> = poles.iter().filter(|pole| pole.im < 0.0).cloned().collect();
+
+    for pole in poles.iter().filter(|pole| pole.im >= 0.0) {
+        if pole.im == 0.0 {
+            ordered.push(*pole);
+            continue;
+        }
+
+        let conjugate = lower.iter().position(|other| (other - pole.conj()).norm_sqr().sqrt() < tolerance);
+        match conjugate {
+            Some(index) => {
+                ordered.push(*pole);
+                ordered.push(lower.swap_remove(index));
+            },
+            None => return Err(ControlError::ArgError("complex poles must come in conjugate pairs".to_string())),
+        }
+    }
+
+    if !lower.is_empty() {
+        return Err(ControlError::ArgError("complex poles must come in conjugate pairs".to_string()));
+    }
+
+    Ok(ordered)
+
+}
+
+// Ackermann's formula K = [0 ... 0 1] C^-1 φ(A), with the controllability matrix C and the
+// desired characteristic polynomial φ
+fn ackermann(system: &LTISystem, poles: &[Complex<Float>]) -> Result<DMatrix<Float>, ControlError> {
+
+    let n = system.dx;
+
+    // Coefficients of φ(s) = (s - p_1)...(s - p_n), from the highest power
+    let mut coefficients = vec![Complex::new(1.0, 0.0)];
+    for pole in poles.iter() {
+        let mut next = coefficients.clone();
+        next.push(Complex::new(0.0, 0.0));
+        for (i, coefficient) in coefficients.iter().enumerate() {
+            next[i + 1] -= coefficient * pole;
+        }
+        coefficients = next;
+    }
+
+    // Horner's method, the coefficients are real for conjugate pairs
+    let mut phi = DMatrix::<Float>::zeros(n, n);
+    for coefficient in coefficients.iter() {
+        phi = &system.A * phi + DMatrix::<Float>::identity(n, n) * coefficient.re;
+    }
+
+    // Last row of C^-1, q^T C = e_n^T
+    let mut last = DVector::<Float>::zeros(n);
+    last[n - 1] = 1.0;
+    let q = match system.controllability_matrix().transpose().lu().solve(&last) {
+        Some(q) => q,
+        None => return Err(ControlError::NotControllableError),
+    };
+
+    Ok(DMatrix::from_row_slice(1, n, (phi.transpose() * q).as_slice()))
+
+}
+
+// Kautsky-Nichols-Van Dooren method 0
+// Each closed-loop eigenvector x_j is restricted to the subspace S_j of vectors with
+// (A - λ_jI)x_j in the range of B. Sweeps rotate each x_j within S_j towards the normal of the other
+// eigenvectors, conditioning X = [x_1, ..., x_n] so the placed poles are insensitive to
+// perturbations. Then A - BK = XΛX^-1, solved for K from the QR decomposition of B.
+// Reference: "Robust Pole Assignment in Linear State Feedback" - J. Kautsky, N.K. Nichols, P. Van Dooren
+fn kautsky_nichols(system: &LTISystem, poles: &[Complex<Float>]) -> Result<DMatrix<Float>, ControlError> {
+
+    let n = system.dx;
+
+    let qr = system.B.clone().qr();
+    let U0 = qr.q();
+    let Z = qr.r();
+    let Z_inv = match Z.try_inverse() {
+        Some(Z_inv) => Z_inv,
+        None => return Err(ControlError::SingularMatrixError("B does not have full column rank".to_string())),
+    };
+
+    let complex = |M: &DMatrix<Float>| M.map(|value| Complex::new(value, 0.0));
+    let A = complex(&system.A);
+    let residual = complex(&(DMatrix::<Float>::identity(n, n) - &U0 * U0.transpose()));
+    let tolerance = Float::EPSILON.sqrt() * system.A.amax().max(1.0);
+
+    // Orthonormal basis of the complement of S_j, the row space of (I - U0U0^T)(A - λ_jI)
+    let mut complements: Vec<Vec<DVector<Complex<Float>>>> = Vec::with_capacity(n);
+    for pole in poles.iter() {
+        let N = &residual * (&A - DMatrix::<Complex<Float>>::identity(n, n) * *pole);
+        let basis = orthonormalize(N.adjoint().column_iter().map(|column| column.into_owned()), tolerance);
+        if basis.len() >= n {
+            return Err(ControlError::ArgError("pole multiplicity exceeds the number of inputs".to_string()));
+        }
+        complements.push(basis);
+    }
+    let project = |j: usize, x: &DVector<Complex<Float>>| {
+        let mut projected = x.clone();
+        for w in complements[j].iter() {
+            projected -= w * w.dotc(&projected);
+        }
+        projected
+    };
+
+    // Start from the projections of the orthogonal columns of the discrete sine transform, which
+    // are not aligned with the coordinate axes of decoupled systems
+    let mut X = DMatrix::<Complex<Float>>::zeros(n, n);
+    for j in 0..n {
+        let x = (0..n)
+            .map(|k| project(j, &DVector::from_fn(n, |i, _| {
+                let angle = PI * ((i + 1) * ((j + k) % n + 1)) as Float / (n + 1) as Float;
+                Complex::new(angle.sin(), 0.0)
+            })))
+            .find(|x| x.norm() > tolerance)
+            .ok_or_else(|| ControlError::ArgError("pole multiplicity exceeds the number of inputs".to_string()))?;
+        X.set_column(j, &(&x / Complex::new(x.norm(), 0.0)));
+    }
+    conjugate_columns(&mut X, poles);
+
+    for _ in 0..MAX_ITERATIONS {
+
+        let mut change: Float = 0.0;
+
+        for (j, pole) in poles.iter().enumerate() {
+            // Conjugate eigenvectors are updated with their pair
+            if pole.im < 0.0 {
+                continue;
+            }
+
+            // Normal to the other eigenvectors, or the coordinate axis furthest from them while X is
+            // singular and x_j lies in their span
+            let others = (0..n).filter(|k| *k != j).map(|k| X.column(k).into_owned());
+            let basis = orthonormalize(others, tolerance);
+            let normal = |y: DVector<Complex<Float>>| {
+                let mut y = y;
+                for _ in 0..2 {
+                    for b in basis.iter() {
+                        y -= b * b.dotc(&y);
+                    }
+                }
+                y
+            };
+            let mut y = normal(X.column(j).into_owned());
+            if y.norm() <= tolerance {
+                y = (0..n)
+                    .map(|k| normal(DVector::from_fn(n, |i, _| if i == k { Complex::new(1.0, 0.0) } else { Complex::new(0.0, 0.0) })))
+                    .max_by(|a, b| a.norm().partial_cmp(&b.norm()).unwrap())
+                    .unwrap();
+            }
+
+            let x = project(j, &y);
+            let norm = x.norm();
+            if norm <= tolerance {
+                continue;
+            }
+            let x = x / Complex::new(norm, 0.0);
+
+            change = change.max(1.0 - x.dotc(&X.column(j)).norm_sqr().sqrt());
+            X.set_column(j, &x);
+            if pole.im > 0.0 {
+                X.set_column(j + 1, &x.map(|value| value.conj()));
+            }
+        }
+
+        if change < TOLERANCE {
+            break;
+        }
+
+    }
+
+    // Closed-loop matrix M = XΛX^-1, real for conjugate poles and eigenvectors
+    let X_inv = match X.clone().try_inverse() {
+        Some(X_inv) => X_inv,
+        None => return Err(ControlError::SingularMatrixError("closed-loop eigenvectors".to_string())),
+    };
+    let M = (&X * DMatrix::from_diagonal(&DVector::from_column_slice(poles)) * X_inv).map(|value| value.re);
+
+    // BK = A - M, with K = Z^-1 U0^T (A - M)
+    Ok(Z_inv * U0.transpose() * (&system.A - M))
+
+}
+
+// Sets the eigenvector of each conjugate pole to the conjugate of its pair
+fn conjugate_columns(X: &mut DMatrix<Complex<Float>>, poles: &[Complex<Float>]) {
+
+    for (j, pole) in poles.iter().enumerate() {
+        if pole.im > 0.0 {
+            let conjugate = X.column(j).map(|value| value.conj());
+            X.set_column(j + 1, &conjugate);
+        }
+    }
+
+}
+
+// Orthonormal basis of the span of the vectors by Gram-Schmidt with reorthogonalization,
+// dropping vectors within tolerance of the span of the previous ones
+fn orthonormalize<I>(vectors: I, tolerance: Float) -> Vec<DVector<Complex<Float>>>
+where
+    I: Iterator<Item = DVector<Complex<Float>>>
+{
+
+    let mut basis: Vec<DVector<Complex<Float>>> = Vec::new();
+    for vector in vectors {
+        let scale = vector.norm().max(1.0);
+        let mut r = vector;
+        for _ in 0..2 {
+            for b in basis.iter() {
+                r -= b * b.dotc(&r);
+            }
+        }
+        let norm = r.norm();
+        if norm > tolerance * scale {
+            basis.push(r / Complex::new(norm, 0.0));
+        }
+    }
+
+    basis
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_poles(system: &LTISystem, K: &DMatrix<Float>) -> Vec<Complex<Float>> {
+        let mut poles = system.closed_loop_poles(K).unwrap();
+        poles.sort_by(|a, b| a.re.partial_cmp(&b.re).unwrap().then(a.im.partial_cmp(&b.im).unwrap()));
+        poles
+    }
+
+    #[test]
+    fn test_place_poles_ackermann() {
+
+        // Double integrator, s^2 + 3s + 2 = (s + 1)(s + 2) with K = [2, 3]
+        let system = LTISystem::new(
+            DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]),
+            DMatrix::from_row_slice(2, 1, &[0., 1.]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::zeros(2, 1)
+        );
+        let poles = [Complex::new(-1.0, 0.0), Complex::new(-2.0, 0.0)];
+
+        let K = place_poles(&system, &poles, PolePlacementMethod::Ackermann).unwrap();
+        assert_relative_eq!(K, DMatrix::from_row_slice(1, 2, &[2., 3.]), epsilon = 1E-5);
+
+        // Both methods agree for a single input, where the gain is unique
+        let K_robust = place_poles(&system, &poles, PolePlacementMethod::KautskyNichols).unwrap();
+        assert_relative_eq!(K_robust, K, epsilon = 1E-3);
+
+        // Complex poles s^2 + 2s + 5
+        let poles = [Complex::new(-1.0, -2.0), Complex::new(-1.0, 2.0)];
+        let K = place_poles(&system, &poles, PolePlacementMethod::Ackermann).unwrap();
+        assert_relative_eq!(K, DMatrix::from_row_slice(1, 2, &[5., 2.]), epsilon = 1E-5);
+
+    }
+
+    #[test]
+    fn test_place_poles_kautsky_nichols() {
+
+        // Two double integrators coupled through their inputs
+        let system = LTISystem::new(
+            DMatrix::from_row_slice(4, 4, &[
+                0., 0., 1., 0.,
+                0., 0., 0., 1.,
+                0., 0., 0., 0.,
+                0., 0., 0., 0.,
+            ]),
+            DMatrix::from_row_slice(4, 2, &[0., 0., 0., 0., 1., 0.5, 0., 1.]),
+            DMatrix::<Float>::identity(4, 4),
+            DMatrix::<Float>::zeros(4, 2)
+        );
+
+        // A repeated pole and a conjugate pair
+        let poles = vec![Complex::new(-2.0, 0.0), Complex::new(-1.0, 1.0), Complex::new(-2.0, 0.0), Complex::new(-1.0, -1.0)];
+        let K = place_poles(&system, &poles, PolePlacementMethod::KautskyNichols).unwrap();
+
+        let placed = sorted_poles(&system, &K);
+        let expected = [Complex::new(-2.0, 0.0), Complex::new(-2.0, 0.0), Complex::new(-1.0, -1.0), Complex::new(-1.0, 1.0)];
+        for (placed, expected) in placed.iter().zip(expected.iter()) {
+            assert_relative_eq!(placed.re, expected.re, epsilon = 1E-3);
+            assert_relative_eq!(placed.im, expected.im, epsilon = 1E-3);
+        }
+
+        // Uncontrollable systems fail
+        let system = LTISystem::new(
+            DMatrix::from_row_slice(2, 2, &[1., 0., 0., 2.]),
+            DMatrix::from_row_slice(2, 1, &[1., 0.]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::zeros(2, 1)
+        );
+        let poles = [Complex::new(-1.0, 0.0), Complex::new(-2.0, 0.0)];
+        assert_eq!(place_poles(&system, &poles, PolePlacementMethod::KautskyNichols), Err(ControlError::NotControllableError));
+        assert!(matches!(PolePlacementController::new(system, poles.to_vec(), PolePlacementMethod::Ackermann), Err(ControlError::NotControllableError)));
+
+    }
+
+    #[test]
+    fn test_PolePlacementController() {
+
+        let system = LTISystem::new(
+            DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]),
+            DMatrix::from_row_slice(2, 1, &[0., 1.]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::zeros(2, 1)
+        );
+        let mut controller = PolePlacementController::new(
+            system,
+            vec![Complex::new(-1.0, 0.0), Complex::new(-2.0, 0.0)],
+            PolePlacementMethod::Ackermann
+        ).unwrap();
+
+        let x = DVector::from_vec(vec![1.0, 1.0]);
+        let reference = DVector::from_vec(vec![2.0, 0.0]);
+        assert_relative_eq!(controller.control(0.0, &x, None)[0], -5.0, epsilon = 1E-4);
+        assert_relative_eq!(controller.control(0.0, &x, Some(&reference))[0], -1.0, epsilon = 1E-4);
+
+        // Moving the poles replaces the gain
+        controller.set_poles(vec![Complex::new(-1.0, 2.0), Complex::new(-1.0, -2.0)]).unwrap();
+        assert_relative_eq!(controller.gain().clone(), DMatrix::from_row_slice(1, 2, &[5., 2.]), epsilon = 1E-4);
+
+        // Invalid poles are rejected and the previous poles kept
+        assert!(matches!(controller.set_poles(vec![Complex::new(-1.0, 0.0)]), Err(ControlError::ArgError(_))));
+        assert!(matches!(controller.set_poles(vec![Complex::new(-1.0, 2.0), Complex::new(-1.0, -3.0)]), Err(ControlError::ArgError(_))));
+        assert!(matches!(controller.set_poles(vec![Complex::new(-1.0, 2.0), Complex::new(-2.0, 0.0)]), Err(ControlError::ArgError(_))));
+        assert_eq!(controller.poles(), &[Complex::new(-1.0, 2.0), Complex::new(-1.0, -2.0)]);
+
+        // Ackermann's formula needs a single input
+        let system = LTISystem::new(
+            DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::zeros(2, 2)
+        );
+        let poles = vec![Complex::new(-1.0, 0.0), Complex::new(-2.0, 0.0)];
+        assert!(matches!(PolePlacementController::new(system.clone(), poles.clone(), PolePlacementMethod::Ackermann), Err(ControlError::ArgError(_))));
+        assert!(PolePlacementController::new(system.clone(), poles, PolePlacementMethod::KautskyNichols).is_ok());
+
+        // A pole repeated more times than there are inputs is rejected by the Kautsky-Nichols method
+        let poles = vec![Complex::new(-1.0, 0.0), Complex::new(-1.0, 0.0)];
+        let single = LTISystem::new(
+            DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]),
+            DMatrix::from_row_slice(2, 1, &[0., 1.]),
+            DMatrix::<Float>::identity(2, 2),
+            DMatrix::<Float>::zeros(2, 1)
+        );
+        assert!(matches!(PolePlacementController::new(single.clone(), poles.clone(), PolePlacementMethod::KautskyNichols), Err(ControlError::ArgError(_))));
+        assert!(PolePlacementController::new(system, poles.clone(), PolePlacementMethod::KautskyNichols).is_ok());
+
+        // Ackermann's formula places the repeated pole of a single-input system, s^2 + 2s + 1
+        let controller = PolePlacementController::new(single, poles, PolePlacementMethod::Ackermann).unwrap();
+        assert_relative_eq!(controller.gain().clone(), DMatrix::from_row_slice(1, 2, &[1., 2.]), epsilon = 1E-4);
+
+    }
+}
@@ -1,11 +1,12 @@
 
 use na::DVector;
+use crate::float::Float;
 
 /// Defines an interface for solving closed-form equations
 ///
 /// x(t) = f(t, x(t))
 ///
 pub trait ClosedFormSolution {
-    fn rhs(&self, t: f32, x: &DVector<f32>) -> DVector<f32>;
+    fn rhs(&self, t: Float, x: &DVector<Float>) -> DVector<Float>;
 }
 
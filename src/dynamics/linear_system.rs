@@ -1,6 +1,23 @@
 
-use na::{DMatrix, DVector};
-use crate::dynamics::statespace::StateSpaceRepresentation;
+use na::{Complex, DMatrix, DVector};
+use crate::dynamics::statespace::{StateSpaceRepresentation, DiscreteStateSpaceRepresentation};
+use crate::math::riccati::{LinAlgError, RiccatiError, is_detectable, is_stabilizable, pbh_test};
+use crate::math::schur::eigenvalues;
+use crate::math::lyapunov::{LyapunovError, controllability_gramian, observability_gramian};
+use crate::float::Float;
+
+/// Methods of converting a continuous-time system into a discrete-time system
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DiscretizationMethod {
+    /// Input held constant over each sample period (exact for piecewise constant inputs)
+    ZeroOrderHold,
+    /// Input linearly interpolated between samples (exact for piecewise linear inputs)
+    FirstOrderHold,
+    /// Bilinear transform, maps the stable half-plane onto the unit disk
+    Tustin,
+    /// First-order approximation of the matrix exponential, I + A*dt
+    ForwardEuler,
+}
 
 /// Defines a linear time-invariant system of equations
 ///
@@ -12,21 +29,318 @@ use crate::dynamics::statespace::StateSpaceRepresentation;
 /// du: control input size \
 #[derive(Debug, Clone, PartialEq)]
 pub struct LTISystem {
-    pub A: DMatrix<f32>,
-    pub B: DMatrix<f32>,
-    pub C: DMatrix<f32>,
-    pub D: DMatrix<f32>,
+    pub A: DMatrix<Float>,
+    pub B: DMatrix<Float>,
+    pub C: DMatrix<Float>,
+    pub D: DMatrix<Float>,
     pub dx: usize,
     pub du: usize,
 }
 
 impl LTISystem {
-    pub fn new(A: DMatrix<f32>, B: DMatrix<f32>, C: DMatrix<f32>, D: DMatrix<f32>) -> Self {
+    pub fn new(A: DMatrix<Float>, B: DMatrix<Float>, C: DMatrix<Float>, D: DMatrix<Float>) -> Self {
         let dx = A.shape().1 as usize;
         let du = B.shape().1 as usize;
 
         Self { A, B, C, D, dx, du }
     }
+
+    /// State transition matrix of the unforced system - Φ(t, t0) = e^{A(t - t0)}
+    pub fn state_transition_matrix(&self, t: Float, t0: Float) -> DMatrix<Float> {
+
+        (&self.A * (t - t0)).exp()
+
+    }
+
+    /// Converts the system into a discrete-time system with sample period dt
+    /// Reference: "Digital Control of Dynamic Systems" - G.F. Franklin, J.D. Powell, M.L. Workman
+    pub fn discretize(&self, dt: Float, method: DiscretizationMethod) -> Result<DiscreteLTISystem, LinAlgError> {
+
+        let n = self.dx;
+        let m = self.du;
+        let I = DMatrix::<Float>::identity(n, n);
+
+        match method {
+
+            DiscretizationMethod::ZeroOrderHold => {
+
+                // e^{[A B; 0 0]dt} = [Ad Bd; 0 I]
+                let mut M = DMatrix::<Float>::zeros(n + m, n + m);
+                M.slice_mut((0, 0), (n, n)).copy_from(&(&self.A * dt));
+                M.slice_mut((0, n), (n, m)).copy_from(&(&self.B * dt));
+                let expM = M.exp();
+
+                let Ad = expM.slice((0, 0), (n, n)).into_owned();
+                let Bd = expM.slice((0, n), (n, m)).into_owned();
+
+                Ok(DiscreteLTISystem::new(Ad, Bd, self.C.clone(), self.D.clone(), dt))
+
+            },
+
+            DiscretizationMethod::FirstOrderHold => {
+
+                // e^{[A*dt B*dt 0; 0 0 I; 0 0 0]} = [Ad G1 G2; 0 I I; 0 0 I]
+                let mut M = DMatrix::<Float>::zeros(n + 2*m, n + 2*m);
+                M.slice_mut((0, 0), (n, n)).copy_from(&(&self.A * dt));
+                M.slice_mut((0, n), (n, m)).copy_from(&(&self.B * dt));
+                M.slice_mut((n, n + m), (m, m)).fill_with_identity();
+                let expM = M.exp();
+
+                let Ad = expM.slice((0, 0), (n, n)).into_owned();
+                let G1 = expM.slice((0, n), (n, m)).into_owned();
+                let G2 = expM.slice((0, n + m), (n, m)).into_owned();
+
+                // Non-causal in the original state, so the state is shifted by G2*u[k]
+                let Bd = &G1 + &Ad * &G2 - &G2;
+                let Dd = &self.D + &self.C * &G2;
+
+                Ok(DiscreteLTISystem::new(Ad, Bd, self.C.clone(), Dd, dt))
+
+            },
+
+            DiscretizationMethod::Tustin => {
+
+                let W = match (&I - (dt / 2.0) * &self.A).try_inverse() {
+                    Some(inverse) => inverse,
+                    None => return Err(LinAlgError),
+                };
+
+                let Ad = &W * (&I + (dt / 2.0) * &self.A);
+                let Bd = &W * &self.B * dt;
+                let Cd = &self.C * &W;
+                let Dd = &self.D + 0.5 * &self.C * &Bd;
+
+                Ok(DiscreteLTISystem::new(Ad, Bd, Cd, Dd, dt))
+
+            },
+
+            DiscretizationMethod::ForwardEuler => {
+
+                let Ad = &I + &self.A * dt;
+                let Bd = &self.B * dt;
+
+                Ok(DiscreteLTISystem::new(Ad, Bd, self.C.clone(), self.D.clone(), dt))
+
+            },
+
+        }
+
+    }
+
+    /// Controllability matrix [B, AB, ..., A^{n-1}B]
+    pub fn controllability_matrix(&self) -> DMatrix<Float> {
+
+        let n = self.dx;
+        let m = self.du;
+        let mut controllability = DMatrix::<Float>::zeros(n, n * m);
+        let mut AkB = self.B.clone();
+        for k in 0..n {
+            controllability.slice_mut((0, k * m), (n, m)).copy_from(&AkB);
+            AkB = &self.A * AkB;
+        }
+
+        controllability
+
+    }
+
+    /// Observability matrix [C; CA; ...; CA^{n-1}]
+    pub fn observability_matrix(&self) -> DMatrix<Float> {
+
+        let n = self.dx;
+        let p = self.C.shape().0;
+        let mut observability = DMatrix::<Float>::zeros(n * p, n);
+        let mut CAk = self.C.clone();
+        for k in 0..n {
+            observability.slice_mut((k * p, 0), (p, n)).copy_from(&CAk);
+            CAk *= &self.A;
+        }
+
+        observability
+
+    }
+
+    /// Numerical rank of the controllability matrix
+    pub fn controllability_rank(&self) -> usize {
+
+        numerical_rank(&self.controllability_matrix())
+
+    }
+
+    /// Numerical rank of the observability matrix
+    pub fn observability_rank(&self) -> usize {
+
+        numerical_rank(&self.observability_matrix())
+
+    }
+
+    /// Checks the controllability matrix has full rank
+    pub fn is_controllable(&self) -> bool {
+
+        self.controllability_rank() == self.dx
+
+    }
+
+    /// Checks the observability matrix has full rank
+    pub fn is_observable(&self) -> bool {
+
+        self.observability_rank() == self.dx
+
+    }
+
+    /// Checks controllability with the Popov-Belevitch-Hautus test
+    /// rank [A - λI, B] = dx for every eigenvalue λ of A
+    ///
+    /// Better conditioned than the rank of the controllability matrix for larger systems
+    pub fn is_controllable_pbh(&self) -> Result<bool, RiccatiError> {
+
+        pbh_test(&self.A, &self.B, |_, _| true)
+
+    }
+
+    /// Checks observability with the Popov-Belevitch-Hautus test on the dual system (A^T, C^T)
+    pub fn is_observable_pbh(&self) -> Result<bool, RiccatiError> {
+
+        pbh_test(&self.A.transpose(), &self.C.transpose(), |_, _| true)
+
+    }
+
+    /// Checks every uncontrollable mode is stable with the Popov-Belevitch-Hautus test
+    pub fn is_stabilizable(&self) -> Result<bool, RiccatiError> {
+
+        is_stabilizable(&self.A, &self.B)
+
+    }
+
+    /// Checks every unobservable mode is stable with the Popov-Belevitch-Hautus test
+    pub fn is_detectable(&self) -> Result<bool, RiccatiError> {
+
+        is_detectable(&self.A, &self.C)
+
+    }
+
+    /// Poles of the system, the eigenvalues of A
+    /// Fails with RiccatiError::SchurError and the LAPACK info code if the Schur decomposition fails
+    pub fn poles(&self) -> Result<Vec<Complex<Float>>, RiccatiError> {
+
+        eigenvalues(&self.A).map_err(RiccatiError::SchurError)
+
+    }
+
+    /// Checks every pole lies in the open left half-plane
+    pub fn is_stable(&self) -> Result<bool, RiccatiError> {
+
+        Ok(self.poles()?.iter().all(|pole| pole.re < 0.0))
+
+    }
+
+    /// Infinite-horizon controllability Gramian W, the solution of AW + WA^T + BB^T = 0
+    /// Only exists for stable systems
+    pub fn controllability_gramian(&self) -> Result<DMatrix<Float>, LyapunovError> {
+
+        controllability_gramian(&self.A, &self.B)
+
+    }
+
+    /// Infinite-horizon observability Gramian W, the solution of A^TW + WA + C^TC = 0
+    /// Only exists for stable systems
+    pub fn observability_gramian(&self) -> Result<DMatrix<Float>, LyapunovError> {
+
+        observability_gramian(&self.A, &self.C)
+
+    }
+
+    /// Closed-loop system under the state feedback u = -Kx + v
+    ///
+    /// x_dot = (A - BK)x + Bv \
+    /// y = (C - DK)x + Dv
+    pub fn closed_loop(&self, K: &DMatrix<Float>) -> LTISystem {
+
+        assert_eq!(K.shape(), (self.du, self.dx));
+
+        LTISystem::new(&self.A - &self.B * K, self.B.clone(), &self.C - &self.D * K, self.D.clone())
+
+    }
+
+    /// Poles of the closed-loop system under the state feedback u = -Kx, the eigenvalues of A - BK
+    pub fn closed_loop_poles(&self, K: &DMatrix<Float>) -> Result<Vec<Complex<Float>>, RiccatiError> {
+
+        self.closed_loop(K).poles()
+
+    }
+}
+
+// Rank of M, neglecting singular values below a tolerance relative to its largest entry
+fn numerical_rank(M: &DMatrix<Float>) -> usize {
+
+    M.rank(Float::EPSILON.sqrt() * M.amax().max(1.0))
+
+}
+
+/// Defines a discrete-time linear time-invariant system of equations
+///
+/// x[k+1] = Ax[k] + Bu[k] \
+/// y[k] = Cx[k] + Du[k] \
+///
+/// A: State/system matrix \
+/// B: Input matrix \
+/// C: Output matrix \
+/// D: Feedforward matrix \
+/// dt: Sample period \
+/// dx: Statespace size \
+/// du: control input size \
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteLTISystem {
+    pub A: DMatrix<Float>,
+    pub B: DMatrix<Float>,
+    pub C: DMatrix<Float>,
+    pub D: DMatrix<Float>,
+    pub dt: Float,
+    pub dx: usize,
+    pub du: usize,
+}
+
+impl DiscreteLTISystem {
+    pub fn new(A: DMatrix<Float>, B: DMatrix<Float>, C: DMatrix<Float>, D: DMatrix<Float>, dt: Float) -> Self {
+        let dx = A.shape().1;
+        let du = B.shape().1;
+
+        Self { A, B, C, D, dt, dx, du }
+    }
+}
+
+impl DiscreteStateSpaceRepresentation for DiscreteLTISystem {
+
+    /// Advances the State vector by one sample - x[k+1] = Ax[k] + Bu[k]
+    /// k: sample index
+    /// x: State vector
+    /// u: Control/input vector
+    fn f(&self, _k: usize, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
+
+        match u {
+            Some(u) => &self.A * x + &self.B * u,
+            None => &self.A * x,
+        }
+
+    }
+
+    /// Solves for the Output vector - y[k] = Cx[k] + Du[k]
+    /// k: sample index
+    /// x: State vector
+    /// u: Control/input vector
+    fn h(&self, _k: usize, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
+
+        match u {
+            Some(u) => &self.C * x + &self.D * u,
+            None => &self.C * x,
+        }
+
+    }
+
+    fn dt(&self) -> Float {
+
+        self.dt
+
+    }
 }
 
 impl StateSpaceRepresentation for LTISystem {
@@ -35,7 +349,7 @@ impl StateSpaceRepresentation for LTISystem {
     /// t: time
     /// x: State vector
     /// u: Control/input vector
-    fn f(&self, _t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, _t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         let result = match u {
             Some(u) => &self.A * x + &self.B * u,
@@ -49,7 +363,7 @@ impl StateSpaceRepresentation for LTISystem {
     /// t: time
     /// x: State vector
     /// u: Control/input vector
-    fn h(&self, _t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, _t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         let result = match u {
             Some(u) => &self.C * x + &self.D * u,
@@ -58,6 +372,27 @@ impl StateSpaceRepresentation for LTISystem {
 
         result
     }
+
+    /// Returns the exact Jacobian of the state equations - df/dx = A
+    fn jacobian(&self, _t: Float, _x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DMatrix<Float> {
+
+        self.A.clone()
+
+    }
+
+    /// Returns the exact Jacobian of the state equations with respect to the input - df/du = B
+    fn input_jacobian(&self, _t: Float, _x: &DVector<Float>, _u: &DVector<Float>) -> DMatrix<Float> {
+
+        self.B.clone()
+
+    }
+
+    /// A linear system is its own linearization about any operating point
+    fn linearize(&self, _t: Float, _x0: &DVector<Float>, _u0: &DVector<Float>) -> LTISystem {
+
+        self.clone()
+
+    }
 }
 
 
@@ -82,7 +417,7 @@ mod tests {
         let x = DVector::from_vec(vec![10., 10.]);
         let u = DVector::from_vec(vec![10.]);
 
-        let result = double_integrator.f(0f32, &x, Some(&u));
+        let result = double_integrator.f(0.0, &x, Some(&u));
 
         assert_eq!(result, DVector::from_vec(vec![20., 20.]));
 
@@ -92,7 +427,7 @@ mod tests {
     fn test_LinearSystem_solve_output() {
 
         let C = DMatrix::from_row_slice(1, 2, &[1., 0.]);
-        let D = DMatrix::<f32>::zeros(1, 1);
+        let D = DMatrix::<Float>::zeros(1, 1);
         let A = C.clone();
         let B = D.clone();
 
@@ -103,9 +438,191 @@ mod tests {
 
         let u = DVector::from_vec(vec![10.]);
 
-        let result = double_integrator.h(0f32, &x, Some(&u));
+        let result = double_integrator.h(0.0, &x, Some(&u));
 
         assert_eq!(result, DVector::from_vec(vec![10.]));
 
     }
+
+    #[test]
+    fn test_LinearSystem_jacobian() {
+
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., -2., -3.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let C = DMatrix::<Float>::identity(2, 2);
+        let D = DMatrix::<Float>::zeros(2, 1);
+
+        let system = LTISystem::new(A.clone(), B, C, D);
+        let x = DVector::from_vec(vec![1., -1.]);
+
+        assert_eq!(system.jacobian(0.0, &x, None), A);
+
+    }
+
+    #[test]
+    fn test_LinearSystem_state_transition_matrix() {
+
+        use crate::math::integrate::IntegratorType;
+        use crate::math::integrate::SolverOptions;
+        use crate::math::integrate::variational::solve_ivp_variational;
+
+        // Double integrator - Φ(t, t0) = [1, t - t0; 0, 1]
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let C = DMatrix::<Float>::identity(2, 2);
+        let D = DMatrix::<Float>::zeros(2, 1);
+
+        let system = LTISystem::new(A, B, C, D);
+        let phi = system.state_transition_matrix(3.0, 1.0);
+
+        assert_relative_eq!(phi, DMatrix::from_row_slice(2, 2, &[1., 2., 0., 1.]), epsilon = 1E-5);
+
+        // Damped oscillator against the variational equations
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., -2., -3.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let C = DMatrix::<Float>::identity(2, 2);
+        let D = DMatrix::<Float>::zeros(2, 1);
+
+        let system = LTISystem::new(A, B, C, D);
+
+        let f = |t: Float, x: &DVector<Float>| system.f(t, x, None);
+        let jacobian = |t: Float, x: &DVector<Float>| system.jacobian(t, x, None);
+        let x0 = DVector::from_vec(vec![1., 0.]);
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-8, ..SolverOptions::default() };
+        let result = solve_ivp_variational(f, jacobian, (0.0, 2.0), x0, IntegratorType::DOPRI5, opts).unwrap();
+
+        let phi = system.state_transition_matrix(2.0, 0.0);
+        assert_relative_eq!(phi, result.phi[result.phi.len()-1], epsilon = 1E-4);
+
+    }
+
+    #[test]
+    fn test_LinearSystem_discretize() {
+
+        let dt = 0.1;
+
+        // Double integrator
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let C = DMatrix::<Float>::identity(2, 2);
+        let D = DMatrix::<Float>::zeros(2, 1);
+        let system = LTISystem::new(A, B, C, D);
+
+        let zoh = system.discretize(dt, DiscretizationMethod::ZeroOrderHold).unwrap();
+        assert_relative_eq!(zoh.A, DMatrix::from_row_slice(2, 2, &[1., dt, 0., 1.]), epsilon = 1E-6);
+        assert_relative_eq!(zoh.B, DMatrix::from_row_slice(2, 1, &[dt*dt/2.0, dt]), epsilon = 1E-6);
+
+        let euler = system.discretize(dt, DiscretizationMethod::ForwardEuler).unwrap();
+        assert_relative_eq!(euler.A, zoh.A, epsilon = 1E-6);
+        assert_relative_eq!(euler.B, DMatrix::from_row_slice(2, 1, &[0., dt]), epsilon = 1E-6);
+
+        // First-order hold reproduces the response to a ramp input u(t) = t exactly
+        let foh = system.discretize(dt, DiscretizationMethod::FirstOrderHold).unwrap();
+        let mut x = DVector::<Float>::zeros(2);
+        for k in 0..10 {
+            let u = DVector::from_vec(vec![k as Float * dt]);
+            x = foh.f(k, &x, Some(&u));
+        }
+        let y = foh.h(10, &x, Some(&DVector::from_vec(vec![1.0])));
+        assert_relative_eq!(y, DVector::from_vec(vec![1.0/6.0, 0.5]), epsilon = 1E-5);
+
+        // Tustin maps the pole s = -1 to z = (1 - dt/2) / (1 + dt/2)
+        let A = DMatrix::from_row_slice(1, 1, &[-1.]);
+        let B = DMatrix::from_row_slice(1, 1, &[1.]);
+        let C = DMatrix::from_row_slice(1, 1, &[1.]);
+        let D = DMatrix::<Float>::zeros(1, 1);
+        let system = LTISystem::new(A, B, C, D);
+
+        let tustin = system.discretize(dt, DiscretizationMethod::Tustin).unwrap();
+        assert_relative_eq!(tustin.A[(0, 0)], (1.0 - dt/2.0) / (1.0 + dt/2.0), epsilon = 1E-6);
+
+        // Unity DC gain is preserved - y = (C(I - Ad)^-1 Bd + Dd) u
+        let dc_gain = &tustin.C * (DMatrix::<Float>::identity(1, 1) - &tustin.A).try_inverse().unwrap() * &tustin.B + &tustin.D;
+        assert_relative_eq!(dc_gain[(0, 0)], 1.0, epsilon = 1E-5);
+
+    }
+
+    #[test]
+    fn test_LinearSystem_controllability() {
+
+        // Double integrator forced through the velocity, measuring the position
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let C = DMatrix::from_row_slice(1, 2, &[1., 0.]);
+        let D = DMatrix::<Float>::zeros(1, 1);
+        let system = LTISystem::new(A.clone(), B, C, D.clone());
+
+        assert_eq!(system.controllability_matrix(), DMatrix::from_row_slice(2, 2, &[0., 1., 1., 0.]));
+        assert_eq!(system.observability_matrix(), DMatrix::<Float>::identity(2, 2));
+        assert!(system.is_controllable() && system.is_observable());
+        assert!(system.is_controllable_pbh().unwrap() && system.is_observable_pbh().unwrap());
+
+        // Forcing the position and measuring the velocity loses both
+        let B = DMatrix::from_row_slice(2, 1, &[1., 0.]);
+        let C = DMatrix::from_row_slice(1, 2, &[0., 1.]);
+        let system = LTISystem::new(A, B, C, D);
+
+        assert_eq!(system.controllability_rank(), 1);
+        assert_eq!(system.observability_rank(), 1);
+        assert!(!system.is_controllable_pbh().unwrap() && !system.is_observable_pbh().unwrap());
+        assert!(!system.is_stabilizable().unwrap() && !system.is_detectable().unwrap());
+
+        // An uncontrollable stable mode is still stabilizable
+        let A = DMatrix::from_row_slice(2, 2, &[1., 0., 0., -1.]);
+        let B = DMatrix::from_row_slice(2, 1, &[1., 0.]);
+        let system = LTISystem::new(A, B.clone(), B.transpose(), DMatrix::<Float>::zeros(1, 1));
+
+        assert!(!system.is_controllable() && !system.is_controllable_pbh().unwrap());
+        assert!(system.is_stabilizable().unwrap() && system.is_detectable().unwrap());
+
+    }
+
+    #[test]
+    fn test_LinearSystem_poles() {
+
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let C = DMatrix::<Float>::identity(2, 2);
+        let D = DMatrix::<Float>::zeros(2, 1);
+        let system = LTISystem::new(A, B, C, D);
+
+        assert!(system.poles().unwrap().iter().all(|pole| pole.re.hypot(pole.im) < 1E-3));
+        assert!(!system.is_stable().unwrap());
+
+        // LQR gain for Q = I, R = 1 places the poles at s^2 + sqrt(3)s + 1 = 0
+        let K = DMatrix::from_row_slice(1, 2, &[1., (3.0 as Float).sqrt()]);
+        let closed_loop = system.closed_loop(&K);
+        assert!(closed_loop.is_stable().unwrap());
+        assert_relative_eq!(closed_loop.C, DMatrix::<Float>::identity(2, 2));
+
+        let mut poles = system.closed_loop_poles(&K).unwrap();
+        poles.sort_by(|a, b| a.im.partial_cmp(&b.im).unwrap());
+        let re = -(3.0 as Float).sqrt() / 2.0;
+        assert_relative_eq!(poles[0].re, re, epsilon = 1E-5);
+        assert_relative_eq!(poles[0].im, -0.5, epsilon = 1E-5);
+        assert_relative_eq!(poles[1].re, re, epsilon = 1E-5);
+        assert_relative_eq!(poles[1].im, 0.5, epsilon = 1E-5);
+
+    }
+
+    #[test]
+    fn test_LinearSystem_gramian() {
+
+        // Decoupled modes, W_ij = b_i b_j / -(λ_i + λ_j)
+        let A = DMatrix::from_row_slice(2, 2, &[-1., 0., 0., -2.]);
+        let B = DMatrix::from_row_slice(2, 1, &[1., 1.]);
+        let C = DMatrix::from_row_slice(1, 2, &[1., 2.]);
+        let D = DMatrix::<Float>::zeros(1, 1);
+        let system = LTISystem::new(A, B, C, D);
+
+        let controllability = system.controllability_gramian().unwrap();
+        assert_relative_eq!(controllability, DMatrix::from_row_slice(2, 2, &[0.5, 1./3., 1./3., 0.25]), epsilon = 1E-5);
+
+        let observability = system.observability_gramian().unwrap();
+        assert_relative_eq!(observability, DMatrix::from_row_slice(2, 2, &[0.5, 2./3., 2./3., 1.]), epsilon = 1E-5);
+
+        let unstable = system.closed_loop(&DMatrix::from_row_slice(1, 2, &[-2., 0.]));
+        assert_eq!(unstable.controllability_gramian().unwrap_err(), LyapunovError::NotStableError);
+
+    }
 }
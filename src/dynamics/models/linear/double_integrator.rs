@@ -2,6 +2,7 @@
 use na::{DMatrix, DVector};
 use crate::dynamics::linear_system::LTISystem;
 use crate::dynamics::statespace::{StateSpace, StateSpaceType, StateSpaceRepresentation};
+use crate::float::Float;
 // use crate::util::print_matrix;
 
 
@@ -56,17 +57,35 @@ impl DoubleIntegrator1D {
 
 impl StateSpaceRepresentation for DoubleIntegrator1D {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.f(t, x, u)
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.h(t, x, u)
 
     }
+
+    fn jacobian(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DMatrix<Float> {
+
+        self.dynamics.jacobian(t, x, u)
+
+    }
+
+    fn input_jacobian(&self, t: Float, x: &DVector<Float>, u: &DVector<Float>) -> DMatrix<Float> {
+
+        self.dynamics.input_jacobian(t, x, u)
+
+    }
+
+    fn linearize(&self, t: Float, x0: &DVector<Float>, u0: &DVector<Float>) -> LTISystem {
+
+        self.dynamics.linearize(t, x0, u0)
+
+    }
 }
 
 
@@ -145,17 +164,35 @@ impl DoubleIntegrator2D {
 
 impl StateSpaceRepresentation for DoubleIntegrator2D {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.f(t, x, u)
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.h(t, x, u)
 
     }
+
+    fn jacobian(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DMatrix<Float> {
+
+        self.dynamics.jacobian(t, x, u)
+
+    }
+
+    fn input_jacobian(&self, t: Float, x: &DVector<Float>, u: &DVector<Float>) -> DMatrix<Float> {
+
+        self.dynamics.input_jacobian(t, x, u)
+
+    }
+
+    fn linearize(&self, t: Float, x0: &DVector<Float>, u0: &DVector<Float>) -> LTISystem {
+
+        self.dynamics.linearize(t, x0, u0)
+
+    }
 }
 
 
@@ -244,17 +281,35 @@ impl DoubleIntegrator3D {
 
 impl StateSpaceRepresentation for DoubleIntegrator3D {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.f(t, x, u)
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.h(t, x, u)
 
     }
+
+    fn jacobian(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DMatrix<Float> {
+
+        self.dynamics.jacobian(t, x, u)
+
+    }
+
+    fn input_jacobian(&self, t: Float, x: &DVector<Float>, u: &DVector<Float>) -> DMatrix<Float> {
+
+        self.dynamics.input_jacobian(t, x, u)
+
+    }
+
+    fn linearize(&self, t: Float, x0: &DVector<Float>, u0: &DVector<Float>) -> LTISystem {
+
+        self.dynamics.linearize(t, x0, u0)
+
+    }
 }
 
 
@@ -268,7 +323,7 @@ mod tests {
         let model = DoubleIntegrator1D::new();
         let x0 = DVector::from_vec(vec![10., 10.]);
 
-        let xdot = model.f(0f32, &x0, None);
+        let xdot = model.f(0.0, &x0, None);
 
         println!("{:?}", xdot);
 
@@ -280,7 +335,7 @@ mod tests {
         let model = DoubleIntegrator2D::new();
         let x0 = DVector::from_vec(vec![10., 10., 10., -10.]);
 
-        let xdot = model.f(0f32, &x0, None);
+        let xdot = model.f(0.0, &x0, None);
 
         println!("{:?}", xdot);
 
@@ -292,7 +347,7 @@ mod tests {
         let model = DoubleIntegrator3D::new();
         let x0 = DVector::from_vec(vec![10., 10., 10., -10., -30., 0.]);
 
-        let xdot = model.f(0f32, &x0, None);
+        let xdot = model.f(0.0, &x0, None);
 
         println!("{:?}", xdot);
 
@@ -2,6 +2,8 @@
 use na::{DVector, DMatrix};
 use crate::dynamics::linear_system::LTISystem;
 use crate::dynamics::statespace::{StateSpace, StateSpaceType, StateSpaceRepresentation};
+use crate::math::integrate::symplectic::SecondOrderPartition;
+use crate::float::Float;
 
 /// Linearized inverted pendulum
 /// Small angle approximations and linearized about vertically upward angle
@@ -63,18 +65,43 @@ impl InvertedPendulum {
 
 impl StateSpaceRepresentation for InvertedPendulum {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.f(t, x, u)
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.h(t, x, u)
 
     }
 
+    fn jacobian(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DMatrix<Float> {
+
+        self.dynamics.jacobian(t, x, u)
+
+    }
+
+    fn input_jacobian(&self, t: Float, x: &DVector<Float>, u: &DVector<Float>) -> DMatrix<Float> {
+
+        self.dynamics.input_jacobian(t, x, u)
+
+    }
+
+    fn linearize(&self, t: Float, x0: &DVector<Float>, u0: &DVector<Float>) -> LTISystem {
+
+        self.dynamics.linearize(t, x0, u0)
+
+    }
+
+
+    // Cart position and rod angle are each followed by their rate
+    fn partition(&self) -> Option<SecondOrderPartition> {
+
+        Some(SecondOrderPartition::interleaved(4))
+
+    }
 }
 
 #[cfg(test)]
@@ -88,7 +115,7 @@ mod tests {
         // x, x_dot, phi, phi_dot (phi is some small deviation from equilibrium)
         let x0 = DVector::from_vec(vec![10., 10., 10., 10.]);
 
-        let xdot = model.f(0f32, &x0, None);
+        let xdot = model.f(0.0, &x0, None);
 
         println!("{:?}", xdot);
     }
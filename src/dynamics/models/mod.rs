@@ -10,3 +10,4 @@ pub use self::linear::inverted_pendulum::InvertedPendulum;
 pub use self::nonlinear::double_pendulum::DoublePendulum;
 pub use self::nonlinear::inverted_pendulum::InvertedPendulum as NonlinearInvertedPendulum;
 pub use self::nonlinear::clohessy_wiltshire::ClohessyWiltshire;
+pub use self::nonlinear::hardening_spring::HardeningSpring;
@@ -3,8 +3,9 @@ use na::DVector;
 use crate::dynamics::closed_form::ClosedFormSolution;
 use crate::dynamics::statespace::{StateSpace, StateSpaceType};
 use crate::dynamics::nonlinear_system::{NonlinearExpression_fn, NonlinearExpression};
+use crate::float::Float;
 
-pub fn ClohessyWiltshireSolution(t: f32, x: &DVector<f32>) -> DVector<f32> {
+pub fn ClohessyWiltshireSolution(t: Float, x: &DVector<Float>) -> DVector<Float> {
 
     // Initial conditions
     let x0 = x[0];
@@ -15,8 +16,8 @@ pub fn ClohessyWiltshireSolution(t: f32, x: &DVector<f32>) -> DVector<f32> {
     let zdot0 = x[5];
 
     // TODO: needs to be user-defined
-    let n: f32 = 0.00113; // LEO orbit - omega_dot
-    // let R: f32 = 405.0 + 6870.0;
+    let n: Float = 0.00113; // LEO orbit - omega_dot
+    // let R: Float = 405.0 + 6870.0;
     // let mu = 398600.5;
     // let n = (mu/(R.powf(3.0))).sqrt();
     let tau = n*t;
@@ -36,7 +37,7 @@ pub fn ClohessyWiltshireSolution(t: f32, x: &DVector<f32>) -> DVector<f32> {
 
     let ztdot = -n*sin_nt*z0 + cos_nt*zdot0;
 
-    DVector::<f32>::from_vec(vec![xt, yt, zt, xtdot, ytdot, ztdot])
+    DVector::<Float>::from_vec(vec![xt, yt, zt, xtdot, ytdot, ztdot])
 
 }
 
@@ -107,7 +108,7 @@ impl ClohessyWiltshire {
 
 impl ClosedFormSolution for ClohessyWiltshire {
 
-    fn rhs(&self, t: f32, x: &DVector<f32>) -> DVector<f32> {
+    fn rhs(&self, t: Float, x: &DVector<Float>) -> DVector<Float> {
 
         self.dynamics.rhs(t, x)
 
@@ -136,7 +137,7 @@ mod tests {
             let test = dynamics.rhs(t, &x);
 
             traj.push(test);
-            t = dt*i as f32;
+            t = dt*i as Float;
 
         }
 
@@ -2,8 +2,10 @@
 use na::{DMatrix, DVector};
 use crate::dynamics::nonlinear_system::{NonlinearStateSpaceModel, NonlinearStateSpace_fn};
 use crate::dynamics::statespace::{StateSpace, StateSpaceType, StateSpaceRepresentation};
+use crate::math::integrate::symplectic::SecondOrderPartition;
+use crate::float::Float;
 
-fn equations_of_motion(_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>) -> DVector<f32> {
+fn equations_of_motion(_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DVector<Float> {
 
     // Double pendulum model params
     // https://web.mit.edu/jorloff/www/chaosTalk/double-pendulum/double-pendulum-en.html
@@ -14,7 +16,7 @@ fn equations_of_motion(_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>) ->
     let m2 = 2.0; // mass at end of rod 2
 
     // Pendulum equations
-    let mut res = DVector::<f32>::zeros(x.len());
+    let mut res = DVector::<Float>::zeros(x.len());
 
     let x1 = x[0]; // theta rod 1
     let x2 = x[1]; // omega rod 1
@@ -49,7 +51,7 @@ fn equations_of_motion(_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>) ->
 
 }
 
-fn output_equations(_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>) -> DVector<f32> {
+fn output_equations(_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DVector<Float> {
 
     let C = DMatrix::from_row_slice(2, 4,
             &[1., 0., 0., 0.,
@@ -113,24 +115,32 @@ impl DoublePendulum {
 
 impl StateSpaceRepresentation for DoublePendulum {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.f(t, x, u)
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.h(t, x, u)
 
     }
+
+    // Each rod angle is followed by its angular velocity
+    fn partition(&self) -> Option<SecondOrderPartition> {
+
+        Some(SecondOrderPartition::interleaved(4))
+
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::math::integrate::runge_kutta::RK45;
-    use std::f32::consts::FRAC_PI_4;
+    use crate::math::integrate::SolverOptions;
+    use crate::float::consts::FRAC_PI_4;
 
     #[test]
     fn test_DoublePendulum() {
@@ -139,21 +149,21 @@ mod tests {
 
         // initial conditions
         // theta1, omega1, theta2, omega2
-        let x0 = DVector::<f32>::from_vec(vec![FRAC_PI_4, 0.0, -FRAC_PI_4, 0.0]);
+        let x0 = DVector::<Float>::from_vec(vec![FRAC_PI_4, 0.0, -FRAC_PI_4, 0.0]);
         let t0 = 0.0;
 
         // Integrate the dynamics
 
         // Wrap model in appropriately defined closure for integrator (ie. f(t,x))
-        let dynamics = |t: f32, x: &DVector<f32>| {
+        let dynamics = |t: Float, x: &DVector<Float>| {
             pendulum.f(t, x, None)
         };
 
         let tf = 10.0;
         let n = 1000.0;
         let step = (tf - t0) / n;
-        let rtol = 1E-3;
-        let (_t, y) = RK45(dynamics, t0, x0, tf, step, rtol);
+        let opts = SolverOptions{ rtol: 1E-3, ..SolverOptions::default() };
+        let y = RK45(dynamics, t0, x0, tf, step, &opts).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
@@ -0,0 +1,96 @@
+
+use na::DVector;
+use crate::dynamics::statespace::{StateSpace, StateSpaceType, StateSpaceRepresentation};
+use crate::math::integrate::symplectic::SecondOrderPartition;
+use crate::float::Float;
+
+/// Unit mass on a hardening (Duffing) spring driven by a force
+///
+/// \ddot{p}(t) = -p(t) - p(t)^3 + u(t)
+///
+/// The stiffness 1 + 3p^2 grows with the displacement, so every position p is an equilibrium
+/// held by the force u0 = p + p^3.
+///
+/// x = [position0]\
+///     [velocity0]\
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardeningSpring {
+
+    statespace: StateSpace,
+
+}
+
+impl HardeningSpring {
+
+    pub fn new() -> Self {
+
+        let mut statespace = StateSpace::new(2);
+        statespace.add_state(0, StateSpaceType::Position0);
+        statespace.add_state(1, StateSpaceType::Velocity0);
+
+        Self { statespace }
+
+    }
+
+    pub fn statespace(&self) -> &StateSpace { &self.statespace }
+
+    /// Returns the equilibrium state and input (x0, u0) holding the mass at position p
+    pub fn trim(p: Float) -> (DVector<Float>, DVector<Float>) {
+
+        (DVector::from_vec(vec![p, 0.0]), DVector::from_vec(vec![p + p.powi(3)]))
+
+    }
+
+}
+
+impl Default for HardeningSpring {
+
+    fn default() -> Self { Self::new() }
+
+}
+
+impl StateSpaceRepresentation for HardeningSpring {
+
+    fn f(&self, _t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
+
+        let force = u.map_or(0.0, |u| u[0]);
+
+        DVector::from_vec(vec![x[1], -x[0] - x[0].powi(3) + force])
+
+    }
+
+    fn h(&self, _t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DVector<Float> {
+
+        x.clone()
+
+    }
+
+    fn partition(&self) -> Option<SecondOrderPartition> {
+
+        Some(SecondOrderPartition::halves(2))
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_HardeningSpring_trim() {
+
+        let spring = HardeningSpring::new();
+
+        for p in [-1.5, 0.0, 2.0] {
+            let (x0, u0) = HardeningSpring::trim(p);
+            assert_relative_eq!(spring.f(0.0, &x0, Some(&u0)), DVector::<Float>::zeros(2), epsilon = 1E-5);
+
+            // Local stiffness 1 + 3p^2
+            let model = spring.linearize(0.0, &x0, &u0);
+            assert_relative_eq!(model.A[(1, 0)], -1.0 - 3.0 * p * p, epsilon = 1E-2);
+        }
+
+    }
+
+}
@@ -1,48 +1,53 @@
 
-use na::{DMatrix, DVector};
+use na::{Complex, ComplexField, DMatrix, DVector};
 use crate::dynamics::nonlinear_system::{NonlinearStateSpace_fn, NonlinearStateSpaceModel};
 use crate::dynamics::statespace::{StateSpace, StateSpaceType, StateSpaceRepresentation};
+use crate::math::integrate::symplectic::SecondOrderPartition;
+use crate::float::Float;
 
-fn equations_of_motion(_t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+// Pendulum equations in any scalar field, so the model can be evaluated with complex steps
+fn pendulum_equations<N: ComplexField<RealField = Float> + Copy>(x: &DVector<N>, u: N) -> DVector<N> {
 
     // Cart inverted pendulum model params
     // https://link.springer.com/article/10.1007/s11633-014-0818-1
-    let g = 9.81; // gravity
-    let l = 5.0; // length of pendulum
+    let g = N::from_real(9.81); // gravity
+    let l = N::from_real(5.0); // length of pendulum
     // let k = 0.7; // coeff. of friction
-    let m = 3.0; // mass
-    let M = 6.0; // mass of cart
-
-    // Pendulum equations
-    let mut res = DVector::<f32>::zeros(x.len());
+    let m = N::from_real(3.0); // mass
+    let M = N::from_real(6.0); // mass of cart
 
     let x1 = x[0]; // theta
     let x2 = x[1]; // theta_dot
     let _x3 = x[2]; // x
     let x4 = x[3]; // x_dot
 
-    match u {
+    DVector::from_vec(vec![
+        x2,
+        (u*x1.cos() - (M+m)*g*x1.sin() + m*l*(x1.cos() * x1.sin())*x2*x2) /
+            ( m*l*x1.cos()*x1.cos() - (M+m)*l ),
+        x4,
+        (u + m*l*(x1.sin())*x2*x2 - m*g*x1.cos()*x1.sin()) /
+            ( M + m - m*x1.cos()*x1.cos() ),
+    ])
+
+}
 
-        Some(u) => {
-            let u = u[0];
-            res[0] = x2;
-            res[1] = (u*x1.cos() - (M+m)*g*x1.sin() + m*l*(x1.cos() * x1.sin())*x2.powf(2.0)) / 
-                ( m*l*x1.cos().powf(2.0) - (M+m)*l );
-            res[2] = x4;
-            res[3] = (u + m*l*(x1.sin())*x2.powf(2.0) - m*g*x1.cos()*x1.sin()) / 
-                ( M + m - m*x1.cos().powf(2.0) );
-        },
+fn equations_of_motion(_t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
-        None => println!("no control input provided")
+    match u {
 
-    };
+        Some(u) => pendulum_equations(x, u[0]),
 
-    res
+        None => {
+            println!("no control input provided");
+            DVector::<Float>::zeros(x.len())
+        }
 
+    }
 
 }
 
-fn output_equations(_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>) -> DVector<f32> {
+fn output_equations(_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DVector<Float> {
 
     let C = DMatrix::from_row_slice(2, 4,
             &[1., 0., 0., 0.,
@@ -107,24 +112,39 @@ impl InvertedPendulum {
 
 impl StateSpaceRepresentation for InvertedPendulum {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.f(t, x, u)
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         self.dynamics.h(t, x, u)
 
     }
+
+    fn f_complex(&self, _t: Float, x: &DVector<Complex<Float>>, u: Option<&DVector<Complex<Float>>>) -> Option<DVector<Complex<Float>>> {
+
+        u.map(|u| pendulum_equations(x, u[0]))
+
+    }
+
+    // Cart position and rod angle are each followed by their rate
+    fn partition(&self) -> Option<SecondOrderPartition> {
+
+        Some(SecondOrderPartition::interleaved(4))
+
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::math::integrate::runge_kutta::RK45;
-    use std::f32::consts::FRAC_PI_4;
+    use crate::math::integrate::SolverOptions;
+    use crate::float::consts::FRAC_PI_4;
+    use crate::controls::models::LinearQuadraticRegulator;
 
     #[test]
     fn test_InvertedPendulum() {
@@ -132,23 +152,23 @@ mod tests {
         let pendulum = InvertedPendulum::new();
 
         // initial conditions
-        let x0 = DVector::<f32>::from_vec(vec![FRAC_PI_4, -1.0, 0.0, 0.0]); // omega, omega_dot
+        let x0 = DVector::<Float>::from_vec(vec![FRAC_PI_4, -1.0, 0.0, 0.0]); // omega, omega_dot
         let t0 = 0.0;
 
         // Integrate the dynamics
 
         // Wrap model in appropriately defined closure for integrator (ie. f(t,x))
-        let dynamics = |t: f32, x: &DVector<f32>| {
+        let dynamics = |t: Float, x: &DVector<Float>| {
             // Some constant control input
-            let u = DVector::<f32>::from_vec(vec![0.0]);
+            let u = DVector::<Float>::from_vec(vec![0.0]);
             pendulum.f(t, x, Some(&u))
         };
 
         let tf = 10.0;
         let n = 100.0;
         let step = (tf - t0) / n;
-        let rtol = 1E-3;
-        let (_t, y) = RK45(dynamics, t0, x0, tf, step, rtol);
+        let opts = SolverOptions{ rtol: 1E-3, ..SolverOptions::default() };
+        let y = RK45(dynamics, t0, x0, tf, step, &opts).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
@@ -156,4 +176,58 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_InvertedPendulum_jacobian() {
+
+        use crate::math::jacobian::finite_difference;
+
+        // Complex-step Jacobians agree with finite differences of the real model away from equilibrium
+        let pendulum = InvertedPendulum::new();
+        let x = DVector::from_vec(vec![0.3, -0.2, 1.0, 0.5]);
+        let u = DVector::from_vec(vec![2.0]);
+
+        let A = finite_difference(|x: &DVector<Float>| pendulum.f(0.0, x, Some(&u)), &x);
+        let B = finite_difference(|u: &DVector<Float>| pendulum.f(0.0, &x, Some(u)), &u);
+
+        assert_relative_eq!(pendulum.jacobian(0.0, &x, Some(&u)), A, epsilon = 1E-3);
+        assert_relative_eq!(pendulum.input_jacobian(0.0, &x, &u), B, epsilon = 1E-3);
+
+    }
+
+    #[test]
+    fn test_InvertedPendulum_linearize() {
+
+        let pendulum = InvertedPendulum::new();
+
+        // Linearized about the upright equilibrium
+        let x0 = DVector::<Float>::zeros(4);
+        let u0 = DVector::<Float>::zeros(1);
+        let model = pendulum.linearize(0.0, &x0, &u0);
+
+        // theta_ddot = (M+m)g/(Ml) theta - u/(Ml), x_ddot = -mg/M theta + u/M
+        let A_true = DMatrix::from_row_slice(4, 4, &[
+            0., 1., 0., 0.,
+            9.0*9.81/30.0, 0., 0., 0.,
+            0., 0., 0., 1.,
+            -3.0*9.81/6.0, 0., 0., 0.
+        ]);
+        let B_true = DMatrix::from_row_slice(4, 1, &[0., -1.0/30.0, 0., 1.0/6.0]);
+        let C_true = DMatrix::from_row_slice(2, 4, &[1., 0., 0., 0., 0., 0., 1., 0.]);
+
+        // A and B are complex-step derivatives, exact to round-off
+        assert_relative_eq!(model.A, A_true, epsilon = 1E-5);
+        assert_relative_eq!(model.B, B_true, epsilon = 1E-5);
+        assert_relative_eq!(model.C, C_true, epsilon = 1E-3);
+        assert_relative_eq!(model.D, DMatrix::<Float>::zeros(2, 1), epsilon = 1E-3);
+
+        // The linearization supports an LQR design that stabilizes the upright equilibrium
+        let Q = DMatrix::<Float>::identity(4, 4);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+        let controller = LinearQuadraticRegulator::new(model.A.clone(), model.B.clone(), Q, R).unwrap();
+        let K = controller.gain();
+        let eigenvalues = (&model.A - &model.B * K).complex_eigenvalues();
+        assert!(eigenvalues.iter().all(|lambda| lambda.re < 0.0));
+
+    }
+
 }
@@ -1,3 +1,4 @@
 pub mod inverted_pendulum;
 pub mod double_pendulum;
 pub mod clohessy_wiltshire;
+pub mod hardening_spring;
@@ -2,12 +2,15 @@
 use na::DVector;
 use crate::dynamics::statespace::StateSpaceRepresentation;
 use crate::dynamics::closed_form::ClosedFormSolution;
+use crate::math::integrate::{IntegrateError, IntegratorType, SolverOptions};
+use crate::math::integrate::variational::{solve_ivp_variational, VariationalResult};
+use crate::float::Float;
 
 /// An alias for function pointer type that satisfy NonlinearSystem trait bounds
-pub type NonlinearStateSpace_fn = fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>;
+pub type NonlinearStateSpace_fn = fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>;
 
 /// An alias for function pointer types that satisfy NonlinearExpression trait bounds
-pub type NonlinearExpression_fn = fn(f32, &DVector<f32>) -> DVector<f32>;
+pub type NonlinearExpression_fn = fn(Float, &DVector<Float>) -> DVector<Float>;
 
 
 // https://stackoverflow.com/questions/27831944/how-do-i-store-a-closure-in-a-struct-in-rust
@@ -25,13 +28,14 @@ pub type NonlinearExpression_fn = fn(f32, &DVector<f32>) -> DVector<f32>;
 ///
 /// ```
 /// use nalgebra::DVector;
+/// use mads::float::Float;
 /// use mads::dynamics::nonlinear_system::*;
 ///
-/// fn equations_of_motion(t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+/// fn equations_of_motion(t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 ///     (x*x).clone()
 /// }
 ///
-/// fn output_equations(t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+/// fn output_equations(t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 ///     x.clone()
 /// }
 ///
@@ -46,8 +50,8 @@ pub type NonlinearExpression_fn = fn(f32, &DVector<f32>) -> DVector<f32>;
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct NonlinearStateSpaceModel<F, H>
 where
-    F: Fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>,
-    H: Fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>,
+    H: Fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>,
 {
 
     state_equation: F,
@@ -59,8 +63,8 @@ where
 
 impl<F, H> NonlinearStateSpaceModel<F, H>
 where
-    F: Fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>,
-    H: Fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>,
+    H: Fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>,
 {
     pub fn new(f: F, h: H, dx: usize, du: usize) -> Self
     {
@@ -69,21 +73,42 @@ where
 
     }
 
+    /// Propagates the state and its state transition matrix Φ(t, t0) over t_span
+    ///
+    /// The variational equations are driven by the Jacobian of the model, which is approximated
+    /// with central finite differences of the state equations
+    pub fn state_transition_matrix(
+        &self,
+        t_span: (Float, Float),
+        x0: DVector<Float>,
+        u: Option<&DVector<Float>>,
+        method: IntegratorType,
+        options: SolverOptions
+    ) -> Result<VariationalResult, IntegrateError>
+    {
+
+        let f = |t: Float, x: &DVector<Float>| self.f(t, x, u);
+        let jacobian = |t: Float, x: &DVector<Float>| self.jacobian(t, x, u);
+
+        solve_ivp_variational(f, jacobian, t_span, x0, method, options)
+
+    }
+
 }
 
 impl<F, H> StateSpaceRepresentation for NonlinearStateSpaceModel<F, H>
 where
-    F: Fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>,
-    H: Fn(f32, &DVector<f32>, Option<&DVector<f32>>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>,
+    H: Fn(Float, &DVector<Float>, Option<&DVector<Float>>) -> DVector<Float>,
 {
 
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
         (self.state_equation)(t, x, u) // syntax for calling the function of a function-typed field
 
     }
 
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32> {
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
 
 
         (self.output_equation)(t, x, u)
@@ -102,9 +127,10 @@ where
 ///
 /// ```
 /// use nalgebra::DVector;
+/// use mads::float::Float;
 /// use mads::dynamics::nonlinear_system::*;
 ///
-/// fn closed_form_solution(t: f32, x: &DVector<f32>) -> DVector<f32> {
+/// fn closed_form_solution(t: Float, x: &DVector<Float>) -> DVector<Float> {
 ///     x.clone()
 /// }
 ///
@@ -113,7 +139,7 @@ where
 ///
 pub struct NonlinearExpression<F>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>
 {
 
     expression: F
@@ -122,7 +148,7 @@ where
 
 impl<F> NonlinearExpression<F>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>
 {
 
     pub fn new(f: F) -> Self {
@@ -135,10 +161,10 @@ where
 
 impl<F> ClosedFormSolution for NonlinearExpression<F>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>
 {
 
-    fn rhs(&self, t: f32, x: &DVector<f32>) -> DVector<f32> {
+    fn rhs(&self, t: Float, x: &DVector<Float>) -> DVector<Float> {
 
         (self.expression)(t, x)
 
@@ -157,8 +183,9 @@ mod tests {
     #[test]
     fn test_NonlinearSystem() {
 
-        use std::f32::consts as consts;
+        use crate::float::consts;
         use crate::math::integrate::runge_kutta::RK45;
+        use crate::math::integrate::SolverOptions;
 
         // Pendulum model params
         // https://ctms.engin.umich.edu/CTMS/index.php?aux=Activities_Pendulum
@@ -169,9 +196,9 @@ mod tests {
 
         // Pendulum equations
         // Wrap equations of motion / state equations in appropriately defined closure for _model
-        let f = |_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>| {
+        let f = |_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>| {
 
-            let mut res = DVector::<f32>::zeros(x.len());
+            let mut res = DVector::<Float>::zeros(x.len());
 
             res[0] = x[1];
             res[1] = -(g/l)*x[0].sin() - (k/(m*l))*x[1].sin();
@@ -180,14 +207,14 @@ mod tests {
         };
 
         // Wrap output equation in appropriately defined closure for _model
-        let h = |_t: f32, x: &DVector<f32>, _u: Option<&DVector<f32>>| {
+        let h = |_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>| {
             x.clone()
         };
 
         let _model = NonlinearStateSpaceModel::new(f, h, 2, 1);
 
         // initial conditions
-        let x0 = DVector::<f32>::from_vec(vec![consts::PI/4.0, -1.0]); // omega, omega_dot
+        let x0 = DVector::<Float>::from_vec(vec![consts::PI/4.0, -1.0]); // omega, omega_dot
         let t0 = 0.0;
 
         // println!("{:?}", _model.f(t0, &x0, None));
@@ -196,15 +223,15 @@ mod tests {
         // Integrate the dynamics
 
         // Wrap model in appropriately defined closure for integrator (ie. f(t,x))
-        let dynamics = |t: f32, x: &DVector<f32>| {
+        let dynamics = |t: Float, x: &DVector<Float>| {
             _model.f(t, x, None)
         };
 
         let tf = 10.0;
         let n = 100.0;
         let step = (tf - t0) / n;
-        let rtol = 1E-3;
-        let (_t, y) = RK45(dynamics, t0, x0, tf, step, rtol);
+        let opts = SolverOptions{ rtol: 1E-3, ..SolverOptions::default() };
+        let y = RK45(dynamics, t0, x0, tf, step, &opts).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
@@ -212,6 +239,43 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_NonlinearSystem_state_transition_matrix() {
+
+        use na::DMatrix;
+
+        // Clohessy-Wiltshire equations of motion
+        let n = 0.00113;
+        let f = |_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>| {
+            DVector::from_vec(vec![
+                x[3],
+                x[4],
+                x[5],
+                3.*n*n*x[0] + 2.*n*x[4],
+                -2.*n*x[3],
+                -n*n*x[2]
+            ])
+        };
+        let h = |_t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>| x.clone();
+
+        let model = NonlinearStateSpaceModel::new(f, h, 6, 0);
+
+        let t_span = (0.0, 1000.0);
+        let x0 = DVector::from_vec(vec![0., 0., 0., 0.1, 0., 0.]);
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-6, ..SolverOptions::default() };
+        let result = model.state_transition_matrix(t_span, x0, None, IntegratorType::DOPRI5, opts).unwrap();
+
+        // The equations are linear, so each column of Φ is the solution from a unit initial state
+        let columns: Vec<DVector<Float>> = (0..6)
+            .map(|j| ClohessyWiltshireSolution(t_span.1, &DVector::from_fn(6, |i, _| if i == j { 1.0 } else { 0.0 })))
+            .collect();
+        let exact = DMatrix::from_columns(&columns);
+
+        let phi = &result.phi[result.phi.len()-1];
+        assert!((phi - &exact).amax() < 1E-5 * exact.amax());
+
+    }
+
     #[test]
     fn test_NonlinearExpression() {
 
@@ -222,7 +286,7 @@ mod tests {
         let mut traj = Vec::new();
         for i in 0..100 {
 
-            let t = dt*i as f32;
+            let t = dt*i as Float;
             let test = closed_form.rhs(t, &x0);
 
             traj.push(test);
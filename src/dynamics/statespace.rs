@@ -1,7 +1,11 @@
 
-use na::DVector;
+use na::{Complex, DMatrix, DVector};
 use std::collections::HashMap;
 use serde::Serialize;
+use crate::dynamics::linear_system::LTISystem;
+use crate::math::jacobian::{complex_step, finite_difference};
+use crate::math::integrate::symplectic::SecondOrderPartition;
+use crate::float::Float;
 
 /// Defines an interface for solving systems of first-order ODEs according to the state-space model
 ///
@@ -9,8 +13,84 @@ use serde::Serialize;
 /// y(t) = h(t, x(t), u(t))
 ///
 pub trait StateSpaceRepresentation {
-    fn f(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32>;
-    fn h(&self, t: f32, x: &DVector<f32>, u: Option<&DVector<f32>>) -> DVector<f32>;
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float>;
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float>;
+
+    /// State equations evaluated in complex arithmetic
+    ///
+    /// None unless overridden by the model, in which case the default Jacobians are computed with
+    /// complex steps, accurate to machine precision, rather than central finite differences
+    fn f_complex(&self, _t: Float, _x: &DVector<Complex<Float>>, _u: Option<&DVector<Complex<Float>>>) -> Option<DVector<Complex<Float>>> {
+        None
+    }
+
+    /// Jacobian of the state equations with respect to the state, df/dx
+    ///
+    /// Complex-step derivative if the model provides f_complex, otherwise approximated with
+    /// central finite differences, unless overridden by the model
+    fn jacobian(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DMatrix<Float> {
+        let u_complex = u.map(|u| u.map(|u| Complex::new(u, 0.0)));
+
+        complex_step(|x: &DVector<Complex<Float>>| self.f_complex(t, x, u_complex.as_ref()), x)
+            .unwrap_or_else(|| finite_difference(|x: &DVector<Float>| self.f(t, x, u), x))
+    }
+
+    /// Jacobian of the state equations with respect to the input, df/du
+    ///
+    /// Complex-step derivative if the model provides f_complex, otherwise approximated with
+    /// central finite differences, unless overridden by the model
+    fn input_jacobian(&self, t: Float, x: &DVector<Float>, u: &DVector<Float>) -> DMatrix<Float> {
+        let x_complex = x.map(|x| Complex::new(x, 0.0));
+
+        complex_step(|u: &DVector<Complex<Float>>| self.f_complex(t, &x_complex, Some(u)), u)
+            .unwrap_or_else(|| finite_difference(|u: &DVector<Float>| self.f(t, x, Some(u)), u))
+    }
+
+    /// Linearizes the model about the operating point (x0, u0) at time t
+    ///
+    /// The model is returned in the deviation coordinates dx = x - x0, du = u - u0 and
+    /// dy = y - h(t, x0, u0)
+    ///
+    /// dx_dot = A dx + B du \
+    /// dy = C dx + D du
+    ///
+    /// The affine term f(t, x0, u0) is dropped, so the linearization is only valid about an
+    /// equilibrium, f(t, x0, u0) = 0. The caller keeps the trim point (x0, u0) to shift states and
+    /// inputs into and out of deviation coordinates.
+    ///
+    /// A and B are the model Jacobians, so models providing exact or complex-step Jacobians
+    /// linearize to machine precision. C and D are approximated with central finite differences of
+    /// the output equations.
+    fn linearize(&self, t: Float, x0: &DVector<Float>, u0: &DVector<Float>) -> LTISystem {
+        let A = self.jacobian(t, x0, Some(u0));
+        let B = self.input_jacobian(t, x0, u0);
+        let C = finite_difference(|x: &DVector<Float>| self.h(t, x, Some(u0)), x0);
+        let D = finite_difference(|u: &DVector<Float>| self.h(t, x0, Some(u)), u0);
+
+        LTISystem::new(A, B, C, D)
+    }
+
+    /// Position and velocity components of the state for symplectic integrators
+    ///
+    /// None unless overridden by the model, in which case the state is taken to be ordered as
+    /// [positions, velocities]
+    fn partition(&self) -> Option<SecondOrderPartition> {
+        None
+    }
+}
+
+/// Defines an interface for advancing systems of first-order difference equations according to
+/// the discrete-time state-space model
+///
+/// x[k+1] = f(k, x[k], u[k]) \
+/// y[k] = h(k, x[k], u[k])
+///
+pub trait DiscreteStateSpaceRepresentation {
+    fn f(&self, k: usize, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float>;
+    fn h(&self, k: usize, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float>;
+
+    /// Sample period of the system
+    fn dt(&self) -> Float;
 }
 
 
@@ -1,10 +1,12 @@
 
 use std::fmt;
-use nalgebra::DVector;
+use nalgebra::{DMatrix, DVector};
 use uuid::Uuid;
 use serde::Serialize;
 use crate::dynamics::statespace::StateSpace;
 use crate::math::frames::ReferenceFrame;
+use crate::math::integrate::IntegrateError;
+use crate::float::Float;
 
 /// Define reference frame for an entity
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
@@ -15,7 +17,55 @@ pub struct EntityFrame {
 /// Assigns a Dynamic Vector (see nalgebra) to an Entity
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct FullState {
-    pub data: DVector<f32>,
+    pub data: DVector<Float>,
+}
+
+/// Records the states of an Entity at evenly spaced times within each engine step
+///
+/// samples: number of evenly spaced intervals each engine step is divided into \
+/// times: time of each recorded state \
+/// data: recorded states \
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SubstepTrajectory {
+    pub samples: usize,
+    pub times: Vec<Float>,
+    pub data: Vec<DVector<Float>>,
+}
+
+impl SubstepTrajectory {
+
+    pub fn new(samples: usize) -> Self {
+
+        assert!(samples > 0);
+
+        Self { samples, times: Vec::new(), data: Vec::new() }
+
+    }
+
+    /// Returns evenly spaced times spanning [t0, tf], including both end points
+    pub fn sample_times(&self, t0: Float, tf: Float) -> Vec<Float> {
+
+        let dt = (tf - t0) / self.samples as Float;
+        let mut times: Vec<Float> = (0..self.samples).map(|k| t0 + dt * k as Float).collect();
+        times.push(tf);
+
+        times
+
+    }
+
+    /// Appends a sampled trajectory, skipping the first state if it was already recorded
+    pub fn record(&mut self, times: Vec<Float>, data: Vec<DVector<Float>>) {
+
+        let skip = match (self.times.last(), times.first()) {
+            (Some(last), Some(first)) => (last == first) as usize,
+            _ => 0,
+        };
+
+        self.times.extend(times.into_iter().skip(skip));
+        self.data.extend(data.into_iter().skip(skip));
+
+    }
+
 }
 
 /// Assigns a unique identifier and name to an Entity
@@ -58,6 +108,17 @@ pub struct WaypointFlag(pub bool);
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct LoggableFlag(pub bool);
 
+/// Flags an Entity whose dynamics failed to integrate
+/// Flagged entities hold their last valid state and are no longer integrated
+///
+/// time: start of the engine step in which the integration failed \
+/// error: error returned by the integrator \
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntegrationFailure {
+    pub time: Float,
+    pub error: IntegrateError,
+}
+
 
 // DYNAMICS
 
@@ -68,8 +129,101 @@ pub type DoubleIntegrator3DComponent = crate::dynamics::models::DoubleIntegrator
 pub type NonlinearInvertedPendulumComponent = crate::dynamics::models::NonlinearInvertedPendulum;
 pub type DoublePendulumComponent = crate::dynamics::models::DoublePendulum;
 pub type ClohessyWiltshireComponent = crate::dynamics::models::ClohessyWiltshire;
+pub type DiscreteLTIComponent = crate::dynamics::linear_system::DiscreteLTISystem;
 
 // CONTROLLERS
 
 pub type LQRComponent = crate::controls::models::LinearQuadraticRegulator;
+pub type DLQRComponent = crate::controls::models::DiscreteLinearQuadraticRegulator;
+pub type FiniteHorizonLQRComponent = crate::controls::models::FiniteHorizonLinearQuadraticRegulator;
+pub type PIDComponent = crate::controls::models::PIDController;
+pub type MPCComponent = crate::controls::models::ModelPredictiveController;
+pub type ILQRComponent<T> = crate::controls::models::IterativeLinearQuadraticRegulator<T>;
+pub type GainScheduleComponent = crate::controls::models::GainScheduledController;
+pub type PolePlacementComponent = crate::controls::models::PolePlacementController;
+
+/// Desired state and feedforward input tracked by an Entity's controller
+/// A setpoint is a reference with a single sample. Time-varying references are linearly
+/// interpolated between samples and held constant outside of them
+///
+/// times: increasing times of the reference samples \
+/// states: reference state x_ref at each time \
+/// inputs: optional feedforward input u_ref at each time \
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub times: Vec<Float>,
+    pub states: Vec<DVector<Float>>,
+    pub inputs: Option<Vec<DVector<Float>>>,
+}
+
+impl Reference {
+
+    pub fn setpoint(state: DVector<Float>, input: Option<DVector<Float>>) -> Self {
 
+        Self { times: vec![0.0], states: vec![state], inputs: input.map(|input| vec![input]) }
+
+    }
+
+    pub fn trajectory(times: Vec<Float>, states: Vec<DVector<Float>>, inputs: Option<Vec<DVector<Float>>>) -> Self {
+
+        assert!(!times.is_empty());
+        assert_eq!(times.len(), states.len());
+        assert!(times.windows(2).all(|w| w[0] < w[1]));
+        if let Some(inputs) = &inputs {
+            assert_eq!(times.len(), inputs.len());
+        }
+
+        Self { times, states, inputs }
+
+    }
+
+    /// Returns the reference state and feedforward input at time t
+    pub fn sample(&self, t: Float) -> (DVector<Float>, Option<DVector<Float>>) {
+
+        let last = self.times.len() - 1;
+        let (i, alpha) = if t <= self.times[0] {
+            (0, 0.0)
+        } else if t >= self.times[last] {
+            (last, 0.0)
+        } else {
+            let i = self.times.partition_point(|ti| *ti <= t) - 1;
+            (i, (t - self.times[i]) / (self.times[i + 1] - self.times[i]))
+        };
+
+        let interpolate = |values: &Vec<DVector<Float>>| match alpha > 0.0 {
+            true => &values[i] * (1.0 - alpha) + &values[i + 1] * alpha,
+            false => values[i].clone(),
+        };
+
+        (interpolate(&self.states), self.inputs.as_ref().map(interpolate))
+
+    }
+
+}
+
+/// Integral action added to a tracking controller
+/// u = -K(x - x_ref) + u_ref - Ki z, where z_dot = C(x - x_ref)
+///
+/// gain: integral gain Ki \
+/// output: matrix C selecting the tracking errors to integrate \
+/// error: accumulated integral z of the tracking error \
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntegralAction {
+    pub gain: DMatrix<Float>,
+    pub output: DMatrix<Float>,
+    pub error: DVector<Float>,
+}
+
+impl IntegralAction {
+
+    pub fn new(gain: DMatrix<Float>, output: DMatrix<Float>) -> Self {
+
+        assert_eq!(gain.shape().1, output.shape().0);
+
+        let error = DVector::<Float>::zeros(output.shape().0);
+
+        Self { gain, output, error }
+
+    }
+
+}
@@ -1,25 +1,28 @@
 
 use std::collections::HashMap;
+use std::sync::Mutex;
+use legion::Entity;
 use uuid::Uuid;
 use serde::Serialize;
 use crate::ecs::components::*;
 use crate::math::integrate::IntegratorType;
 use crate::math::frames::ReferenceFrame;
+use crate::float::Float;
 
 // Define Engine resources for Legion Entity-Component-System
 
 /// Current simulation time
 #[derive(Default)]
-pub struct SimulationTime(pub f32);
+pub struct SimulationTime(pub Float);
 
 /// Simulation time history
 #[derive(Default, Debug)]
 pub struct SimulationTimeHistory {
-    pub data: Vec<f32>
+    pub data: Vec<Float>
 }
 
 /// Max simulation time
-pub struct MaxSimulationTime(pub f32);
+pub struct MaxSimulationTime(pub Float);
 
 impl Default for MaxSimulationTime {
 
@@ -30,7 +33,7 @@ impl Default for MaxSimulationTime {
 }
 
 /// Simulation engine step size
-pub struct EngineStep(pub f32);
+pub struct EngineStep(pub Float);
 
 impl Default for EngineStep {
 
@@ -41,7 +44,7 @@ impl Default for EngineStep {
 }
 
 /// Integrator step size (ie. dynamics integrations)
-pub struct IntegratorStep(pub f32);
+pub struct IntegratorStep(pub Float);
 
 impl Default for IntegratorStep {
 
@@ -66,6 +69,13 @@ pub struct SimulationResult {
     pub data: HashMap<SimID, Vec<FullState>>
 }
 
+/// Integration failures reported by the dynamics systems during an engine step
+/// Systems iterate over entities in parallel, so reports are collected behind a Mutex
+#[derive(Default, Debug)]
+pub struct IntegrationFailures {
+    pub data: Mutex<Vec<(Entity, IntegrationFailure)>>
+}
+
 /// Define an inertial reference frame for the World
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct WorldFrame {
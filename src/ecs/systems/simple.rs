@@ -42,11 +42,11 @@ pub fn update_result(id: &SimID, state: &FullState, #[resource] storage: &mut Si
 
     // Access SimulationResult data and match against uuid keys
     match storage.data.entry(id.clone()) {
-        // No previous entry for uuid: insert new Vector of DVector<f32>
+        // No previous entry for uuid: insert new Vector of DVector<Float>
         std::collections::hash_map::Entry::Vacant(e) => {
             e.insert(vec![state.clone()]);
         },
-        // Previous entry for uuid: push DVector<f32> to existing Vector
+        // Previous entry for uuid: push DVector<Float> to existing Vector
         std::collections::hash_map::Entry::Occupied(mut e) => {
             e.get_mut().push(state.clone());
         }
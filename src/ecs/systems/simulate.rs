@@ -1,39 +1,48 @@
 
-use nalgebra::{DMatrix, DVector};
+use nalgebra::DVector;
 use legion::*;
 use legion::storage::Component;
 use thiserror::Error;
-use crate::dynamics::statespace::StateSpaceRepresentation;
+use crate::dynamics::statespace::{StateSpaceRepresentation, DiscreteStateSpaceRepresentation};
 use crate::dynamics::closed_form::ClosedFormSolution;
+use crate::controls::controller::Controller;
 use crate::math::integrate::{solve_ivp, SolverOptions, IntegrateError};
+use crate::math::integrate::symplectic::SecondOrderPartition;
 use crate::ecs::resources::*;
 use crate::ecs::components::*;
+use crate::float::Float;
 
 // NOTE: to parallelize with Rayon, use par_for_each
 // #[system(for_each)]
+// Entities with a finite-horizon LQR evaluate the interpolated gain K(t) throughout the engine step
+// Entities with a pole placement controller are driven by its gain in place of an LQR gain
+// Entities with a Reference are driven by integrate_lqr_tracking_dynamics instead, and entities
+// with an LQR, finite-horizon LQR or pole placement controller are excluded from
+// integrate_controlled_dynamics
 #[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>() & !component::<Reference>() & (component::<LQRComponent>() | component::<FiniteHorizonLQRComponent>() | component::<PolePlacementComponent>()))]
+#[allow(clippy::too_many_arguments)]
 pub fn integrate_lqr_dynamics<T>(
+    entity: &Entity,
     state: &mut FullState,
     dynamics: &T,
-    controller: &LQRComponent,
+    controller: Option<&LQRComponent>,
+    finite_horizon: Option<&FiniteHorizonLQRComponent>,
+    pole_placement: Option<&PolePlacementComponent>,
+    substeps: Option<&mut SubstepTrajectory>,
     #[resource] time: &SimulationTime,
     #[resource] sim_step: &EngineStep,
     #[resource] integrator: &Integrator,
-    #[resource] step: &IntegratorStep
-) -> Result<(), IntegrateError>
+    #[resource] step: &IntegratorStep,
+    #[resource] failures: &IntegrationFailures
+)
 where
     T: Component + StateSpaceRepresentation // Need to include Component trait from Legion
 {
 
     // Define initial conditions
     let x0 = state.data.clone();
-    let mut trajectory: Vec<DVector<f32>> = vec![x0.clone()];
-
-    // Solve the LQR controller
-    let (K, _P) = match controller.solve() {
-        Ok((value1, value2)) => (value1, value2),
-        Err(_) => (DMatrix::<f32>::zeros(1, 1), DMatrix::<f32>::zeros(1, 1)),
-    };
+    let mut trajectory: Vec<DVector<Float>> = vec![x0.clone()];
 
     // Parameters
     let dt = sim_step.0;
@@ -43,19 +52,43 @@ where
     let t_span = (t0, tf);
     let rtol = 1E-3;
 
+    // LQR or pole placement gain
+    let K = controller.map(|controller| controller.gain())
+        .or_else(|| pole_placement.map(|controller| controller.gain()));
+
     // Wrap dynamics/controls in appropriately defined closure - f(t, x)
-    let f = |t: f32, x: &DVector<f32>| {
-        let u = -&K * x;
-        dynamics.f(t, x, Some(&u))
+    let f = |t: Float, x: &DVector<Float>| {
+        let u = match (finite_horizon, K) {
+            (Some(finite_horizon), _) => Some(-finite_horizon.gain(t) * x),
+            (None, Some(K)) => Some(-K * x),
+            (None, None) => None,
+        };
+        dynamics.f(t, x, u.as_ref())
     };
 
+    // Sample the solution within the engine step if the entity records its trajectory
+    let t_eval = substeps.as_ref().map(|substeps| substeps.sample_times(t0, tf));
+
     // Integrate dynamics
-    let opts = SolverOptions{ first_step: Some(step), rtol, ..SolverOptions::default() };
-    let (_times, traj) = solve_ivp(f, t_span, x0, integrator.0, opts)?;
+    let opts = SolverOptions{ first_step: Some(step), rtol, t_eval, partition: dynamics.partition(), ..SolverOptions::default() };
+    // Report failures to the Simulator, holding the last valid state
+    let result = match solve_ivp(f, t_span, x0, integrator.0, opts) {
+        Ok(result) => result,
+        Err(error) => {
+            let failure = IntegrationFailure { time: t0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+            return;
+        }
+    };
+    let traj = result.y;
 
     // Update entity FullState component
     state.data = traj[traj.len()-1].clone();
 
+    if let Some(substeps) = substeps {
+        substeps.record(result.t, traj.clone());
+    }
+
     // Store result
     for state in traj {
         trajectory.push(state);
@@ -68,7 +101,281 @@ where
 
     // }
 
-    Ok(())
+}
+
+
+// Integrates entities tracking a Reference with an LQR or pole placement controller
+// u = -K(t)(x - x_ref(t)) + u_ref(t), with optional integral action on the tracking error. The
+// integral of the error is integrated alongside the state and carried between engine steps
+// Entities with an LQR, finite-horizon LQR or pole placement controller are excluded from
+// integrate_controlled_dynamics
+// NOTE: to parallelize with Rayon, use par_for_each
+// #[system(for_each)]
+#[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>() & (component::<LQRComponent>() | component::<FiniteHorizonLQRComponent>() | component::<PolePlacementComponent>()))]
+#[allow(clippy::too_many_arguments)]
+pub fn integrate_lqr_tracking_dynamics<T>(
+    entity: &Entity,
+    state: &mut FullState,
+    dynamics: &T,
+    controller: Option<&LQRComponent>,
+    finite_horizon: Option<&FiniteHorizonLQRComponent>,
+    pole_placement: Option<&PolePlacementComponent>,
+    reference: &Reference,
+    integral: Option<&mut IntegralAction>,
+    substeps: Option<&mut SubstepTrajectory>,
+    #[resource] time: &SimulationTime,
+    #[resource] sim_step: &EngineStep,
+    #[resource] integrator: &Integrator,
+    #[resource] step: &IntegratorStep,
+    #[resource] failures: &IntegrationFailures
+)
+where
+    T: Component + StateSpaceRepresentation // Need to include Component trait from Legion
+{
+
+    let dx = state.data.len();
+
+    // Augment the state with the integral of the tracking error
+    let x0 = match &integral {
+        Some(integral) => DVector::from_iterator(
+            dx + integral.error.len(),
+            state.data.iter().chain(integral.error.iter()).cloned()
+        ),
+        None => state.data.clone(),
+    };
+
+    // Parameters
+    let dt = sim_step.0;
+    let step = step.0;
+    let t0 = time.0;
+    let tf = time.0 + dt;
+    let t_span = (t0, tf);
+    let rtol = 1E-3;
+
+    // LQR or pole placement gain
+    let K = controller.map(|controller| controller.gain())
+        .or_else(|| pole_placement.map(|controller| controller.gain()));
+
+    // Wrap dynamics/controls in appropriately defined closure - f(t, x)
+    let f = |t: Float, y: &DVector<Float>| {
+        let x = y.rows(0, dx).clone_owned();
+        let (x_ref, u_ref) = reference.sample(t);
+        let error = &x - &x_ref;
+
+        let mut u = match (finite_horizon, K) {
+            (Some(finite_horizon), _) => -finite_horizon.gain(t) * &error,
+            (None, Some(K)) => -K * &error,
+            // Excluded by the system filter
+            (None, None) => unreachable!("tracking entity without a controller"),
+        };
+        if let Some(u_ref) = u_ref {
+            u += u_ref;
+        }
+
+        match &integral {
+            Some(integral) => {
+                u -= &integral.gain * y.rows(dx, integral.error.len());
+                let x_dot = dynamics.f(t, &x, Some(&u));
+                let z_dot = &integral.output * error;
+                DVector::from_iterator(y.len(), x_dot.iter().chain(z_dot.iter()).cloned())
+            },
+            None => dynamics.f(t, &x, Some(&u)),
+        }
+    };
+
+    // Sample the solution within the engine step if the entity records its trajectory
+    let t_eval = substeps.as_ref().map(|substeps| substeps.sample_times(t0, tf));
+
+    // Integrate dynamics
+    // The partition of the model does not cover the integral of the error, which symplectic
+    // integrators reject
+    let partition = dynamics.partition().unwrap_or_else(|| SecondOrderPartition::halves(dx));
+    let opts = SolverOptions{ first_step: Some(step), rtol, t_eval, partition: Some(partition), ..SolverOptions::default() };
+    // Report failures to the Simulator, holding the last valid state
+    let result = match solve_ivp(f, t_span, x0, integrator.0, opts) {
+        Ok(result) => result,
+        Err(error) => {
+            let failure = IntegrationFailure { time: t0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+            return;
+        }
+    };
+    let y = &result.y[result.y.len()-1];
+
+    // Update entity FullState and integral action components
+    state.data = y.rows(0, dx).clone_owned();
+    if let Some(integral) = integral {
+        integral.error = y.rows(dx, y.len() - dx).clone_owned();
+    }
+
+    // Record the entity state, without the integral of the error
+    if let Some(substeps) = substeps {
+        let traj = result.y.iter().map(|y| y.rows(0, dx).clone_owned()).collect();
+        substeps.record(result.t, traj);
+    }
+
+}
+
+
+// Integrates entities driven by any Controller component
+// The controller is updated from the sampled state at the start of the engine step, then its input
+// u = control(t, x, x_ref) + u_ref is evaluated throughout the step. Entities without a Reference
+// are regulated to the origin
+// Entities with an LQR, finite-horizon LQR or pole placement controller are driven by
+// integrate_lqr_dynamics and integrate_lqr_tracking_dynamics and excluded here, so an entity is
+// integrated once per engine step whichever systems are registered
+// NOTE: to parallelize with Rayon, use par_for_each
+// #[system(for_each)]
+#[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>() & !component::<LQRComponent>() & !component::<FiniteHorizonLQRComponent>() & !component::<PolePlacementComponent>())]
+#[allow(clippy::too_many_arguments)]
+pub fn integrate_controlled_dynamics<T, C>(
+    entity: &Entity,
+    state: &mut FullState,
+    dynamics: &T,
+    controller: &mut C,
+    reference: Option<&Reference>,
+    substeps: Option<&mut SubstepTrajectory>,
+    #[resource] time: &SimulationTime,
+    #[resource] sim_step: &EngineStep,
+    #[resource] integrator: &Integrator,
+    #[resource] step: &IntegratorStep,
+    #[resource] failures: &IntegrationFailures
+)
+where
+    T: Component + StateSpaceRepresentation, // Need to include Component trait from Legion
+    C: Component + Controller
+{
+
+    // Define initial conditions
+    let x0 = state.data.clone();
+
+    // Parameters
+    let dt = sim_step.0;
+    let step = step.0;
+    let t0 = time.0;
+    let tf = time.0 + dt;
+    let t_span = (t0, tf);
+    let rtol = 1E-3;
+
+    // Update the controller, reporting a failure to the Simulator
+    let x_ref = reference.map(|reference| reference.sample(t0).0);
+    if let Err(error) = controller.update(t0, &x0, x_ref.as_ref(), dt) {
+        let failure = IntegrationFailure { time: t0, error: IntegrateError::ControllerError(t0, error) };
+        failures.data.lock().unwrap().push((*entity, failure));
+        return;
+    }
+    let controller = &*controller;
+
+    // Wrap dynamics/controls in appropriately defined closure - f(t, x)
+    let f = |t: Float, x: &DVector<Float>| {
+        let u = match reference.map(|reference| reference.sample(t)) {
+            Some((x_ref, Some(u_ref))) => controller.control(t, x, Some(&x_ref)) + u_ref,
+            Some((x_ref, None)) => controller.control(t, x, Some(&x_ref)),
+            None => controller.control(t, x, None),
+        };
+        dynamics.f(t, x, Some(&u))
+    };
+
+    // Sample the solution within the engine step if the entity records its trajectory
+    let t_eval = substeps.as_ref().map(|substeps| substeps.sample_times(t0, tf));
+
+    // Integrate dynamics
+    let opts = SolverOptions{ first_step: Some(step), rtol, t_eval, partition: dynamics.partition(), ..SolverOptions::default() };
+    // Report failures to the Simulator, holding the last valid state
+    let result = match solve_ivp(f, t_span, x0, integrator.0, opts) {
+        Ok(result) => result,
+        Err(error) => {
+            let failure = IntegrationFailure { time: t0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+            return;
+        }
+    };
+    let traj = result.y;
+
+    // Update entity FullState component
+    state.data = traj[traj.len()-1].clone();
+
+    if let Some(substeps) = substeps {
+        substeps.record(result.t, traj);
+    }
+
+}
+
+
+// Integrates entities driven by a discrete LQR sampled at the engine step
+// The input u = -Kx is computed from the state at the start of the engine step and held constant
+// (zero-order hold) while the dynamics are integrated over the step
+// NOTE: to parallelize with Rayon, use par_for_each
+// #[system(for_each)]
+#[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>())]
+#[allow(clippy::too_many_arguments)]
+pub fn integrate_dlqr_dynamics<T>(
+    entity: &Entity,
+    state: &mut FullState,
+    dynamics: &T,
+    controller: &DLQRComponent,
+    substeps: Option<&mut SubstepTrajectory>,
+    #[resource] time: &SimulationTime,
+    #[resource] sim_step: &EngineStep,
+    #[resource] integrator: &Integrator,
+    #[resource] step: &IntegratorStep,
+    #[resource] failures: &IntegrationFailures
+)
+where
+    T: Component + StateSpaceRepresentation // Need to include Component trait from Legion
+{
+
+    // Define initial conditions
+    let x0 = state.data.clone();
+
+    // Parameters
+    let dt = sim_step.0;
+    let step = step.0;
+    let t0 = time.0;
+    let tf = time.0 + dt;
+    let t_span = (t0, tf);
+    let rtol = 1E-3;
+
+    // Sample the input from the cached LQR gain, reporting a failed solve to the Simulator
+    let u = match controller.gain() {
+        Ok(K) => -K * &x0,
+        Err(error) => {
+            let failure = IntegrationFailure { time: t0, error: IntegrateError::ControllerError(t0, error.clone()) };
+            failures.data.lock().unwrap().push((*entity, failure));
+            return;
+        }
+    };
+
+    // Wrap dynamics/controls in appropriately defined closure - f(t, x)
+    let f = |t: Float, x: &DVector<Float>| {
+        dynamics.f(t, x, Some(&u))
+    };
+
+    // Sample the solution within the engine step if the entity records its trajectory
+    let t_eval = substeps.as_ref().map(|substeps| substeps.sample_times(t0, tf));
+
+    // Integrate dynamics
+    let opts = SolverOptions{ first_step: Some(step), rtol, t_eval, partition: dynamics.partition(), ..SolverOptions::default() };
+    // Report failures to the Simulator, holding the last valid state
+    let result = match solve_ivp(f, t_span, x0, integrator.0, opts) {
+        Ok(result) => result,
+        Err(error) => {
+            let failure = IntegrationFailure { time: t0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+            return;
+        }
+    };
+    let traj = result.y;
+
+    // Update entity FullState component
+    state.data = traj[traj.len()-1].clone();
+
+    if let Some(substeps) = substeps {
+        substeps.record(result.t, traj);
+    }
 
 }
 
@@ -76,21 +383,26 @@ where
 // NOTE: to parallelize with Rayon, use par_for_each
 // #[system(for_each)]
 #[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>())]
+#[allow(clippy::too_many_arguments)]
 pub fn integrate_dynamics<T>(
+    entity: &Entity,
     state: &mut FullState,
     dynamics: &T,
+    substeps: Option<&mut SubstepTrajectory>,
     #[resource] time: &SimulationTime,
     #[resource] sim_step: &EngineStep,
     #[resource] integrator: &Integrator,
-    #[resource] step: &IntegratorStep
-) -> Result<(), IntegrateError>
+    #[resource] step: &IntegratorStep,
+    #[resource] failures: &IntegrationFailures
+)
 where
     T: Component + StateSpaceRepresentation // Need to include Component trait from Legion
 {
 
     // Define initial conditions
     let x0 = state.data.clone();
-    let mut trajectory: Vec<DVector<f32>> = vec![x0.clone()];
+    let mut trajectory: Vec<DVector<Float>> = vec![x0.clone()];
 
     // Parameters
     let dt = sim_step.0;
@@ -101,17 +413,45 @@ where
     let rtol = 1E-3;
 
     // Wrap dynamics/controls in appropriately defined closure - f(t, x)
-    let f = |t: f32, x: &DVector<f32>| {
+    let f = |t: Float, x: &DVector<Float>| {
         dynamics.f(t, x, None)
     };
 
+    // Sample the solution within the engine step if the entity records its trajectory
+    let t_eval = substeps.as_ref().map(|substeps| substeps.sample_times(t0, tf));
+
     // Integrate dynamics
-    let opts = SolverOptions{ first_step: Some(step), rtol, ..SolverOptions::default() };
-    let (_times, traj) = solve_ivp(f, t_span, x0, integrator.0, opts)?;
+    // Jacobian of the model for implicit integrators
+    let jacobian = |t: Float, x: &DVector<Float>| {
+        dynamics.jacobian(t, x, None)
+    };
+
+    let opts = SolverOptions{
+        first_step: Some(step),
+        rtol,
+        t_eval,
+        jacobian: Some(Box::new(jacobian)),
+        partition: dynamics.partition(),
+        ..SolverOptions::default()
+    };
+    // Report failures to the Simulator, holding the last valid state
+    let result = match solve_ivp(f, t_span, x0, integrator.0, opts) {
+        Ok(result) => result,
+        Err(error) => {
+            let failure = IntegrationFailure { time: t0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+            return;
+        }
+    };
+    let traj = result.y;
 
     // Update entity FullState component
     state.data = traj[traj.len()-1].clone();
 
+    if let Some(substeps) = substeps {
+        substeps.record(result.t, traj.clone());
+    }
+
     // Store result
     for state in traj {
         trajectory.push(state);
@@ -124,8 +464,6 @@ where
 
     // }
 
-    Ok(())
-
 }
 
 
@@ -155,3 +493,98 @@ where
 
 }
 
+
+// Advances entities with discretized linear dynamics over the engine step without an integrator
+// The sample period of the dynamics must divide the engine step. If the entity has a discrete
+// LQR, its input u[k] = -Kx[k] is held constant over each sample.
+// NOTE: to parallelize with Rayon, use par_for_each
+// #[system(for_each)]
+#[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>())]
+pub fn step_discrete_lti_dynamics(
+    entity: &Entity,
+    state: &mut FullState,
+    dynamics: &DiscreteLTIComponent,
+    controller: Option<&DLQRComponent>,
+    #[resource] time: &SimulationTime,
+    #[resource] sim_step: &EngineStep,
+    #[resource] failures: &IntegrationFailures
+) {
+
+    // Report a failed solve or a mistimed sample period to the Simulator
+    match step_samples(dynamics, controller, &state.data, time.0, sim_step.0) {
+        Ok(x) => state.data = x,
+        Err(error) => {
+            let failure = IntegrationFailure { time: time.0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+        }
+    }
+
+}
+
+
+// Advances entities with discrete-time dynamics over the engine step
+// The sample period dt() of the dynamics must divide the engine step, which then advances the
+// entity by EngineStep / dt() samples. If the entity has a discrete LQR, its input u[k] = -Kx[k] is
+// computed from each sampled state.
+// NOTE: to parallelize with Rayon, use par_for_each
+// #[system(for_each)]
+#[system(par_for_each)]
+#[filter(!component::<IntegrationFailure>())]
+pub fn step_discrete_dynamics<T>(
+    entity: &Entity,
+    state: &mut FullState,
+    dynamics: &T,
+    controller: Option<&DLQRComponent>,
+    #[resource] time: &SimulationTime,
+    #[resource] sim_step: &EngineStep,
+    #[resource] failures: &IntegrationFailures
+)
+where
+    T: Component + DiscreteStateSpaceRepresentation // Need to include Component trait from Legion
+{
+
+    // Report a failed solve or a mistimed sample period to the Simulator
+    match step_samples(dynamics, controller, &state.data, time.0, sim_step.0) {
+        Ok(x) => state.data = x,
+        Err(error) => {
+            let failure = IntegrationFailure { time: time.0, error };
+            failures.data.lock().unwrap().push((*entity, failure));
+        }
+    }
+
+}
+
+
+// Advances discrete-time dynamics over the engine step from x0 at time t0
+// The sample index counts the samples elapsed since t = 0, and a discrete LQR input u[k] = -Kx[k]
+// is computed from each sampled state. Fails if the sample period does not divide the engine step.
+fn step_samples<T>(dynamics: &T, controller: Option<&DLQRComponent>, x0: &DVector<Float>, t0: Float, sim_step: Float)
+    -> Result<DVector<Float>, IntegrateError>
+where
+    T: DiscreteStateSpaceRepresentation
+{
+
+    let dt = dynamics.dt();
+    let ratio = sim_step / dt;
+    let samples = ratio.round();
+    if samples < 1.0 || (ratio - samples).abs() > 1E-6 * samples {
+        return Err(IntegrateError::ArgError(format!("sample period {} does not divide the engine step {}", dt, sim_step)));
+    }
+
+    let K = match controller.map(|controller| controller.gain()) {
+        Some(Ok(K)) => Some(K),
+        Some(Err(error)) => return Err(IntegrateError::ControllerError(t0, error.clone())),
+        None => None,
+    };
+
+    let k0 = (t0 / dt).round() as usize;
+    let mut x = x0.clone();
+    for k in k0..k0 + samples as usize {
+        let u = K.map(|K| -K * &x);
+        x = dynamics.f(k, &x, u.as_ref());
+    }
+
+    Ok(x)
+
+}
@@ -0,0 +1,19 @@
+
+// Floating point precision used throughout MADS
+// Single precision by default, double precision with the "f64" feature
+
+/// Floating point type of all states, matrices and times
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+
+/// Floating point type of all states, matrices and times
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Mathematical constants at the precision of Float
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+
+/// Mathematical constants at the precision of Float
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
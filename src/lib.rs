@@ -9,6 +9,7 @@ extern crate serde;
 extern crate rand;
 
 // MADS Core
+pub mod float;
 pub mod controls;
 pub mod dynamics;
 pub mod math;
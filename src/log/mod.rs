@@ -8,6 +8,7 @@ use legion::*;
 use crate::ecs::resources::{SimulationResult, SimulationTimeHistory};
 use crate::ecs::components::*;
 use crate::simulator::state::SimulatorState;
+use crate::float::Float;
 
 #[derive(Error, Debug)]
 pub enum LogError {
@@ -122,7 +123,7 @@ pub trait Logger {
         // Go row-by-row and serialize time and header aligned state values
         for (k, time) in time_history.data.iter().enumerate() {
 
-            let mut row: Vec<f32> = vec![*time];
+            let mut row: Vec<Float> = vec![*time];
             for (_id, state_history) in results.data.iter() {
                 let fullstate_k = &state_history[k];
                 for dim in fullstate_k.data.iter() {
@@ -177,7 +178,7 @@ mod tests {
     struct TestScenario {
 
         name: String,
-        time: f32
+        time: Float
 
     }
 
@@ -205,7 +206,7 @@ mod tests {
             let sim_id = SimID { uuid: id, name: "Entity0".to_string() };
 
             // Initial conditions
-            let state = DVector::<f32>::from_vec(vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+            let state = DVector::<Float>::from_vec(vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
             let fullstate = FullState { data: state };
 
             // Agent dynamics model
@@ -214,9 +215,9 @@ mod tests {
             let B = dynamics_component.dynamics().B.clone();
 
             // Agent controller
-            let Q = DMatrix::<f32>::identity(6, 6);
-            let R = DMatrix::<f32>::identity(3, 3);
-            let controller_component = LQRComponent::new(A, B, Q, R);
+            let Q = DMatrix::<Float>::identity(6, 6);
+            let R = DMatrix::<Float>::identity(3, 3);
+            let controller_component = LQRComponent::new(A, B, Q, R).unwrap();
 
             let dynamics_flag = DynamicFlag { 0: true };
             let statespace_component = dynamics_component.statespace().clone();
@@ -1,17 +1,18 @@
 
 use na::{Rotation3, Vector3, Matrix3};
+use crate::float::Float;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ReferenceFrame {
 
-    origin: Vector3<f32>,
-    coordinates: Matrix3<f32>
+    origin: Vector3<Float>,
+    coordinates: Matrix3<Float>
 
 }
 
 impl ReferenceFrame {
 
-    pub fn new(origin: (f32, f32, f32)) -> Self {
+    pub fn new(origin: (Float, Float, Float)) -> Self {
 
         let o = Vector3::new(origin.0, origin.1, origin.2);
         let mut coordinates = Matrix3::from_element(0.0);
@@ -22,7 +23,7 @@ impl ReferenceFrame {
     }
 
     /// Rotates frame using euler angles rotations with a ZYX sequence
-    pub fn rotate(&mut self, roll: f32, pitch: f32, yaw: f32) {
+    pub fn rotate(&mut self, roll: Float, pitch: Float, yaw: Float) {
 
         let rot = Rotation3::from_euler_angles(roll, pitch, yaw);
 
@@ -38,7 +39,7 @@ impl Default for ReferenceFrame {
     /// Generates a ReferenceFrame at (0,0,0) with cartesian unit vectors +x, +y, +z
     fn default() -> Self {
 
-        let origin = Vector3::new(0f32, 0f32, 0f32);
+        let origin = Vector3::new(0.0, 0.0, 0.0);
         let mut coordinates = Matrix3::from_element(0.0);
         coordinates.fill_with_identity();
 
@@ -57,7 +58,7 @@ mod tests {
     fn test_ReferenceFrame_rotate() {
 
         let mut some_frame = ReferenceFrame::default();
-        some_frame.rotate(std::f32::consts::FRAC_PI_4, 0.0, std::f32::consts::FRAC_PI_6);
+        some_frame.rotate(crate::float::consts::FRAC_PI_4, 0.0, crate::float::consts::FRAC_PI_6);
 
         let correct = Matrix3::from_row_slice(
             &[1.0, 0.0, 0.0,
@@ -0,0 +1,115 @@
+
+use na::DVector;
+use crate::float::Float;
+
+/// Continuous extension of a discrete IVP solution
+///
+/// Each step of the solution is interpolated by a cubic Hermite polynomial matching the state
+/// and its derivative at both ends of the step. The interpolant is independent of the
+/// integrator that produced the solution.
+///
+/// t: times at which the solution was computed \
+/// y: solution at each time in t \
+/// dydt: derivative of the solution at each time in t \
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseOutput {
+    t: Vec<Float>,
+    y: Vec<DVector<Float>>,
+    dydt: Vec<DVector<Float>>,
+}
+
+impl DenseOutput {
+
+    /// Builds the interpolant by evaluating the ODE at every point of the solution
+    pub fn new<F>(f: F, t: &[Float], y: &[DVector<Float>]) -> Self
+    where
+        F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+    {
+
+        assert_eq!(t.len(), y.len());
+        assert!(!t.is_empty());
+
+        let dydt = t.iter().zip(y.iter()).map(|(tk, yk)| f(*tk, yk)).collect();
+
+        Self { t: t.to_vec(), y: y.to_vec(), dydt }
+
+    }
+
+    /// Returns the interval covered by the interpolant
+    pub fn t_span(&self) -> (Float, Float) {
+
+        (self.t[0], self.t[self.t.len()-1])
+
+    }
+
+    /// Returns the interpolated state at time t, or None if t lies outside of the solution
+    pub fn evaluate(&self, t: Float) -> Option<DVector<Float>> {
+
+        let (t_first, t_last) = self.t_span();
+
+        // Allow for round-off in the final time reached by the integrator
+        let eps = 10.0 * Float::EPSILON * t_first.abs().max(t_last.abs()).max(1.0);
+        if t < t_first - eps || t > t_last + eps {
+            return None;
+        }
+
+        if self.t.len() == 1 {
+            return Some(self.y[0].clone());
+        }
+
+        let t = t.max(t_first).min(t_last);
+
+        // Index of the step [t_k, t_k+1] which contains t
+        let k = match self.t.partition_point(|tk| *tk <= t) {
+            0 => 0,
+            i => (i - 1).min(self.t.len() - 2),
+        };
+
+        let h = self.t[k+1] - self.t[k];
+        if h <= 0.0 {
+            return Some(self.y[k].clone());
+        }
+
+        let s = (t - self.t[k]) / h;
+        let s2 = s * s;
+        let s3 = s2 * s;
+
+        // Cubic Hermite basis functions
+        let h00 = 2.0*s3 - 3.0*s2 + 1.0;
+        let h10 = s3 - 2.0*s2 + s;
+        let h01 = -2.0*s3 + 3.0*s2;
+        let h11 = s3 - s2;
+
+        Some(h00 * &self.y[k] + (h10 * h) * &self.dydt[k] + h01 * &self.y[k+1] + (h11 * h) * &self.dydt[k+1])
+
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_DenseOutput_evaluate() {
+
+        // exact solution of x_dot = [x1, -x0] sampled coarsely
+        let f = |_t: Float, x: &DVector<Float>| DVector::from_vec(vec![x[1], -x[0]]);
+        let t: Vec<Float> = (0..11).map(|k| 0.1 * k as Float).collect();
+        let y: Vec<DVector<Float>> = t.iter().map(|tk| DVector::from_vec(vec![tk.cos(), -tk.sin()])).collect();
+
+        let sol = DenseOutput::new(f, &t, &y);
+
+        for tk in [0.0 as Float, 0.05, 0.333, 0.71, 1.0].iter() {
+            let exact = DVector::from_vec(vec![tk.cos(), -tk.sin()]);
+            let error = (sol.evaluate(*tk).unwrap() - exact).amax();
+            assert!(error < 1E-5);
+        }
+
+        assert_eq!(sol.evaluate(-0.1), None);
+        assert_eq!(sol.evaluate(1.1), None);
+
+    }
+
+}
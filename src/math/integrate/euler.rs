@@ -1,61 +1,49 @@
 
 use na::DVector;
-use crate::util::misc::range_step;
+use crate::math::integrate::{IntegrateError, OdeResult, fixed_step};
+use crate::math::integrate::events::Event;
+use crate::float::Float;
 
 pub fn ForwardEuler<F>(
     f: F,
-    t0: f32,
-    y0: DVector<f32>,
-    tf: f32,
-    step: f32
-) -> (Vec<f32>, Vec<DVector<f32>>)
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
 {
-    let h = step;
-    let time = range_step(t0, tf, h);
 
-    let mut y: Vec<DVector<f32>> = vec![DVector::<f32>::zeros(y0.len()); time.len()];
-    y[0] = y0;
+    let method = |t: Float, x: &mut DVector<Float>, h: Float| {
+        *x += h * f(t, x);
+    };
 
-    for k in 0..time.len() - 1 {
-        let yk = &y[k];
+    fixed_step(&f, method, t0, y0, tf, step, events)
 
-        let m = h * f(time[k], &yk);
-
-        // Need to clone because y owns the state data
-        y[k + 1] = yk + m;
-    }
-
-    (time, y)
 }
 
 
 pub fn MidPointEuler<F>(
     f: F,
-    t0: f32,
-    y0: DVector<f32>,
-    tf: f32,
-    step: f32
-) -> (Vec<f32>, Vec<DVector<f32>>)
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
 {
-    let h = step;
-    let time = range_step(t0, tf, h);
-
-    let mut y: Vec<DVector<f32>> = vec![DVector::<f32>::zeros(y0.len()); time.len()];
-    y[0] = y0;
 
-    for k in 0..time.len() - 1 {
-        let yk = &y[k];
+    let method = |t: Float, x: &mut DVector<Float>, h: Float| {
+        let x_half = &*x + (h / 2.0) * f(t, x);
+        *x += h * f(t + h / 2.0, &x_half);
+    };
 
-        let y_half = yk + (h / 2.0 * f(time[k], yk));
+    fixed_step(&f, method, t0, y0, tf, step, events)
 
-        y[k + 1] = yk + (h * f(time[k] + (h/2.0), &y_half));
-    }
-
-    (time, y)
 }
 
 
@@ -73,7 +61,7 @@ mod tests {
 
         let model = DoubleIntegrator1D::new();
 
-        let Q = DMatrix::<f32>::identity(2, 2);
+        let Q = DMatrix::<Float>::identity(2, 2);
         let R = DMatrix::from_vec(1, 1, vec![1.]);
 
         let lqr = LQR::new(
@@ -81,16 +69,13 @@ mod tests {
             model.dynamics().B.clone(),
             Q,
             R,
-        );
+        ).unwrap();
 
         let y0 = DVector::from_vec(vec![10., -40.]);
-        let (K, _P) = match lqr.solve() {
-            Ok((value1, value2)) => (value1, value2),
-            Err(_) => (DMatrix::<f32>::zeros(1, 1), DMatrix::<f32>::zeros(1, 1)),
-        };
+        let (K, _P) = lqr.solve();
 
         // Wrap dynamics/controls in appropriately defined closure
-        let f = |t: f32, x: &DVector<f32>| {
+        let f = |t: Float, x: &DVector<Float>| {
             let u = -&K * x;
             model.dynamics().f(t, x, Some(&u))
         };
@@ -99,7 +84,7 @@ mod tests {
         let tf = 10.0;
         let n = 100.0;
         let step = (tf - t0) / n;
-        let (_t, y) = ForwardEuler(f, t0, y0, tf, step);
+        let y = ForwardEuler(f, t0, y0, tf, step, &[]).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
@@ -116,25 +101,22 @@ mod tests {
 
         let model = DoubleIntegrator2D::new();
 
-        let Q = DMatrix::<f32>::identity(4, 4);
-        let R = DMatrix::<f32>::identity(2, 2);
+        let Q = DMatrix::<Float>::identity(4, 4);
+        let R = DMatrix::<Float>::identity(2, 2);
 
         let lqr = LQR::new(
             model.dynamics().A.clone(),
             model.dynamics().B.clone(),
             Q,
             R,
-        );
+        ).unwrap();
 
-        let (K, _P) = match lqr.solve() {
-            Ok((value1, value2)) => (value1, value2),
-            Err(_) => panic!["LQR solve"],
-        };
+        let (K, _P) = lqr.solve();
 
         let y0 = DVector::from_vec(vec![10., 10., 10., 10.]);
 
         // Wrap dynamics/controls in appropriately defined closure
-        let f = |t: f32, x: &DVector<f32>| {
+        let f = |t: Float, x: &DVector<Float>| {
             let u = -&K * x;
             model.dynamics().f(t, x, Some(&u))
         };
@@ -143,11 +125,24 @@ mod tests {
         let tf = 10.0;
         let n = 100.0;
         let step = (tf - t0) / n;
-        let (_t, y) = MidPointEuler(f, t0, y0, tf, step);
+        let y = MidPointEuler(f, t0, y0, tf, step, &[]).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
         }
     }
-}
 
+    #[test]
+    fn test_ForwardEuler_non_finite() {
+
+        // solution of x_dot = c*x^2 escapes to infinity in finite time
+        let f = |_t: Float, x: &DVector<Float>| x.map(|xi| xi * xi * 1E6);
+        let y0 = DVector::from_vec(vec![1.]);
+
+        match ForwardEuler(f, 0.0, y0, 10.0, 0.1, &[]) {
+            Err(IntegrateError::NonFiniteStateError(t)) => assert!(t < 10.0),
+            _ => panic!("expected NonFiniteStateError"),
+        }
+
+    }
+}
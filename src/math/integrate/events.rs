@@ -0,0 +1,219 @@
+
+use na::DVector;
+use crate::math::integrate::dense_output::DenseOutput;
+use crate::float::Float;
+
+/// An alias for the boxed event function g(t, x) held by an Event
+pub type EventFunction<'a> = Box<dyn Fn(Float, &DVector<Float>) -> Float + 'a>;
+
+/// Direction of a zero-crossing which triggers an event
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EventDirection {
+    /// g(t, x) crosses zero from negative to positive
+    Rising,
+    /// g(t, x) crosses zero from positive to negative
+    Falling,
+    /// g(t, x) crosses zero in either direction
+    Either,
+}
+
+/// A zero-crossing event g(t, x) = 0 monitored while solving an IVP
+///
+/// function: event function g(t, x) \
+/// direction: direction of the zero-crossings to detect \
+/// terminal: stop the integration at the first occurrence of this event \
+///
+/// # Example
+///
+/// ```
+/// use nalgebra::DVector;
+/// use mads::float::Float;
+/// use mads::math::integrate::events::{Event, EventDirection};
+///
+/// // pendulum passes through vertical while swinging in the positive direction
+/// let vertical = Event::new(|_t: Float, x: &DVector<Float>| x[0], EventDirection::Rising, true);
+/// ```
+///
+pub struct Event<'a> {
+    pub function: EventFunction<'a>,
+    pub direction: EventDirection,
+    pub terminal: bool,
+}
+
+impl<'a> Event<'a> {
+
+    pub fn new<G>(g: G, direction: EventDirection, terminal: bool) -> Self
+    where
+        G: Fn(Float, &DVector<Float>) -> Float + 'a,
+    {
+
+        Self { function: Box::new(g), direction, terminal }
+
+    }
+
+    /// Returns true if the change in sign between g_a and g_b is a zero-crossing for this event
+    fn crosses(&self, g_a: Float, g_b: Float) -> bool {
+
+        let rising = g_a < 0.0 && g_b >= 0.0;
+        let falling = g_a > 0.0 && g_b <= 0.0;
+
+        match self.direction {
+            EventDirection::Rising => rising,
+            EventDirection::Falling => falling,
+            EventDirection::Either => rising || falling,
+        }
+
+    }
+
+}
+
+/// Monitors events along a solution as it is integrated
+///
+/// Each accepted step is checked for zero-crossings as soon as the solver takes it, so that a
+/// terminal event ends the integration at the step in which it occurred. Crossings are bracketed
+/// by the end points of the step and root-localised on its cubic Hermite interpolant with the
+/// Illinois variant of regula falsi.
+///
+/// t_events: times at which each event occurred \
+/// y_events: solution at each time in t_events \
+/// terminated: a terminal event ended the solution \
+pub struct EventMonitor<'e, 'a> {
+    events: &'e [Event<'a>],
+    g_prev: Vec<Float>,
+    pub t_events: Vec<Vec<Float>>,
+    pub y_events: Vec<Vec<DVector<Float>>>,
+    pub terminated: bool,
+}
+
+impl<'e, 'a> EventMonitor<'e, 'a> {
+
+    pub fn new(events: &'e [Event<'a>], t0: Float, y0: &DVector<Float>) -> Self {
+
+        Self {
+            events,
+            g_prev: events.iter().map(|event| (event.function)(t0, y0)).collect(),
+            t_events: vec![Vec::new(); events.len()],
+            y_events: vec![Vec::new(); events.len()],
+            terminated: false,
+        }
+
+    }
+
+    /// Checks the latest accepted step, between the last two points of the solution (t, y)
+    ///
+    /// Returns true at a terminal event, after replacing the last point of the solution with the
+    /// time and state of the event. Events after the terminal event within the step are ignored.
+    pub fn step<F>(&mut self, f: &F, t: &mut [Float], y: &mut [DVector<Float>]) -> bool
+    where
+        F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+    {
+
+        if self.events.is_empty() || self.terminated {
+            return self.terminated;
+        }
+
+        let k = t.len() - 2;
+        let g_next: Vec<Float> = self.events.iter().map(|event| (event.function)(t[k+1], &y[k+1])).collect();
+
+        // Interpolant of the step, only built once a crossing is bracketed
+        let mut sol: Option<DenseOutput> = None;
+
+        // (time, state, event index) of each crossing within this step
+        let mut crossings: Vec<(Float, DVector<Float>, usize)> = Vec::new();
+        for (i, event) in self.events.iter().enumerate() {
+            if event.crosses(self.g_prev[i], g_next[i]) {
+                let sol = sol.get_or_insert_with(|| DenseOutput::new(f, &t[k..], &y[k..]));
+                let g = |t: Float| match sol.evaluate(t) {
+                    Some(x) => (event.function)(t, &x),
+                    None => Float::NAN,
+                };
+                let t_root = illinois(g, t[k], t[k+1], self.g_prev[i], g_next[i]);
+                let y_root = sol.evaluate(t_root).unwrap_or_else(|| y[k+1].clone());
+                crossings.push((t_root, y_root, i));
+            }
+        }
+
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (t_root, y_root, i) in crossings {
+            self.t_events[i].push(t_root);
+            self.y_events[i].push(y_root.clone());
+
+            if self.events[i].terminal {
+                t[k+1] = t_root;
+                y[k+1] = y_root;
+                self.terminated = true;
+                return true;
+            }
+        }
+
+        self.g_prev = g_next;
+
+        false
+
+    }
+
+}
+
+/// Finds a root of g within [a, b] given g(a) and g(b) of opposite signs
+fn illinois<G>(g: G, a: Float, b: Float, g_a: Float, g_b: Float) -> Float
+where
+    G: Fn(Float) -> Float,
+{
+
+    if g_b == 0.0 {
+        return b;
+    }
+
+    let (mut a, mut b, mut g_a, mut g_b) = (a, b, g_a, g_b);
+    let tol = 4.0 * Float::EPSILON * a.abs().max(b.abs()).max(1.0);
+
+    // side of the bracket retained on the previous iteration
+    let mut side = 0;
+
+    for _ in 0..100 {
+
+        let c = (a * g_b - b * g_a) / (g_b - g_a);
+        let g_c = g(c);
+
+        if !g_c.is_finite() || g_c == 0.0 || (b - a).abs() <= tol {
+            return c;
+        }
+
+        if g_c * g_b > 0.0 {
+            b = c;
+            g_b = g_c;
+            if side == -1 {
+                g_a /= 2.0;
+            }
+            side = -1;
+        } else {
+            a = c;
+            g_a = g_c;
+            if side == 1 {
+                g_b /= 2.0;
+            }
+            side = 1;
+        }
+
+    }
+
+    (a * g_b - b * g_a) / (g_b - g_a)
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_illinois() {
+
+        let root = illinois(|t: Float| t.cos(), 1.0, 2.0, (1 as Float).cos(), (2 as Float).cos());
+
+        assert_relative_eq!(root, crate::float::consts::FRAC_PI_2, epsilon = 1E-6);
+
+    }
+
+}
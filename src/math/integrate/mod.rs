@@ -1,14 +1,28 @@
 
-use na::DVector;
+use na::{DMatrix, DVector};
 use crate::math::integrate::euler::{ForwardEuler, MidPointEuler};
-use crate::math::integrate::runge_kutta::{RK45, RKF45};
+use crate::math::integrate::runge_kutta::{RK4, RK45, RKF45, DOPRI5};
+use crate::math::integrate::multistep::ABM4;
+use crate::math::integrate::dense_output::DenseOutput;
+use crate::math::integrate::events::{Event, EventMonitor};
+use crate::math::integrate::rosenbrock::Rosenbrock23;
+use crate::math::integrate::symplectic::{VelocityVerlet, Leapfrog, Yoshida4, SecondOrderPartition};
+use crate::controls::models::ControlError;
+use crate::math::jacobian::finite_difference;
+use crate::float::Float;
 
 pub mod euler;
 pub mod runge_kutta;
+pub mod multistep;
+pub mod dense_output;
+pub mod events;
+pub mod rosenbrock;
+pub mod symplectic;
+pub mod variational;
 
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum IntegrateError {
     #[error("Improper argument: {0}")]
     ArgError(String),
@@ -18,6 +32,18 @@ pub enum IntegrateError {
 
     #[error("Did not converge")]
     NoCoverganceError,
+
+    #[error("Step size, {0}, fell below the minimum step size")]
+    MinStepError(Float),
+
+    #[error("Singular iteration matrix at t = {0}")]
+    SingularMatrixError(Float),
+
+    #[error("Non-finite state at t = {0}")]
+    NonFiniteStateError(Float),
+
+    #[error("Controller failed to compute an input at t = {0}: {1}")]
+    ControllerError(Float, #[source] ControlError),
 }
 
 /// IVP Integrators
@@ -25,8 +51,15 @@ pub enum IntegrateError {
 pub enum IntegratorType {
     ForwardEuler,
     MidpointEuler,
+    RK4,
+    ABM4,
     RKF45,
     RK45,
+    DOPRI5,
+    Rosenbrock23,
+    VelocityVerlet,
+    Leapfrog,
+    Yoshida4,
 }
 
 impl Default for IntegratorType {
@@ -35,24 +68,108 @@ impl Default for IntegratorType {
 
 }
 
+/// An alias for the boxed Jacobian J(t, x) = df/dx of the system passed to an implicit solver
+pub type JacobianFunction<'a> = Box<dyn Fn(Float, &DVector<Float>) -> DMatrix<Float> + 'a>;
+
 /// Defines options to be passed to the chosen IVP solver
-pub struct SolverOptions {
-    pub first_step: Option<f32>,
-    pub rtol: f32,
-    pub atol: f32,
+///
+/// first_step: initial step size \
+/// rtol: relative tolerance applied to each state component \
+/// atol: absolute tolerance applied to each state component \
+/// max_step: largest step size an adaptive solver may take \
+/// min_step: smallest step size an adaptive solver may take before failing \
+/// max_iterations: largest number of steps, accepted or rejected, an adaptive solver may attempt \
+/// t_eval: increasing times at which to report the solution, interpolated from the integrator steps \
+/// dense_output: return a continuous interpolant of the solution \
+/// events: zero-crossing events checked after each accepted step \
+/// jacobian: Jacobian of the system for implicit solvers, approximated by finite differences if None \
+/// partition: position and velocity components for symplectic solvers, [positions, velocities] if None \
+pub struct SolverOptions<'a> {
+    pub first_step: Option<Float>,
+    pub rtol: Float,
+    pub atol: Float,
+    pub max_step: Option<Float>,
+    pub min_step: Option<Float>,
+    pub max_iterations: usize,
+    pub t_eval: Option<Vec<Float>>,
+    pub dense_output: bool,
+    pub events: Vec<Event<'a>>,
+    pub jacobian: Option<JacobianFunction<'a>>,
+    pub partition: Option<SecondOrderPartition>,
 }
 
-impl Default for SolverOptions {
+impl Default for SolverOptions<'_> {
     fn default() -> Self {
-        Self { first_step: None, rtol: 1E-3, atol: 1E-6 }
+        Self {
+            first_step: None,
+            rtol: 1E-3,
+            atol: 1E-6,
+            max_step: None,
+            min_step: None,
+            max_iterations: 100_000,
+            t_eval: None,
+            dense_output: false,
+            events: Vec::new(),
+            jacobian: None,
+            partition: None
+        }
+    }
+}
+
+/// Solution of an initial value problem
+///
+/// t: times at which the solution was computed, or t_eval if requested \
+/// y: solution at each time in t \
+/// n_rejected: number of steps rejected by the error control of an adaptive solver \
+/// sol: continuous interpolant of the solution if dense output was requested \
+/// t_events: times at which each event occurred \
+/// y_events: solution at each time in t_events \
+/// terminated: the solution was ended early by a terminal event \
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdeResult {
+    pub t: Vec<Float>,
+    pub y: Vec<DVector<Float>>,
+    pub n_rejected: usize,
+    pub sol: Option<DenseOutput>,
+    pub t_events: Vec<Vec<Float>>,
+    pub y_events: Vec<Vec<DVector<Float>>>,
+    pub terminated: bool,
+}
+
+impl OdeResult {
+
+    pub fn new(t: Vec<Float>, y: Vec<DVector<Float>>, n_rejected: usize) -> Self {
+
+        Self {
+            t,
+            y,
+            n_rejected,
+            sol: None,
+            t_events: Vec::new(),
+            y_events: Vec::new(),
+            terminated: false
+        }
+
     }
+
+    /// Records the events found along the solution by a monitor
+    pub fn with_events(mut self, monitor: EventMonitor) -> Self {
+
+        self.t_events = monitor.t_events;
+        self.y_events = monitor.y_events;
+        self.terminated = monitor.terminated;
+
+        self
+
+    }
+
 }
 
 /// Integrates a system of ordinary differential equations given an initial value
-pub fn solve_ivp<F>(fun: F, t_span: (f32, f32), y0: DVector<f32>, method: IntegratorType, options: SolverOptions)
-    -> Result< (Vec<f32>, Vec<DVector<f32>>), IntegrateError >
+pub fn solve_ivp<F>(fun: F, t_span: (Float, Float), y0: DVector<Float>, method: IntegratorType, options: SolverOptions)
+    -> Result< OdeResult, IntegrateError >
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
 {
 
     if t_span.0 < 0.0 || t_span.0 >= t_span.1 || t_span.1 <= 0.0 {
@@ -65,19 +182,148 @@ where
         None => (tf - t0)/1000.0
     };
     let rtol = options.rtol;
-    let _atol = options.atol;
+    let max_step = options.max_step.unwrap_or(tf - t0);
+    let min_step = options.min_step.unwrap_or(0.0);
 
-    // TODO: propogate errors from the individual functions
-    let (t_steps, trajectory) = match method {
+    if rtol < 0.0 || options.atol < 0.0 {
+        return Err(IntegrateError::ArgError("rtol, atol".to_string()));
+    }
 
-        IntegratorType::ForwardEuler => ForwardEuler(fun, t0, y0, tf, step),
-        IntegratorType::MidpointEuler => MidPointEuler(fun, t0, y0, tf, step),
-        IntegratorType::RKF45 => RKF45(fun, t0, y0, tf, step, rtol),
-        IntegratorType::RK45 => RK45(fun, t0, y0, tf, step, rtol)
+    if max_step <= 0.0 || min_step < 0.0 || min_step > max_step {
+        return Err(IntegrateError::ArgError("max_step, min_step".to_string()));
+    }
+
+    if options.max_iterations == 0 {
+        return Err(IntegrateError::ArgError("max_iterations".to_string()));
+    }
+
+    if !y0.iter().all(|x| x.is_finite()) {
+        return Err(IntegrateError::ArgError("y0".to_string()));
+    }
+
+    if let Some(t_eval) = &options.t_eval {
+        let sorted = t_eval.windows(2).all(|w| w[0] <= w[1]);
+        let inside = t_eval.iter().all(|t| *t >= t0 && *t <= tf);
+        if !sorted || !inside {
+            return Err(IntegrateError::ArgError("t_eval".to_string()));
+        }
+    }
+
+    // Symplectic solvers update every component of the state as either a position or a velocity,
+    // so an odd state dimension cannot be split in halves
+    let partition = match &options.partition {
+        Some(partition) => partition.clone(),
+        None => SecondOrderPartition::halves(y0.len()),
+    };
+
+    let symplectic = matches!(method, IntegratorType::VelocityVerlet | IntegratorType::Leapfrog | IntegratorType::Yoshida4);
+    if symplectic && !partition.is_valid(y0.len()) {
+        return Err(IntegrateError::ArgError("partition".to_string()));
+    }
+
+    let mut result = match method {
+
+        IntegratorType::ForwardEuler => ForwardEuler(&fun, t0, y0, tf, step, &options.events)?,
+        IntegratorType::MidpointEuler => MidPointEuler(&fun, t0, y0, tf, step, &options.events)?,
+        IntegratorType::RK4 => RK4(&fun, t0, y0, tf, step, &options.events)?,
+        IntegratorType::ABM4 => ABM4(&fun, t0, y0, tf, step, &options.events)?,
+        IntegratorType::RKF45 => RKF45(&fun, t0, y0, tf, step, &options)?,
+        IntegratorType::RK45 => RK45(&fun, t0, y0, tf, step, &options)?,
+        IntegratorType::DOPRI5 => DOPRI5(&fun, t0, y0, tf, step, &options)?,
+        IntegratorType::Rosenbrock23 => match &options.jacobian {
+            Some(jacobian) => Rosenbrock23(&fun, jacobian, t0, y0, tf, step, &options)?,
+            None => {
+                let jacobian = |t: Float, x: &DVector<Float>| finite_difference(|x: &DVector<Float>| fun(t, x), x);
+                Rosenbrock23(&fun, jacobian, t0, y0, tf, step, &options)?
+            }
+        },
+        IntegratorType::VelocityVerlet => VelocityVerlet(&fun, t0, y0, tf, step, &partition, &options.events)?,
+        IntegratorType::Leapfrog => Leapfrog(&fun, t0, y0, tf, step, &partition, &options.events)?,
+        IntegratorType::Yoshida4 => Yoshida4(&fun, t0, y0, tf, step, &partition, &options.events)?,
 
     };
 
-    Ok( (t_steps, trajectory) )
+    if options.dense_output || options.t_eval.is_some() {
+
+        let sol = DenseOutput::new(&fun, &result.t, &result.y);
+
+        // Report the solution at the requested times, up to a terminal event
+        if let Some(t_eval) = options.t_eval {
+            let t_end = result.t[result.t.len()-1];
+            let t_eval: Vec<Float> = match result.terminated {
+                true => t_eval.into_iter().filter(|t| *t <= t_end).collect(),
+                false => t_eval,
+            };
+
+            let mut y = Vec::with_capacity(t_eval.len());
+            for t in t_eval.iter() {
+                match sol.evaluate(*t) {
+                    Some(value) => y.push(value),
+                    None => return Err(IntegrateError::ArgError("t_eval outside of integrated span".to_string())),
+                }
+            }
+
+            result.t = t_eval;
+            result.y = y;
+        }
+
+        if options.dense_output {
+            result.sol = Some(sol);
+        }
+
+    }
+
+    Ok( result )
+
+}
+
+/// Advances a fixed-step one-step method from t0 to tf, clipping the final step to land on tf
+///
+/// Step k ends at t0 + (k+1)*step rather than an accumulated sum of steps, so that the time grid
+/// is reproducible. Events are checked after every step, and the solution ends at a terminal event.
+fn fixed_step<F, S>(f: &F, method: S, t0: Float, y0: DVector<Float>, tf: Float, step: Float, events: &[Event])
+    -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+    S: Fn(Float, &mut DVector<Float>, Float),
+{
+
+    let iterations = ((tf - t0) / step).ceil() as usize;
+
+    let mut time: Vec<Float> = Vec::with_capacity(iterations + 1);
+    let mut y: Vec<DVector<Float>> = Vec::with_capacity(iterations + 1);
+
+    let mut monitor = EventMonitor::new(events, t0, &y0);
+
+    time.push(t0);
+    y.push(y0);
+
+    for k in 0..iterations {
+
+        let tk = time[k];
+        let t_next = if k + 1 == iterations { tf } else { (t0 + (k + 1) as Float * step).min(tf) };
+        let h = t_next - tk;
+        if h <= 0.0 {
+            break;
+        }
+
+        let mut x = y[k].clone();
+        method(tk, &mut x, h);
+
+        if !x.iter().all(|xi| xi.is_finite()) {
+            return Err(IntegrateError::NonFiniteStateError(t_next));
+        }
+
+        time.push(t_next);
+        y.push(x);
+
+        if monitor.step(f, &mut time, &mut y) {
+            break;
+        }
+
+    }
+
+    Ok(OdeResult::new(time, y, 0).with_events(monitor))
 
 }
 
@@ -88,6 +334,7 @@ mod tests {
     use crate::dynamics::statespace::StateSpaceRepresentation;
     use super::{SolverOptions, IntegratorType};
     use na::DVector;
+    use crate::float::Float;
 
     #[test]
     fn test_solve_ivp() {
@@ -96,14 +343,14 @@ mod tests {
         let model = InvertedPendulum::new();
 
         // explicitly capture the model ODE within a closure of the form f(t, x)
-        let f = |t: f32, x: &DVector<f32>| model.dynamics().f(t, x, None);
+        let f = |t: Float, x: &DVector<Float>| model.dynamics().f(t, x, None);
 
         // integrate and handle errors
         let t_span = (0.0, 1.0);
         let x0 = DVector::from_vec(vec![10., 10., 10., 10.]);
         let opts = SolverOptions{ first_step: Some(0.1), ..SolverOptions::default() };
         let (_times, trajectory) = match super::solve_ivp(f, t_span, x0, IntegratorType::RK45, opts) {
-            Ok(ode_result) => (ode_result.0, ode_result.1),
+            Ok(ode_result) => (ode_result.t, ode_result.y),
             Err(_) => panic!("solve_ivp failed"),
         };
 
@@ -111,4 +358,316 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_solve_ivp_DOPRI5_tolerances() {
+
+        // harmonic oscillator: x(t) = [cos(t), -sin(t)]
+        let f = |_t: Float, x: &DVector<Float>| DVector::from_vec(vec![x[1], -x[0]]);
+
+        let t_span = (0.0, 10.0);
+        let x0 = DVector::from_vec(vec![1., 0.]);
+
+        let loose = SolverOptions{ rtol: 1E-2, atol: 1E-2, ..SolverOptions::default() };
+        let tight = SolverOptions{ rtol: 1E-6, atol: 1E-6, ..SolverOptions::default() };
+
+        let loose_result = super::solve_ivp(f, t_span, x0.clone(), IntegratorType::DOPRI5, loose).unwrap();
+        let tight_result = super::solve_ivp(f, t_span, x0, IntegratorType::DOPRI5, tight).unwrap();
+
+        let exact = DVector::from_vec(vec![(10 as Float).cos(), -(10 as Float).sin()]);
+        let loose_error = (loose_result.y.last().unwrap() - &exact).amax();
+        let tight_error = (tight_result.y.last().unwrap() - &exact).amax();
+
+        assert!(tight_error < 1E-4);
+        assert!(tight_error < loose_error);
+        assert!(tight_result.t.len() > loose_result.t.len());
+        assert_eq!(*tight_result.t.last().unwrap(), 10.0);
+
+    }
+
+    #[test]
+    fn test_solve_ivp_embedded_atol() {
+
+        // harmonic oscillator: x(t) = [cos(t), -sin(t)]
+        let f = |_t: Float, x: &DVector<Float>| DVector::from_vec(vec![x[1], -x[0]]);
+
+        let t_span = (0.0, 10.0);
+        let x0 = DVector::from_vec(vec![1., 0.]);
+
+        // The absolute tolerance bounds the error of components near zero
+        for method in [IntegratorType::RKF45, IntegratorType::RK45] {
+            let loose = SolverOptions{ rtol: 1E-3, atol: 1E-1, ..SolverOptions::default() };
+            let tight = SolverOptions{ rtol: 1E-3, atol: 1E-7, ..SolverOptions::default() };
+
+            let loose_result = super::solve_ivp(f, t_span, x0.clone(), method, loose).unwrap();
+            let tight_result = super::solve_ivp(f, t_span, x0.clone(), method, tight).unwrap();
+
+            assert!(tight_result.t.len() > loose_result.t.len());
+            assert_eq!(*tight_result.t.last().unwrap(), 10.0);
+        }
+
+    }
+
+    #[test]
+    fn test_solve_ivp_t_eval() {
+
+        let f = |_t: Float, x: &DVector<Float>| DVector::from_vec(vec![x[1], -x[0]]);
+
+        let t_span = (0.0, 5.0);
+        let x0 = DVector::from_vec(vec![1., 0.]);
+        let t_eval: Vec<Float> = (0..51).map(|k| 0.1 * k as Float).collect();
+        let opts = SolverOptions{
+            rtol: 1E-6,
+            atol: 1E-6,
+            t_eval: Some(t_eval.clone()),
+            dense_output: true,
+            ..SolverOptions::default()
+        };
+
+        let result = super::solve_ivp(f, t_span, x0, IntegratorType::DOPRI5, opts).unwrap();
+
+        assert_eq!(result.t, t_eval);
+        assert_eq!(result.y.len(), t_eval.len());
+        for (t, y) in result.t.iter().zip(result.y.iter()) {
+            let exact = DVector::from_vec(vec![t.cos(), -t.sin()]);
+            assert!((y - exact).amax() < 1E-3);
+        }
+
+        // interpolant is available between the requested times
+        let sol = result.sol.unwrap();
+        let exact = DVector::from_vec(vec![(2.25 as Float).cos(), -(2.25 as Float).sin()]);
+        assert!((sol.evaluate(2.25).unwrap() - exact).amax() < 1E-3);
+
+    }
+
+    #[test]
+    fn test_solve_ivp_events() {
+
+        use super::events::{Event, EventDirection};
+
+        // harmonic oscillator: x(t) = [cos(t), -sin(t)]
+        let f = |_t: Float, x: &DVector<Float>| DVector::from_vec(vec![x[1], -x[0]]);
+
+        let t_span = (0.0, 10.0);
+        let x0 = DVector::from_vec(vec![1., 0.]);
+
+        // x0 crosses zero at pi/2 (falling), 3pi/2 (rising), 5pi/2 (falling)
+        let crossing = Event::new(|_t: Float, x: &DVector<Float>| x[0], EventDirection::Either, false);
+        let rising = Event::new(|_t: Float, x: &DVector<Float>| x[0], EventDirection::Rising, false);
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-6, events: vec![crossing, rising], ..SolverOptions::default() };
+
+        let result = super::solve_ivp(f, t_span, x0.clone(), IntegratorType::DOPRI5, opts).unwrap();
+
+        let pi = crate::float::consts::PI;
+        assert_eq!(result.t_events[0].len(), 3);
+        assert_eq!(result.t_events[1].len(), 1);
+        assert_relative_eq!(result.t_events[0][0], 0.5*pi, epsilon = 1E-3);
+        assert_relative_eq!(result.t_events[1][0], 1.5*pi, epsilon = 1E-3);
+        assert_relative_eq!(result.y_events[1][0][1], 1.0, epsilon = 1E-3);
+        assert!(!result.terminated);
+
+        // terminal event ends the solution at the first falling crossing
+        let terminal = Event::new(|_t: Float, x: &DVector<Float>| x[0], EventDirection::Falling, true);
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-6, events: vec![terminal], ..SolverOptions::default() };
+
+        let result = super::solve_ivp(f, t_span, x0, IntegratorType::DOPRI5, opts).unwrap();
+
+        assert!(result.terminated);
+        assert_relative_eq!(*result.t.last().unwrap(), 0.5*pi, epsilon = 1E-3);
+        assert_relative_eq!(result.y.last().unwrap()[0], 0.0, epsilon = 1E-3);
+
+    }
+
+    #[test]
+    fn test_solve_ivp_terminal_event() {
+
+        use super::events::{Event, EventDirection};
+
+        // harmonic oscillator whose dynamics are undefined beyond t = 2, after the terminal event
+        let f = |t: Float, x: &DVector<Float>| match t > 2.0 {
+            true => x.map(|_| Float::NAN),
+            false => DVector::from_vec(vec![x[1], -x[0]]),
+        };
+
+        let t_span = (0.0, 10.0);
+        let pi = crate::float::consts::PI;
+        let methods = vec![
+            IntegratorType::ForwardEuler,
+            IntegratorType::RK4,
+            IntegratorType::ABM4,
+            IntegratorType::RK45,
+            IntegratorType::DOPRI5,
+            IntegratorType::Rosenbrock23,
+            IntegratorType::Leapfrog,
+        ];
+
+        for method in methods {
+            let x0 = DVector::from_vec(vec![1., 0.]);
+            let terminal = Event::new(|_t: Float, x: &DVector<Float>| x[0], EventDirection::Falling, true);
+            let opts = SolverOptions{ first_step: Some(0.01), events: vec![terminal], ..SolverOptions::default() };
+
+            let result = super::solve_ivp(f, t_span, x0, method, opts).unwrap();
+
+            assert!(result.terminated);
+            assert_eq!(result.t_events[0].len(), 1);
+            assert_relative_eq!(*result.t.last().unwrap(), 0.5*pi, epsilon = 2E-2);
+            assert_eq!(*result.t.last().unwrap(), result.t_events[0][0]);
+        }
+
+    }
+
+    #[test]
+    fn test_solve_ivp_Rosenbrock23() {
+
+        use na::DMatrix;
+        use crate::controls::models::LinearQuadraticRegulator as LQR;
+        use crate::dynamics::models::DoubleIntegrator1D;
+
+        // high-gain LQR closed loop
+        let model = DoubleIntegrator1D::new();
+        let Q = DMatrix::<Float>::identity(2, 2) * 1E6;
+        let R = DMatrix::from_vec(1, 1, vec![1E-2]);
+        let lqr = LQR::new(model.dynamics().A.clone(), model.dynamics().B.clone(), Q, R).unwrap();
+        let (K, _P) = lqr.solve();
+
+        let f = |t: Float, x: &DVector<Float>| {
+            let u = -&K * x;
+            model.f(t, x, Some(&u))
+        };
+
+        // closed-loop Jacobian supplied by the model
+        let jacobian = |t: Float, x: &DVector<Float>| model.jacobian(t, x, None) - &model.dynamics().B * &K;
+
+        let t_span = (0.0, 10.0);
+        let x0 = DVector::from_vec(vec![10., -40.]);
+        let opts = SolverOptions{ first_step: Some(0.1), jacobian: Some(Box::new(jacobian)), ..SolverOptions::default() };
+
+        let result = super::solve_ivp(f, t_span, x0.clone(), IntegratorType::Rosenbrock23, opts).unwrap();
+        assert!(result.y.last().unwrap().amax() < 1E-2);
+
+        // finite difference Jacobian when none is given
+        let opts = SolverOptions{ first_step: Some(0.1), ..SolverOptions::default() };
+        let result = super::solve_ivp(f, t_span, x0, IntegratorType::Rosenbrock23, opts).unwrap();
+        assert!(result.y.last().unwrap().amax() < 1E-2);
+
+    }
+
+    #[test]
+    fn test_solve_ivp_symplectic() {
+
+        use crate::dynamics::models::DoublePendulum;
+        use super::symplectic::SecondOrderPartition;
+
+        // double pendulum states are ordered as (angle, rate) pairs, given by the model partition
+        let model = DoublePendulum::new();
+        assert_eq!(model.partition(), Some(SecondOrderPartition::interleaved(4)));
+        let f = |t: Float, x: &DVector<Float>| model.f(t, x, None);
+
+        let t_span = (0.0, 1.0);
+        let x0 = DVector::from_vec(vec![0.1, 0., -0.1, 0.]);
+        let reference = super::solve_ivp(f, t_span, x0.clone(), IntegratorType::DOPRI5, SolverOptions{ rtol: 1E-6, atol: 1E-6, ..SolverOptions::default() }).unwrap();
+        let x_ref = reference.y.last().unwrap();
+
+        for method in [IntegratorType::VelocityVerlet, IntegratorType::Leapfrog, IntegratorType::Yoshida4].iter() {
+            let opts = SolverOptions{ first_step: Some(0.01), partition: model.partition(), ..SolverOptions::default() };
+            let result = super::solve_ivp(f, t_span, x0.clone(), *method, opts).unwrap();
+
+            assert_eq!(*result.t.last().unwrap(), 1.0);
+            assert!((result.y.last().unwrap() - x_ref).amax() < 5E-3);
+        }
+
+        // partition must index distinct components of the state
+        let opts = SolverOptions{ partition: Some(SecondOrderPartition::new(vec![0, 1], vec![1, 2])), ..SolverOptions::default() };
+        assert!(super::solve_ivp(f, t_span, x0, IntegratorType::Leapfrog, opts).is_err());
+
+        // an odd state dimension has no default partition, but is accepted by other solvers
+        let f = |_t: Float, x: &DVector<Float>| -x;
+        let x0 = DVector::from_vec(vec![1., 0., 0.]);
+        assert!(super::solve_ivp(f, t_span, x0.clone(), IntegratorType::VelocityVerlet, SolverOptions::default()).is_err());
+        assert!(super::solve_ivp(f, t_span, x0, IntegratorType::RK4, SolverOptions::default()).is_ok());
+
+    }
+
+    #[test]
+    fn test_solve_ivp_max_step() {
+
+        let f = |_t: Float, x: &DVector<Float>| -x;
+
+        let t_span = (0.0, 1.0);
+        let x0 = DVector::from_vec(vec![1.]);
+
+        for method in [IntegratorType::RKF45, IntegratorType::RK45, IntegratorType::DOPRI5] {
+            let opts = SolverOptions{ first_step: Some(0.01), max_step: Some(0.05), ..SolverOptions::default() };
+
+            let result = super::solve_ivp(f, t_span, x0.clone(), method, opts).unwrap();
+
+            for k in 0..result.t.len()-1 {
+                assert!(result.t[k+1] - result.t[k] <= 0.05 + 1E-6);
+            }
+        }
+
+    }
+
+    #[test]
+    fn test_solve_ivp_min_step() {
+
+        // stiff decay forces the step size below min_step
+        let f = |_t: Float, x: &DVector<Float>| -1E6 * x;
+
+        let t_span = (0.0, 1.0);
+        let x0 = DVector::from_vec(vec![1.]);
+        let opts = SolverOptions{ first_step: Some(0.1), min_step: Some(0.01), ..SolverOptions::default() };
+
+        match super::solve_ivp(f, t_span, x0, IntegratorType::DOPRI5, opts) {
+            Err(super::IntegrateError::MinStepError(_)) => (),
+            _ => panic!("expected MinStepError"),
+        }
+
+    }
+
+    #[test]
+    fn test_solve_ivp_non_finite() {
+
+        // dynamics are undefined beyond t = 0.5
+        let f = |t: Float, x: &DVector<Float>| if t > 0.5 { x.map(|_| Float::NAN) } else { -x };
+
+        let t_span = (0.0, 1.0);
+        let methods = vec![
+            IntegratorType::ForwardEuler,
+            IntegratorType::MidpointEuler,
+            IntegratorType::RK4,
+            IntegratorType::ABM4,
+            IntegratorType::RKF45,
+            IntegratorType::RK45,
+            IntegratorType::DOPRI5,
+            IntegratorType::Rosenbrock23,
+            IntegratorType::VelocityVerlet,
+        ];
+
+        for method in methods {
+            let x0 = DVector::from_vec(vec![1., 0.]);
+            let opts = SolverOptions{ first_step: Some(0.1), ..SolverOptions::default() };
+            assert!(super::solve_ivp(f, t_span, x0, method, opts).is_err());
+        }
+
+        let x0 = DVector::from_vec(vec![Float::NAN]);
+        assert!(super::solve_ivp(f, t_span, x0, IntegratorType::RK45, SolverOptions::default()).is_err());
+
+    }
+
+    #[test]
+    fn test_solve_ivp_max_iterations() {
+
+        let f = |_t: Float, x: &DVector<Float>| -x;
+
+        let t_span = (0.0, 1.0);
+        let x0 = DVector::from_vec(vec![1.]);
+        let opts = SolverOptions{ first_step: Some(0.01), max_step: Some(0.01), max_iterations: 10, ..SolverOptions::default() };
+
+        match super::solve_ivp(f, t_span, x0, IntegratorType::DOPRI5, opts) {
+            Err(super::IntegrateError::MaxIterationsError(10)) => (),
+            _ => panic!("expected MaxIterationsError"),
+        }
+
+    }
+
 }
@@ -0,0 +1,128 @@
+
+use na::DVector;
+use crate::math::integrate::{IntegrateError, OdeResult};
+use crate::math::integrate::events::{Event, EventMonitor};
+use crate::math::integrate::runge_kutta::rk4_step;
+use crate::float::Float;
+
+// Fourth-order Adams-Bashforth-Moulton predictor-corrector (PECE)
+// The Adams-Bashforth predictor and Adams-Moulton corrector reuse the derivatives of the three
+// previous steps, which are started with classical RK4. The time grid is t0 + k*step, as for RK4.
+// The coefficients assume a uniform step, so a final step clipped to land on tf is taken with RK4.
+// "Numerical Analysis" - R.L. Burden, J.D. Faires
+pub fn ABM4<F>(
+    f: F,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let iterations = ((tf - t0) / step).ceil() as usize;
+
+    let mut grid: Vec<Float> = (0..iterations).map(|k| t0 + k as Float * step).collect();
+    grid.push(tf);
+
+    let mut time: Vec<Float> = Vec::with_capacity(iterations + 1);
+    let mut y: Vec<DVector<Float>> = Vec::with_capacity(iterations + 1);
+    let mut dydt: Vec<DVector<Float>> = Vec::with_capacity(iterations + 1);
+
+    let mut monitor = EventMonitor::new(events, t0, &y0);
+
+    time.push(t0);
+    dydt.push(f(t0, &y0));
+    y.push(y0);
+
+    for k in 0..iterations {
+
+        let yk = &y[k];
+        let h = grid[k+1] - grid[k];
+
+        let y_next = if k < 3 || (k + 1 == iterations && h < step) {
+
+            // Startup, or the clipped final step
+            rk4_step(&f, grid[k], yk, h)
+
+        } else {
+
+            // Predict
+            let y_p = yk + (h/24.0) * (55.0 * &dydt[k] - 59.0 * &dydt[k-1] + 37.0 * &dydt[k-2] - 9.0 * &dydt[k-3]);
+
+            // Evaluate, then correct
+            let f_p = f(grid[k+1], &y_p);
+            yk + (h/24.0) * (9.0 * f_p + 19.0 * &dydt[k] - 5.0 * &dydt[k-1] + &dydt[k-2])
+
+        };
+
+        if !y_next.iter().all(|x| x.is_finite()) {
+            return Err(IntegrateError::NonFiniteStateError(grid[k+1]));
+        }
+
+        time.push(grid[k+1]);
+        dydt.push(f(grid[k+1], &y_next));
+        y.push(y_next);
+
+        if monitor.step(&f, &mut time, &mut y) {
+            break;
+        }
+
+    }
+
+    Ok(OdeResult::new(time, y, 0).with_events(monitor))
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ABM4_convergence() {
+
+        use crate::dynamics::models::closed_form_solution::ClohessyWiltshireSolution;
+
+        // Clohessy-Wiltshire equations of motion
+        let n = 0.00113;
+        let f = |_t: Float, x: &DVector<Float>| {
+            DVector::from_vec(vec![
+                x[3],
+                x[4],
+                x[5],
+                3.*n*n*x[0] + 2.*n*x[4],
+                -2.*n*x[3],
+                -n*n*x[2]
+            ])
+        };
+
+        let y0 = DVector::from_vec(vec![0., 0., 0., 0.1, 0., 0.]);
+        let t0 = 0.0;
+        let tf = 6000.0;
+        let exact = ClohessyWiltshireSolution(tf, &y0);
+
+        let error = |step: Float| {
+            let result = ABM4(f, t0, y0.clone(), tf, step, &[]).unwrap();
+            let (t, y) = (result.t, result.y);
+            assert_eq!(t[t.len()-1], tf);
+            (&y[y.len()-1] - &exact).amax()
+        };
+
+        // halving the step reduces the global error by 2^4
+        let order = (error(125.0) / error(62.5)).log2();
+        assert!(order > 3.5);
+
+        // fixed time grid, with the final step clipped to tf
+        let result = ABM4(f, t0, y0.clone(), tf, 130.0, &[]).unwrap();
+        let t = &result.t;
+        assert_eq!(t.len(), 48);
+        assert_eq!(t[1] - t[0], 130.0);
+        assert_eq!(t[t.len()-1], tf);
+        assert!((&result.y[t.len()-1] - &exact).amax() < 2.0 * error(125.0));
+
+    }
+
+}
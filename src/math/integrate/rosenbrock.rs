@@ -0,0 +1,209 @@
+
+use na::{DMatrix, DVector};
+use crate::math::integrate::{IntegrateError, OdeResult, SolverOptions};
+use crate::math::integrate::events::EventMonitor;
+use crate::float::Float;
+
+// Linearly implicit Rosenbrock method of order 2(3) for stiff problems
+// L-stable second-order solution with an embedded third-order error estimate. Each step
+// solves three linear systems with the iteration matrix W = I - h*d*J, so no Newton iterations
+// are required.
+// "The MATLAB ODE Suite" - L.F. Shampine, M.W. Reichelt
+pub fn Rosenbrock23<F, J>(
+    f: F,
+    jacobian: J,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    options: &SolverOptions
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+    J: Fn(Float, &DVector<Float>) -> DMatrix<Float>,
+{
+
+    let iterations = (tf - t0) / step;
+    let rtol = options.rtol;
+    let atol = options.atol;
+    let max_step = options.max_step.unwrap_or(tf - t0);
+    let min_step = options.min_step.unwrap_or(0.0);
+
+    let mut h = step.min(max_step);
+    let mut time: Vec<Float> = Vec::with_capacity(iterations as usize);
+    let mut y: Vec<DVector<Float>> = Vec::with_capacity(iterations as usize);
+
+    let n = y0.len();
+    let mut tk = t0;
+    let mut f0 = f(t0, &y0);
+
+    let mut monitor = EventMonitor::new(&options.events, t0, &y0);
+
+    time.push(t0);
+    y.push(y0);
+
+    let mut n_rejected = 0;
+    let mut n_iterations = 0;
+    let mut last_rejected = false;
+
+    // step size scale factor safety bounds
+    let safety = 0.8;
+    let fac_min = 0.2;
+    let fac_max = 5.0;
+
+    let d = 1.0 / (2.0 + (2 as Float).sqrt());
+    let e32 = 6.0 + (2 as Float).sqrt();
+
+    while tk < tf {
+
+        let yk = y[y.len()-1].clone();
+
+        if !f0.iter().all(|x| x.is_finite()) {
+            return Err(IntegrateError::NonFiniteStateError(tk));
+        }
+
+        // Jacobian and time derivative of f are held fixed over the step and its retries
+        let J = jacobian(tk, &yk);
+        let dt = Float::EPSILON.sqrt() * tk.abs().max(1.0);
+        let dfdt = (f(tk + dt, &yk) - &f0) / dt;
+
+        loop {
+
+            n_iterations += 1;
+            if n_iterations > options.max_iterations {
+                return Err(IntegrateError::MaxIterationsError(options.max_iterations));
+            }
+
+            // Clip the final step to land on tf
+            let last_step = tk + h >= tf;
+            if last_step {
+                h = tf - tk;
+            }
+
+            // Iteration matrix
+            let W = DMatrix::<Float>::identity(n, n) - (h * d) * &J;
+            let lu = W.lu();
+
+            let k1 = match lu.solve(&(&f0 + (h * d) * &dfdt)) {
+                Some(k) => k,
+                None => return Err(IntegrateError::SingularMatrixError(tk)),
+            };
+
+            let f1 = f(tk + 0.5*h, &(&yk + (0.5*h) * &k1));
+            let k2 = match lu.solve(&(&f1 - &k1)) {
+                Some(k) => k + &k1,
+                None => return Err(IntegrateError::SingularMatrixError(tk)),
+            };
+
+            // Second-order result
+            let y_next = &yk + h * &k2;
+
+            let f2 = f(tk + h, &y_next);
+            let k3 = match lu.solve(&(&f2 - e32 * (&k2 - &f1) - 2.0 * (&k1 - &f0) + (h * d) * &dfdt)) {
+                Some(k) => k,
+                None => return Err(IntegrateError::SingularMatrixError(tk)),
+            };
+
+            // Embedded error estimate
+            let error = (h / 6.0) * (&k1 - 2.0 * &k2 + &k3);
+
+            // RMS error norm weighted by the tolerance of each component
+            let mut sum = 0.0;
+            for i in 0..n {
+                let scale = atol + rtol * yk[i].abs().max(y_next[i].abs());
+                sum += (error[i] / scale).powi(2);
+            }
+            let error_norm = (sum / n.max(1) as Float).sqrt();
+
+            // Optimal step size scale factor
+            let s = if error_norm == 0.0 {
+                fac_max
+            } else {
+                (safety * error_norm.powf(-1.0/3.0)).max(fac_min).min(fac_max)
+            };
+
+            // If step size satisfies error tolerance, accept this value
+            if error_norm <= 1.0 {
+
+                tk = if last_step { tf } else { tk + h };
+
+                // Do not grow the step immediately after a rejection
+                let s = if last_rejected { s.min(1.0) } else { s };
+                h = (s*h).min(max_step);
+
+                time.push(tk);
+                y.push(y_next);
+
+                f0 = f2;
+
+                last_rejected = false;
+
+                break;
+
+            } else {
+
+                h *= s;
+                n_rejected += 1;
+                last_rejected = true;
+
+                // Smallest step which still advances time at this precision
+                let h_min = min_step.max(10.0 * Float::EPSILON * tk.abs());
+                if h < h_min {
+                    return Err(IntegrateError::MinStepError(h));
+                }
+
+            }
+
+        }
+
+        if monitor.step(&f, &mut time, &mut y) {
+            break;
+        }
+
+    }
+
+    Ok(OdeResult::new(time, y, n_rejected).with_events(monitor))
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_Rosenbrock23() {
+
+        use crate::math::jacobian::finite_difference;
+
+        // Robertson chemical kinetics problem
+        let f = |_t: Float, x: &DVector<Float>| {
+            DVector::from_vec(vec![
+                -0.04*x[0] + 1E4*x[1]*x[2],
+                0.04*x[0] - 1E4*x[1]*x[2] - 3E7*x[1]*x[1],
+                3E7*x[1]*x[1]
+            ])
+        };
+        let jacobian = |t: Float, x: &DVector<Float>| finite_difference(|x: &DVector<Float>| f(t, x), x);
+
+        let y0 = DVector::from_vec(vec![1., 0., 0.]);
+
+        let t0 = 0.0;
+        let tf = 40.0;
+        let step = 1E-4;
+        let opts = SolverOptions{ rtol: 1E-3, atol: 1E-6, ..SolverOptions::default() };
+        let result = Rosenbrock23(f, jacobian, t0, y0, tf, step, &opts).unwrap();
+
+        let y = &result.y[result.y.len()-1];
+
+        // reference solution at t = 40
+        assert_relative_eq!(y[0], 0.7158, epsilon = 1E-2);
+        assert_relative_eq!(y[2], 0.2842, epsilon = 1E-2);
+        assert_relative_eq!(y.sum(), 1.0, epsilon = 1E-3);
+
+        // an explicit method would need tens of thousands of steps
+        assert!(result.t.len() < 1000);
+
+    }
+
+}
@@ -1,28 +1,34 @@
 
 use na::DVector;
-use na::base::UniformNorm;
-use std::f32;
+use crate::math::integrate::{IntegrateError, OdeResult, SolverOptions, fixed_step};
+use crate::math::integrate::events::{Event, EventMonitor};
+use crate::float::Float;
 
 // Reference: https://math.okstate.edu/people/yqwang/teaching/math4513_fall11/Notes/rungekutta.pdf
 // Runge-Kutta-Fehlberg method
 pub fn RKF45<F>(
     f: F,
-    t0: f32,
-    y0: DVector<f32>,
-    tf: f32,
-    step: f32,
-    rtol: f32
-) -> (Vec<f32>, Vec<DVector<f32>>)
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    options: &SolverOptions
+) -> Result<OdeResult, IntegrateError>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
 {
 
     let iterations = (tf - t0) / step;
+    let rtol = options.rtol;
+    let atol = options.atol;
+    let max_step = options.max_step.unwrap_or(tf - t0);
+    let min_step = options.min_step.unwrap_or(0.0);
 
-    let mut h = step;
-    let mut time: Vec<f32> = Vec::with_capacity(iterations as usize);
-    let mut y: Vec<DVector<f32>> = Vec::with_capacity(iterations as usize);
-    let atol = 1E-10;
+    let mut h = step.min(max_step);
+    let mut time: Vec<Float> = Vec::with_capacity(iterations as usize);
+    let mut y: Vec<DVector<Float>> = Vec::with_capacity(iterations as usize);
+
+    let mut monitor = EventMonitor::new(&options.events, t0, &y0);
 
     time.push(t0);
     y.push(y0);
@@ -35,6 +41,9 @@ where
     // global iteration
     let mut _it = 1;
 
+    let mut n_rejected = 0;
+    let mut n_iterations = 0;
+
     let a2 = 1./4.;
     let a3 = 3./8.;
     let a4 = 12./13.;
@@ -76,12 +85,20 @@ where
 
     while tk < tf {
 
+        n_iterations += 1;
+        if n_iterations > options.max_iterations {
+            return Err(IntegrateError::MaxIterationsError(options.max_iterations));
+        }
+
         h = h.min(tf - tk);
 
         // let tk = time[time.len()-1];
         let yk = &y[y.len()-1];
 
         let k1 = h * f(tk, yk);
+        if !k1.iter().all(|x| x.is_finite()) {
+            return Err(IntegrateError::NonFiniteStateError(tk));
+        }
         let k2 = h * f(tk + a2*h, &(yk + b21 * &k1));
         let k3 = h * f(tk + a3*h, &(yk + b31 * &k1 + b32 * &k2));
         let k4 = h * f(tk + a4*h, &(yk + b41 * &k1 + b42 * &k2 + b43 * &k3));
@@ -94,21 +111,25 @@ where
         // Fifth-order Runge-Kutta result
         let w2 = yk + ch1*&k1 + ch3*&k3 + ch4*&k4 + ch5*&k5 + ch6*&k6;
 
-        // Error tolerance
-        let tol = atol + yk.apply_norm(&UniformNorm)*rtol;
-
-        // L-infinity error norm
-        let truncation_error = ((&w2 - &w1) / h).apply_norm(&UniformNorm);
-        // let truncation_error = h * (ce1*&k1 + ce3*&k3 + ce4*&k4 + ce5*&k5 + ce6*&k6).apply_norm(&UniformNorm);
+        // L-infinity norm of the truncation error weighted by the tolerance of each component,
+        // propagating a non-finite error
+        let mut error_norm: Float = 0.0;
+        for i in 0..yk.len() {
+            let scale = atol + rtol * yk[i].abs().max(w2[i].abs());
+            let error = (w2[i] - w1[i]).abs() / h / scale;
+            if error.is_nan() || error > error_norm {
+                error_norm = error;
+            }
+        }
 
         // Optimal step size scale factor
-        let s = 0.84 * (tol / truncation_error).powf(0.25);
+        let s = 0.84 * error_norm.powf(-0.25);
 
         // If step size satisfies error tolerance, accept this value
-        if truncation_error <= tol {
+        if error_norm <= 1.0 {
 
             tk += h;
-            h = s*h;
+            h = (s*h).min(max_step);
             _it += 1;
 
             time.push(tk);
@@ -116,19 +137,34 @@ where
 
             _k = 0;
 
-        } else if _k == 0 { // Tolerance not met for first time in this step
+            if monitor.step(&f, &mut time, &mut y) {
+                break;
+            }
 
-            h = s*h;
-            _k += 1;
+        } else {
+
+            if _k == 0 && s.is_normal() { // Tolerance not met for first time in this step
 
-        } else { // continue searching for a better step size
+                h = s*h;
 
-            h = h / 2.0;
+            } else { // continue searching for a better step size, or recover from a non-finite error
+
+                h = h / 2.0;
+
+            }
+
+            _k += 1;
+            n_rejected += 1;
+
+            // Step size no longer advances time at this precision
+            if h < min_step || tk + h == tk {
+                return Err(IntegrateError::MinStepError(h));
+            }
 
         }
     }
 
-    (time, y)
+    Ok(OdeResult::new(time, y, n_rejected).with_events(monitor))
 
 }
 
@@ -138,22 +174,27 @@ where
 // J.R.Dormand and P.J.Prince
 pub fn RK45<F>(
     f: F,
-    t0: f32,
-    y0: DVector<f32>,
-    tf: f32,
-    step: f32,
-    rtol: f32
-) -> (Vec<f32>, Vec<DVector<f32>>)
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    options: &SolverOptions
+) -> Result<OdeResult, IntegrateError>
 where
-    F: Fn(f32, &DVector<f32>) -> DVector<f32>,
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
 {
 
     let iterations = (tf - t0) / step;
+    let rtol = options.rtol;
+    let atol = options.atol;
+    let max_step = options.max_step.unwrap_or(tf - t0);
+    let min_step = options.min_step.unwrap_or(0.0);
 
-    let mut h = step;
-    let mut time: Vec<f32> = Vec::with_capacity(iterations as usize);
-    let mut y: Vec<DVector<f32>> = Vec::with_capacity(iterations as usize);
-    let atol = 1E-10;
+    let mut h = step.min(max_step);
+    let mut time: Vec<Float> = Vec::with_capacity(iterations as usize);
+    let mut y: Vec<DVector<Float>> = Vec::with_capacity(iterations as usize);
+
+    let mut monitor = EventMonitor::new(&options.events, t0, &y0);
 
     time.push(t0);
     y.push(y0);
@@ -166,6 +207,9 @@ where
     // global iteration
     let mut _it = 1;
 
+    let mut n_rejected = 0;
+    let mut n_iterations = 0;
+
     let a2 = 1./5.;
     let a3 = 3./10.;
     let a4 = 4./5.;
@@ -209,12 +253,20 @@ where
 
     while tk < tf {
 
+        n_iterations += 1;
+        if n_iterations > options.max_iterations {
+            return Err(IntegrateError::MaxIterationsError(options.max_iterations));
+        }
+
         h = h.min(tf - tk);
 
         // let tk = time[time.len()-1];
         let yk = &y[y.len()-1];
 
         let k1 = h * f(tk, yk);
+        if !k1.iter().all(|x| x.is_finite()) {
+            return Err(IntegrateError::NonFiniteStateError(tk));
+        }
         let k2 = h * f(tk + a2*h, &(yk + b21 * &k1));
         let k3 = h * f(tk + a3*h, &(yk + b31 * &k1 + b32 * &k2));
         let k4 = h * f(tk + a4*h, &(yk + b41 * &k1 + b42 * &k2 + b43 * &k3));
@@ -228,20 +280,25 @@ where
         // Fifth-order Runge-Kutta result
         let w2 = yk + ch1*&k1 + ch3*&k3 + ch4*&k4 + ch5*&k5 + ch6*&k6;
 
-        // Error tolerance
-        let tol = atol + yk.apply_norm(&UniformNorm)*rtol;
-
-        // L-infinity error norm
-        let truncation_error = ((&w2 - &w1) / h).apply_norm(&UniformNorm);
+        // L-infinity norm of the truncation error weighted by the tolerance of each component,
+        // propagating a non-finite error
+        let mut error_norm: Float = 0.0;
+        for i in 0..yk.len() {
+            let scale = atol + rtol * yk[i].abs().max(w2[i].abs());
+            let error = (w2[i] - w1[i]).abs() / h / scale;
+            if error.is_nan() || error > error_norm {
+                error_norm = error;
+            }
+        }
 
         // Optimal step size scale factor
-        let s = 0.9 * (tol / truncation_error).powf(0.25);
+        let s = 0.9 * error_norm.powf(-0.25);
 
         // If step size satisfies error tolerance, accept this value
-        if truncation_error <= tol {
+        if error_norm <= 1.0 {
 
             tk += h;
-            h = s*h;
+            h = (s*h).min(max_step);
             _it += 1;
 
             time.push(tk);
@@ -249,24 +306,243 @@ where
 
             _k = 0;
 
-        } else if _k == 0 { // Tolerance not met for first time in this step
+            if monitor.step(&f, &mut time, &mut y) {
+                break;
+            }
 
-            h = s*h;
-            _k += 1;
+        } else {
+
+            if _k == 0 && s.is_normal() { // Tolerance not met for first time in this step
 
-        } else { // continue searching for a better step size
+                h = s*h;
 
-            h = h / 2.0;
+            } else { // continue searching for a better step size, or recover from a non-finite error
+
+                h = h / 2.0;
+
+            }
+
+            _k += 1;
+            n_rejected += 1;
+
+            // Step size no longer advances time at this precision
+            if h < min_step || tk + h == tk {
+                return Err(IntegrateError::MinStepError(h));
+            }
 
         }
     }
 
 
-    (time, y)
+    Ok(OdeResult::new(time, y, n_rejected).with_events(monitor))
+
+}
+
+
+// Classical fourth-order Runge-Kutta method with a fixed step size
+// The time grid is t0 + k*step, with the final step clipped to land on tf
+pub fn RK4<F>(
+    f: F,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let method = |t: Float, x: &mut DVector<Float>, h: Float| {
+        *x = rk4_step(&f, t, x, h);
+    };
+
+    fixed_step(&f, method, t0, y0, tf, step, events)
+
+}
+
+/// Advances x(t) by a single classical Runge-Kutta step of size h
+pub(crate) fn rk4_step<F>(f: &F, t: Float, x: &DVector<Float>, h: Float) -> DVector<Float>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let k1 = f(t, x);
+    let k2 = f(t + h/2.0, &(x + (h/2.0) * &k1));
+    let k3 = f(t + h/2.0, &(x + (h/2.0) * &k2));
+    let k4 = f(t + h, &(x + h * &k3));
+
+    x + (h/6.0) * (k1 + 2.0 * k2 + 2.0 * k3 + k4)
 
 }
 
 
+// Dormand-Prince Runge-Kutta method of order 5(4) with per-component error control
+// Local extrapolation is used (the fifth-order result advances the solution) and the
+// final stage is reused as the first stage of the next step (FSAL)
+// "Solving Ordinary Differential Equations I: Nonstiff Problems" - E. Hairer, S.P. Norsett, G. Wanner
+pub fn DOPRI5<F>(
+    f: F,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    options: &SolverOptions
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let iterations = (tf - t0) / step;
+    let rtol = options.rtol;
+    let atol = options.atol;
+    let max_step = options.max_step.unwrap_or(tf - t0);
+    let min_step = options.min_step.unwrap_or(0.0);
+
+    let mut h = step.min(max_step);
+    let mut time: Vec<Float> = Vec::with_capacity(iterations as usize);
+    let mut y: Vec<DVector<Float>> = Vec::with_capacity(iterations as usize);
+
+    let mut tk = t0;
+    let mut k1 = f(t0, &y0);
+
+    let mut monitor = EventMonitor::new(&options.events, t0, &y0);
+
+    time.push(t0);
+    y.push(y0);
+
+    let mut n_rejected = 0;
+    let mut n_iterations = 0;
+    let mut last_rejected = false;
+
+    // step size scale factor safety bounds
+    let safety = 0.9;
+    let fac_min = 0.2;
+    let fac_max = 10.0;
+
+    let a2 = 1./5.;
+    let a3 = 3./10.;
+    let a4 = 4./5.;
+    let a5 = 8./9.;
+
+    let b21 = 1./5.;
+    let b31 = 3./40.;
+    let b32 = 9./40.;
+    let b41 = 44./45.;
+    let b42 = -56./15.;
+    let b43 = 32./9.;
+    let b51 = 19372./6561.;
+    let b52 = -25360./2187.;
+    let b53 = 64448./6561.;
+    let b54 = -212./729.;
+    let b61 = 9017./3168.;
+    let b62 = -355./33.;
+    let b63 = 46732./5247.;
+    let b64 = 49./176.;
+    let b65 = -5103./18656.;
+
+    // Fifth-order weights
+    let c1 = 35./384.;
+    let c3 = 500./1113.;
+    let c4 = 125./192.;
+    let c5 = -2187./6784.;
+    let c6 = 11./84.;
+
+    // Difference between fifth and fourth-order weights
+    let e1 = 71./57600.;
+    let e3 = -71./16695.;
+    let e4 = 71./1920.;
+    let e5 = -17253./339200.;
+    let e6 = 22./525.;
+    let e7 = -1./40.;
+
+    while tk < tf {
+
+        n_iterations += 1;
+        if n_iterations > options.max_iterations {
+            return Err(IntegrateError::MaxIterationsError(options.max_iterations));
+        }
+
+        if !k1.iter().all(|x| x.is_finite()) {
+            return Err(IntegrateError::NonFiniteStateError(tk));
+        }
+
+        // Clip the final step to land on tf
+        let last_step = tk + h >= tf;
+        if last_step {
+            h = tf - tk;
+        }
+
+        let yk = &y[y.len()-1];
+
+        let k2 = f(tk + a2*h, &(yk + h * (b21 * &k1)));
+        let k3 = f(tk + a3*h, &(yk + h * (b31 * &k1 + b32 * &k2)));
+        let k4 = f(tk + a4*h, &(yk + h * (b41 * &k1 + b42 * &k2 + b43 * &k3)));
+        let k5 = f(tk + a5*h, &(yk + h * (b51 * &k1 + b52 * &k2 + b53 * &k3 + b54 * &k4)));
+        let k6 = f(tk + h, &(yk + h * (b61 * &k1 + b62 * &k2 + b63 * &k3 + b64 * &k4 + b65 * &k5)));
+
+        // Fifth-order Runge-Kutta result
+        let y_next = yk + h * (c1 * &k1 + c3 * &k3 + c4 * &k4 + c5 * &k5 + c6 * &k6);
+        let k7 = f(tk + h, &y_next);
+
+        // Embedded error estimate
+        let error = h * (e1 * &k1 + e3 * &k3 + e4 * &k4 + e5 * &k5 + e6 * &k6 + e7 * &k7);
+
+        // RMS error norm weighted by the tolerance of each component
+        let mut sum = 0.0;
+        for i in 0..error.len() {
+            let scale = atol + rtol * yk[i].abs().max(y_next[i].abs());
+            sum += (error[i] / scale).powi(2);
+        }
+        let error_norm = (sum / error.len().max(1) as Float).sqrt();
+
+        // Optimal step size scale factor
+        let s = if error_norm == 0.0 {
+            fac_max
+        } else {
+            (safety * error_norm.powf(-0.2)).max(fac_min).min(fac_max)
+        };
+
+        // If step size satisfies error tolerance, accept this value
+        if error_norm <= 1.0 {
+
+            tk = if last_step { tf } else { tk + h };
+
+            // Do not grow the step immediately after a rejection
+            let s = if last_rejected { s.min(1.0) } else { s };
+            h = (s*h).min(max_step);
+
+            time.push(tk);
+            y.push(y_next);
+
+            // First same as last
+            k1 = k7;
+
+            last_rejected = false;
+
+            if monitor.step(&f, &mut time, &mut y) {
+                break;
+            }
+
+        } else {
+
+            h *= s;
+            n_rejected += 1;
+            last_rejected = true;
+
+            // Smallest step which still advances time at this precision
+            let h_min = min_step.max(10.0 * Float::EPSILON * tk.abs());
+            if h < h_min {
+                return Err(IntegrateError::MinStepError(h));
+            }
+
+        }
+    }
+
+    Ok(OdeResult::new(time, y, n_rejected).with_events(monitor))
+
+}
+
 
 
 #[cfg(test)]
@@ -283,25 +559,22 @@ mod tests {
 
         let model = DoubleIntegrator3D::new();
 
-        let Q = DMatrix::<f32>::identity(6, 6);
-        let R = DMatrix::<f32>::identity(3, 3);
+        let Q = DMatrix::<Float>::identity(6, 6);
+        let R = DMatrix::<Float>::identity(3, 3);
 
         let lqr = LQR::new(
             model.dynamics().A.clone(),
             model.dynamics().B.clone(),
             Q,
             R,
-        );
+        ).unwrap();
 
-        let (K, _P) = match lqr.solve() {
-            Ok((value1, value2)) => (value1, value2),
-            Err(_) => panic!["LQR solve"],
-        };
+        let (K, _P) = lqr.solve();
 
         let y0 = DVector::from_vec(vec![10., 10., 10., 10., 10., 10.]);
 
         // Wrap dynamics/controls in appropriately defined closure
-        let f = |t: f32, x: &DVector<f32>| {
+        let f = |t: Float, x: &DVector<Float>| {
             let u = -&K * x;
             model.dynamics().f(t, x, Some(&u))
         };
@@ -310,8 +583,8 @@ mod tests {
         let tf = 10.0;
         let n = 1000.0;
         let step = (tf - t0) / n;
-        let rtol = 1E-5;
-        let (_t, y) = RKF45(f, t0, y0, tf, step, rtol);
+        let opts = SolverOptions{ rtol: 1E-5, ..SolverOptions::default() };
+        let y = RKF45(f, t0, y0, tf, step, &opts).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
@@ -327,25 +600,22 @@ mod tests {
 
         let model = DoubleIntegrator3D::new();
 
-        let Q = DMatrix::<f32>::identity(6, 6);
-        let R = DMatrix::<f32>::identity(3, 3);
+        let Q = DMatrix::<Float>::identity(6, 6);
+        let R = DMatrix::<Float>::identity(3, 3);
 
         let lqr = LQR::new(
             model.dynamics().A.clone(),
             model.dynamics().B.clone(),
             Q,
             R,
-        );
+        ).unwrap();
 
-        let (K, _P) = match lqr.solve() {
-            Ok((value1, value2)) => (value1, value2),
-            Err(_) => panic!["LQR solve"],
-        };
+        let (K, _P) = lqr.solve();
 
         let y0 = DVector::from_vec(vec![10., 10., 10., 10., 10., 10.]);
 
         // Wrap dynamics/controls in appropriately defined closure
-        let f = |t: f32, x: &DVector<f32>| {
+        let f = |t: Float, x: &DVector<Float>| {
             let u = -&K * x;
             model.dynamics().f(t, x, Some(&u))
         };
@@ -354,14 +624,88 @@ mod tests {
         let tf = 10.0;
         let n = 1000.0;
         let step = (tf - t0) / n;
-        let rtol = 1E-6;
-        let (_t, y) = RK45(f, t0, y0, tf, step, rtol);
+        let opts = SolverOptions{ rtol: 1E-6, ..SolverOptions::default() };
+        let y = RK45(f, t0, y0, tf, step, &opts).unwrap().y;
 
         for ele in y.iter() {
             println!("{:?}", ele.data);
         }
     }
 
+    #[test]
+    fn test_DOPRI5() {
+
+        use crate::dynamics::models::closed_form_solution::ClohessyWiltshireSolution;
+
+        // Clohessy-Wiltshire equations of motion
+        let n = 0.00113;
+        let f = |_t: Float, x: &DVector<Float>| {
+            DVector::from_vec(vec![
+                x[3],
+                x[4],
+                x[5],
+                3.*n*n*x[0] + 2.*n*x[4],
+                -2.*n*x[3],
+                -n*n*x[2]
+            ])
+        };
+
+        let y0 = DVector::from_vec(vec![0., 0., 0., 0.1, 0., 0.]);
+
+        let t0 = 0.0;
+        let tf = 2000.0;
+        // deliberately large first step to exercise step rejection
+        let step = tf;
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-6, ..SolverOptions::default() };
+        let result = DOPRI5(f, t0, y0.clone(), tf, step, &opts).unwrap();
+
+        let exact = ClohessyWiltshireSolution(tf, &y0);
+        let error = (&result.y[result.y.len()-1] - &exact).amax();
+
+        assert_eq!(result.t[result.t.len()-1], tf);
+        assert!(error < 1E-2);
+        assert!(result.n_rejected > 0);
+
+    }
+
+    #[test]
+    fn test_RK4_convergence() {
+
+        use crate::dynamics::models::closed_form_solution::ClohessyWiltshireSolution;
+
+        // Clohessy-Wiltshire equations of motion
+        let n = 0.00113;
+        let f = |_t: Float, x: &DVector<Float>| {
+            DVector::from_vec(vec![
+                x[3],
+                x[4],
+                x[5],
+                3.*n*n*x[0] + 2.*n*x[4],
+                -2.*n*x[3],
+                -n*n*x[2]
+            ])
+        };
+
+        let y0 = DVector::from_vec(vec![0., 0., 0., 0.1, 0., 0.]);
+        let t0 = 0.0;
+        let tf = 6000.0;
+        let exact = ClohessyWiltshireSolution(tf, &y0);
+
+        let error = |step: Float| {
+            let y = RK4(f, t0, y0.clone(), tf, step, &[]).unwrap().y;
+            (&y[y.len()-1] - &exact).amax()
+        };
+
+        // halving the step reduces the global error by 2^4
+        let order = (error(250.0) / error(125.0)).log2();
+        assert!(order > 3.5);
 
+        // fixed time grid, with the final step clipped to tf
+        let t = RK4(f, t0, y0.clone(), tf, 700.0, &[]).unwrap().t;
+        assert_eq!(t.len(), 10);
+        assert_eq!(t[5], 3500.0);
+        assert_eq!(t[t.len()-1], tf);
+
+    }
 
 }
@@ -0,0 +1,242 @@
+
+use na::DVector;
+use crate::math::integrate::{IntegrateError, OdeResult, fixed_step};
+use crate::math::integrate::events::Event;
+use crate::float::Float;
+
+/// Partition of a state vector into generalized positions and their velocities
+///
+/// Symplectic integrators advance a second-order system q_ddot = a(t, q) in the first-order
+/// form x = [q, q_dot], where the accelerations are read from the velocity components of
+/// f(t, x). positions[i] and velocities[i] must index the same degree of freedom.
+///
+/// positions: state vector indices of the generalized positions \
+/// velocities: state vector indices of the corresponding velocities \
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecondOrderPartition {
+    pub positions: Vec<usize>,
+    pub velocities: Vec<usize>,
+}
+
+impl SecondOrderPartition {
+
+    pub fn new(positions: Vec<usize>, velocities: Vec<usize>) -> Self {
+
+        assert_eq!(positions.len(), velocities.len());
+
+        Self { positions, velocities }
+
+    }
+
+    /// State vector ordered as all positions followed by all velocities
+    /// eg. x = [position0, position1, velocity0, velocity1]
+    pub fn halves(dx: usize) -> Self {
+
+        let m = dx / 2;
+
+        Self::new((0..m).collect(), (m..2*m).collect())
+
+    }
+
+    /// State vector ordered as position and velocity pairs
+    /// eg. x = [attitude0, angular_velocity0, attitude1, angular_velocity1]
+    pub fn interleaved(dx: usize) -> Self {
+
+        let m = dx / 2;
+
+        Self::new((0..m).map(|i| 2*i).collect(), (0..m).map(|i| 2*i + 1).collect())
+
+    }
+
+    /// Returns true if the partition indexes every component of a state vector of size dx once
+    pub fn is_valid(&self, dx: usize) -> bool {
+
+        let mut seen = vec![false; dx];
+        for i in self.positions.iter().chain(self.velocities.iter()) {
+            if *i >= dx || seen[*i] {
+                return false;
+            }
+            seen[*i] = true;
+        }
+
+        self.positions.len() == self.velocities.len() && seen.iter().all(|covered| *covered)
+
+    }
+
+    /// Updates velocities with the accelerations of f(t, x) over dt
+    fn kick<F>(&self, f: &F, t: Float, x: &mut DVector<Float>, dt: Float)
+    where
+        F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+    {
+
+        let xdot = f(t, x);
+        for v in self.velocities.iter() {
+            x[*v] += dt * xdot[*v];
+        }
+
+    }
+
+    /// Updates positions with the current velocities over dt
+    fn drift(&self, x: &mut DVector<Float>, dt: Float) {
+
+        for (q, v) in self.positions.iter().zip(self.velocities.iter()) {
+            x[*q] += dt * x[*v];
+        }
+
+    }
+
+}
+
+// Velocity Verlet (kick-drift-kick)
+// Second-order, symplectic and time-reversible for separable systems q_ddot = a(t, q)
+pub fn VelocityVerlet<F>(
+    f: F,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    partition: &SecondOrderPartition,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let method = |t: Float, x: &mut DVector<Float>, h: Float| {
+        partition.kick(&f, t, x, h / 2.0);
+        partition.drift(x, h);
+        partition.kick(&f, t + h, x, h / 2.0);
+    };
+
+    fixed_step(&f, method, t0, y0, tf, step, events)
+
+}
+
+// Leapfrog (drift-kick-drift)
+// Second-order, symplectic and time-reversible for separable systems q_ddot = a(t, q)
+// Requires a single evaluation of f per step
+pub fn Leapfrog<F>(
+    f: F,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    partition: &SecondOrderPartition,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let method = |t: Float, x: &mut DVector<Float>, h: Float| {
+        partition.drift(x, h / 2.0);
+        partition.kick(&f, t + h / 2.0, x, h);
+        partition.drift(x, h / 2.0);
+    };
+
+    fixed_step(&f, method, t0, y0, tf, step, events)
+
+}
+
+// Fourth-order Yoshida integrator
+// Symmetric composition of three velocity Verlet steps
+// "Construction of higher order symplectic integrators" - H. Yoshida
+pub fn Yoshida4<F>(
+    f: F,
+    t0: Float,
+    y0: DVector<Float>,
+    tf: Float,
+    step: Float,
+    partition: &SecondOrderPartition,
+    events: &[Event]
+) -> Result<OdeResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+{
+
+    let cbrt2 = (2 as Float).cbrt();
+    let w1 = 1.0 / (2.0 - cbrt2);
+    let w0 = -cbrt2 / (2.0 - cbrt2);
+
+    let method = |t: Float, x: &mut DVector<Float>, h: Float| {
+        let mut tk = t;
+        for w in [w1, w0, w1].iter() {
+            let hw = w * h;
+            partition.kick(&f, tk, x, hw / 2.0);
+            partition.drift(x, hw);
+            partition.kick(&f, tk + hw, x, hw / 2.0);
+            tk += hw;
+        }
+    };
+
+    fixed_step(&f, method, t0, y0, tf, step, events)
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nonlinear pendulum, x = [theta, theta_dot]
+    fn pendulum(_t: Float, x: &DVector<Float>) -> DVector<Float> {
+        DVector::from_vec(vec![x[1], -x[0].sin()])
+    }
+
+    fn energy(x: &DVector<Float>) -> Float {
+        0.5 * x[1] * x[1] - x[0].cos()
+    }
+
+    #[test]
+    fn test_symplectic_energy_bounded() {
+
+        let partition = SecondOrderPartition::halves(2);
+        let y0 = DVector::from_vec(vec![1.0, 0.0]);
+        let e0 = energy(&y0);
+
+        // thousands of periods of the small-amplitude period 2pi
+        let t0 = 0.0;
+        let tf = 2000.0 * 2.0 * crate::float::consts::PI;
+        let step = 0.1;
+
+        let solutions = vec![
+            VelocityVerlet(pendulum, t0, y0.clone(), tf, step, &partition, &[]).unwrap(),
+            Leapfrog(pendulum, t0, y0.clone(), tf, step, &partition, &[]).unwrap(),
+            Yoshida4(pendulum, t0, y0.clone(), tf, step, &partition, &[]).unwrap(),
+        ];
+
+        for solution in solutions.iter() {
+            let (t, y) = (&solution.t, &solution.y);
+            assert_eq!(t[t.len()-1], tf);
+
+            let max_error = y.iter().map(|x| (energy(x) - e0).abs()).fold(0.0, Float::max);
+            assert!(max_error < 1E-2 * e0.abs());
+        }
+
+        // fourth-order method conserves energy more closely
+        let verlet_error = solutions[0].y.iter().map(|x| (energy(x) - e0).abs()).fold(0.0, Float::max);
+        let yoshida_error = solutions[2].y.iter().map(|x| (energy(x) - e0).abs()).fold(0.0, Float::max);
+        assert!(yoshida_error < verlet_error);
+
+    }
+
+    #[test]
+    fn test_SecondOrderPartition() {
+
+        let partition = SecondOrderPartition::interleaved(4);
+        assert_eq!(partition.positions, vec![0, 2]);
+        assert_eq!(partition.velocities, vec![1, 3]);
+        assert!(partition.is_valid(4));
+        assert!(!partition.is_valid(3));
+
+        let partition = SecondOrderPartition::new(vec![0, 1], vec![1, 2]);
+        assert!(!partition.is_valid(3));
+
+        // every component must be updated as a position or a velocity
+        assert!(SecondOrderPartition::halves(4).is_valid(4));
+        assert!(!SecondOrderPartition::halves(3).is_valid(3));
+        assert!(!SecondOrderPartition::new(vec![0], vec![1]).is_valid(4));
+
+    }
+
+}
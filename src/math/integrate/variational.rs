@@ -0,0 +1,125 @@
+
+use na::{DMatrix, DVector};
+use crate::math::integrate::{solve_ivp, IntegrateError, IntegratorType, OdeResult, SolverOptions};
+use crate::float::Float;
+
+/// Solution of an initial value problem together with its state transition matrix
+///
+/// The state transition matrix Φ(t, t0) = dx(t)/dx(t0) maps perturbations of the initial
+/// state to perturbations of the state at time t.
+///
+/// ode: solution of the state equations, with y and y_events holding the state only \
+/// phi: state transition matrix Φ(t, t0) at each time in ode.t \
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationalResult {
+    pub ode: OdeResult,
+    pub phi: Vec<DMatrix<Float>>,
+}
+
+/// Integrates a system of ordinary differential equations together with its variational equations
+///
+/// The augmented system z = [x, vec(Φ)] is solved with the chosen IVP solver, where
+///
+/// \dot{x}(t) = f(t, x(t)) \
+/// \dot{Φ}(t, t0) = J(t, x(t)) Φ(t, t0), Φ(t0, t0) = I \
+///
+/// and J = df/dx is the Jacobian of the system. Any dense output interpolates the augmented state
+/// and event functions receive it, with the state in its first components. Symplectic solvers are
+/// not supported as the variational equations are not of second-order form.
+pub fn solve_ivp_variational<F, J>(
+    fun: F,
+    jacobian: J,
+    t_span: (Float, Float),
+    y0: DVector<Float>,
+    method: IntegratorType,
+    options: SolverOptions
+) -> Result<VariationalResult, IntegrateError>
+where
+    F: Fn(Float, &DVector<Float>) -> DVector<Float>,
+    J: Fn(Float, &DVector<Float>) -> DMatrix<Float>,
+{
+
+    match method {
+        IntegratorType::VelocityVerlet | IntegratorType::Leapfrog | IntegratorType::Yoshida4 => {
+            return Err(IntegrateError::ArgError("method".to_string()));
+        },
+        _ => (),
+    }
+
+    let n = y0.len();
+
+    // Augmented initial condition [x0, vec(I)], with Φ stored column-major
+    let mut z0 = DVector::<Float>::zeros(n + n*n);
+    z0.rows_mut(0, n).copy_from(&y0);
+    for i in 0..n {
+        z0[n + i*n + i] = 1.0;
+    }
+
+    let augmented = |t: Float, z: &DVector<Float>| {
+        let x = z.rows(0, n).into_owned();
+        let phi = DMatrix::from_column_slice(n, n, z.rows(n, n*n).as_slice());
+
+        let mut dz = DVector::<Float>::zeros(n + n*n);
+        dz.rows_mut(0, n).copy_from(&fun(t, &x));
+        dz.rows_mut(n, n*n).copy_from_slice((jacobian(t, &x) * phi).as_slice());
+        dz
+    };
+
+    // A Jacobian supplied for the state equations does not apply to the augmented system
+    let options = SolverOptions{ jacobian: None, ..options };
+    let mut ode = solve_ivp(augmented, t_span, z0, method, options)?;
+
+    let phi = ode.y.iter().map(|z| DMatrix::from_column_slice(n, n, z.rows(n, n*n).as_slice())).collect();
+    ode.y = ode.y.iter().map(|z| z.rows(0, n).into_owned()).collect();
+    for y_events in ode.y_events.iter_mut() {
+        *y_events = y_events.iter().map(|z| z.rows(0, n).into_owned()).collect();
+    }
+
+    Ok(VariationalResult { ode, phi })
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_ivp_variational() {
+
+        // Nonlinear pendulum, x = [theta, theta_dot]
+        let f = |_t: Float, x: &DVector<Float>| DVector::from_vec(vec![x[1], -x[0].sin()]);
+        let jacobian = |_t: Float, x: &DVector<Float>| DMatrix::from_row_slice(2, 2, &[0., 1., -x[0].cos(), 0.]);
+
+        let t_span = (0.0, 2.0);
+        let x0 = DVector::from_vec(vec![0.5, 0.]);
+        let opts = SolverOptions{ rtol: 1E-6, atol: 1E-8, ..SolverOptions::default() };
+        let result = solve_ivp_variational(f, jacobian, t_span, x0.clone(), IntegratorType::DOPRI5, opts).unwrap();
+
+        let phi = &result.phi[result.phi.len()-1];
+        assert_eq!(result.phi[0], DMatrix::<Float>::identity(2, 2));
+        assert_eq!(result.ode.y[0], x0);
+
+        // Compare against central differences of the flow
+        let flow = |x0: DVector<Float>| {
+            let opts = SolverOptions{ rtol: 1E-6, atol: 1E-8, ..SolverOptions::default() };
+            let y = solve_ivp(f, t_span, x0, IntegratorType::DOPRI5, opts).unwrap().y;
+            y[y.len()-1].clone()
+        };
+
+        let dx = 1E-2;
+        for j in 0..2 {
+            let mut e = DVector::<Float>::zeros(2);
+            e[j] = dx;
+            let column = (flow(&x0 + &e) - flow(&x0 - &e)) / (2.0 * dx);
+            for i in 0..2 {
+                assert_relative_eq!(phi[(i, j)], column[i], epsilon = 1E-3);
+            }
+        }
+
+        // The phase flow of a Hamiltonian system preserves area
+        assert_relative_eq!(phi.determinant(), 1.0, epsilon = 1E-3);
+
+    }
+
+}
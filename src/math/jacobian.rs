@@ -0,0 +1,127 @@
+
+use na::{Complex, DMatrix, DVector};
+use crate::float::Float;
+
+/// Approximates the Jacobian of f with respect to x using central finite differences
+///
+/// J[(i, j)] = (f(x + h_j e_j)[i] - f(x - h_j e_j)[i]) / 2h_j
+///
+/// The perturbation of each component is scaled by its magnitude to balance truncation
+/// and round-off error.
+pub fn finite_difference<F>(f: F, x: &DVector<Float>) -> DMatrix<Float>
+where
+    F: Fn(&DVector<Float>) -> DVector<Float>,
+{
+
+    // optimal central difference step relative to machine precision: eps^(1/3)
+    let eps = Float::EPSILON.cbrt();
+
+    let mut columns: Vec<DVector<Float>> = Vec::with_capacity(x.len());
+
+    for j in 0..x.len() {
+
+        let h = eps * x[j].abs().max(1.0);
+
+        let mut x_plus = x.clone();
+        let mut x_minus = x.clone();
+        x_plus[j] += h;
+        x_minus[j] -= h;
+
+        columns.push((f(&x_plus) - f(&x_minus)) / (x_plus[j] - x_minus[j]));
+
+    }
+
+    // Without columns, the number of rows is the dimension of f
+    match columns.is_empty() {
+        true => DMatrix::<Float>::zeros(f(x).len(), 0),
+        false => DMatrix::from_columns(&columns),
+    }
+
+}
+
+/// Approximates the Jacobian of f with respect to x using complex-step differentiation
+///
+/// J[(i, j)] = Im(f(x + ih e_j)[i]) / h
+///
+/// There is no subtractive cancellation, so the derivative is accurate to machine precision with
+/// a vanishingly small step. f must be analytic and evaluated in complex arithmetic. Returns None
+/// if f cannot be evaluated in complex arithmetic, signalled by f returning None.
+pub fn complex_step<F>(f: F, x: &DVector<Float>) -> Option<DMatrix<Float>>
+where
+    F: Fn(&DVector<Complex<Float>>) -> Option<DVector<Complex<Float>>>,
+{
+
+    let h = Float::EPSILON * Float::EPSILON;
+
+    let x_complex: DVector<Complex<Float>> = x.map(|x| Complex::new(x, 0.0));
+    let mut columns: Vec<DVector<Float>> = Vec::with_capacity(x.len());
+
+    for j in 0..x.len() {
+
+        let mut x_step = x_complex.clone();
+        x_step[j].im = h;
+
+        columns.push(f(&x_step)?.map(|y| y.im / h));
+
+    }
+
+    // Without columns, the number of rows is the dimension of f
+    match columns.is_empty() {
+        true => Some(DMatrix::<Float>::zeros(f(&x_complex)?.len(), 0)),
+        false => Some(DMatrix::from_columns(&columns)),
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::ComplexField;
+
+    #[test]
+    fn test_complex_step() {
+
+        let f = |x: &DVector<Complex<Float>>| Some(DVector::from_vec(vec![x[0]*x[1], x[0].sin(), x[1] * 3.0]));
+        let x = DVector::from_vec(vec![0.5, 2.0]);
+
+        let J = complex_step(f, &x).unwrap();
+
+        let J_true = DMatrix::from_row_slice(3, 2, &[2.0, 0.5, (0.5 as Float).cos(), 0.0, 0.0, 3.0]);
+
+        assert_relative_eq!(J, J_true, epsilon = 1E-6);
+
+        // Functions without a complex evaluation have no complex-step derivative
+        assert!(complex_step(|_x: &DVector<Complex<Float>>| None, &x).is_none());
+
+    }
+
+    #[test]
+    fn test_finite_difference() {
+
+        let f = |x: &DVector<Float>| DVector::from_vec(vec![x[0]*x[1], x[0].sin(), 3.0*x[1]]);
+        let x = DVector::from_vec(vec![0.5, 2.0]);
+
+        let J = finite_difference(f, &x);
+
+        let J_true = DMatrix::from_row_slice(3, 2, &[2.0, 0.5, (0.5 as Float).cos(), 0.0, 0.0, 3.0]);
+
+        assert_relative_eq!(J, J_true, epsilon = 1E-3);
+
+    }
+
+    #[test]
+    fn test_jacobian_empty() {
+
+        // Jacobians with respect to an empty input have a row for each component of f
+        let x = DVector::<Float>::zeros(0);
+
+        let J = finite_difference(|_x: &DVector<Float>| DVector::from_vec(vec![1.0, 2.0]), &x);
+        assert_eq!(J.shape(), (2, 0));
+
+        let J = complex_step(|_x: &DVector<Complex<Float>>| Some(DVector::from_vec(vec![Complex::new(1.0, 0.0); 3])), &x).unwrap();
+        assert_eq!(J.shape(), (3, 0));
+
+    }
+
+}
@@ -0,0 +1,231 @@
+use na::{Complex, DMatrix, DVector};
+use thiserror::Error;
+use crate::math::schur::{real_schur, RealSchur};
+use crate::float::Float;
+
+/// Reasons a Lyapunov equation solve can fail
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LyapunovError {
+
+    #[error("Lyapunov matrix dimensions are inconsistent")]
+    DimensionError,
+
+    #[error("Schur decomposition failed, LAPACK info = {0}")]
+    SchurError(i32),
+
+    #[error("Lyapunov equation is singular, the solution is not unique")]
+    SingularError,
+
+    #[error("system is not stable, the infinite-horizon Gramian does not exist")]
+    NotStableError,
+
+}
+
+/// Solve the continuous Lyapunov equation AX + XA^T + Q = 0
+///
+/// Unique when no two eigenvalues of A sum to zero. For a stable A and a positive semi-definite Q,
+/// the solution is the positive semi-definite integral of e^{At} Q e^{A^Tt} over [0, ∞)
+/// Reference: "Solution of the Matrix Equation AX + XB = C" - R.H. Bartels, G.W. Stewart
+pub fn solve_continuous_lyapunov(A: &DMatrix<Float>, Q: &DMatrix<Float>) -> Result<DMatrix<Float>, LyapunovError> {
+
+    check_dimensions(A, Q)?;
+    let schur = real_schur(A).map_err(LyapunovError::SchurError)?;
+
+    bartels_stewart(A, &schur, Q, continuous_separation, false)
+
+}
+
+/// Solve the discrete Lyapunov equation AXA^T - X + Q = 0
+///
+/// Unique when no product of two eigenvalues of A is one. For a stable A and a positive semi-definite
+/// Q, the solution is the positive semi-definite sum of A^k Q (A^T)^k over k >= 0
+pub fn solve_discrete_lyapunov(A: &DMatrix<Float>, Q: &DMatrix<Float>) -> Result<DMatrix<Float>, LyapunovError> {
+
+    check_dimensions(A, Q)?;
+    let schur = real_schur(A).map_err(LyapunovError::SchurError)?;
+
+    bartels_stewart(A, &schur, Q, |a, b| { let s = a * b - 1.0; s.re.hypot(s.im) }, true)
+
+}
+
+/// Infinite-horizon controllability Gramian W of the stable system (A, B), AW + WA^T + BB^T = 0
+pub fn controllability_gramian(A: &DMatrix<Float>, B: &DMatrix<Float>) -> Result<DMatrix<Float>, LyapunovError> {
+
+    if B.shape().0 != A.shape().0 {
+        return Err(LyapunovError::DimensionError);
+    }
+    let Q = B * B.transpose();
+    check_dimensions(A, &Q)?;
+
+    // The stability check and the solve share one Schur decomposition
+    let schur = real_schur(A).map_err(LyapunovError::SchurError)?;
+    if schur.2.iter().any(|lambda| lambda.re >= 0.0) {
+        return Err(LyapunovError::NotStableError);
+    }
+
+    bartels_stewart(A, &schur, &Q, continuous_separation, false)
+
+}
+
+/// Infinite-horizon observability Gramian W of the stable system (A, C), A^TW + WA + C^TC = 0
+pub fn observability_gramian(A: &DMatrix<Float>, C: &DMatrix<Float>) -> Result<DMatrix<Float>, LyapunovError> {
+
+    controllability_gramian(&A.transpose(), &C.transpose())
+
+}
+
+// Validates A and Q are square matrices of the same size
+fn check_dimensions(A: &DMatrix<Float>, Q: &DMatrix<Float>) -> Result<(), LyapunovError> {
+
+    let n = A.shape().0;
+    if A.shape() != (n, n) || Q.shape() != (n, n) {
+        return Err(LyapunovError::DimensionError);
+    }
+
+    Ok(())
+
+}
+
+// Distance of a pair of eigenvalues from making the continuous equation singular, a + b = 0
+fn continuous_separation(a: Complex<Float>, b: Complex<Float>) -> Float {
+
+    let s = a + b;
+    s.re.hypot(s.im)
+
+}
+
+// Solves the quasi-triangular equation in Y = U^TXU block by block, given the real Schur form
+// A = UTU^T. `separation` measures the distance of each pair of eigenvalues of A from making the
+// equation singular
+fn bartels_stewart<F>(A: &DMatrix<Float>, schur: &RealSchur, Q: &DMatrix<Float>, separation: F, discrete: bool) -> Result<DMatrix<Float>, LyapunovError>
+where
+    F: Fn(Complex<Float>, Complex<Float>) -> Float
+{
+
+    let n = A.shape().0;
+    let (U, T, eigenvalues) = schur;
+
+    let tolerance = Float::EPSILON.sqrt() * A.amax().max(1.0);
+    for a in eigenvalues.iter() {
+        if eigenvalues.iter().any(|b| separation(*a, *b) < tolerance) {
+            return Err(LyapunovError::SingularError);
+        }
+    }
+
+    let C = -(U.transpose() * Q * U);
+    let blocks = schur_blocks(T);
+    let mut Y = DMatrix::<Float>::zeros(n, n);
+
+    // Blocks of Y are found from the bottom right, so the blocks they depend on are already known
+    for &(j, q) in blocks.iter().rev() {
+        let Tjj = T.slice((j, j), (q, q)).into_owned();
+
+        for &(i, p) in blocks.iter().rev() {
+            let Tii = T.slice((i, i), (p, p)).into_owned();
+            let mut rhs = C.slice((i, j), (p, q)).into_owned();
+
+            let M = if discrete {
+                // T_ii Y_ij T_jj^T - Y_ij = C_ij - sum of T_il Y_lk T_jk^T over the other l >= i, k >= j
+                for &(l, r) in blocks.iter().filter(|(l, _)| *l >= i) {
+                    for &(k, s) in blocks.iter().filter(|(k, _)| *k >= j) {
+                        if l != i || k != j {
+                            rhs -= T.slice((i, l), (p, r)) * Y.slice((l, k), (r, s)) * T.slice((j, k), (q, s)).transpose();
+                        }
+                    }
+                }
+                Tjj.kronecker(&Tii) - DMatrix::<Float>::identity(p * q, p * q)
+            } else {
+                // T_ii Y_ij + Y_ij T_jj^T = C_ij - sum of T_il Y_lj over l > i - sum of Y_ik T_jk^T over k > j
+                for &(l, r) in blocks.iter().filter(|(l, _)| *l > i) {
+                    rhs -= T.slice((i, l), (p, r)) * Y.slice((l, j), (r, q));
+                }
+                for &(k, s) in blocks.iter().filter(|(k, _)| *k > j) {
+                    rhs -= Y.slice((i, k), (p, s)) * T.slice((j, k), (q, s)).transpose();
+                }
+                DMatrix::<Float>::identity(q, q).kronecker(&Tii) + Tjj.kronecker(&DMatrix::<Float>::identity(p, p))
+            };
+
+            // Solve the vectorized block equation, of size at most 4
+            let y = match M.lu().solve(&DVector::from_column_slice(rhs.as_slice())) {
+                Some(y) => y,
+                None => return Err(LyapunovError::SingularError),
+            };
+            Y.slice_mut((i, j), (p, q)).copy_from_slice(y.as_slice());
+        }
+    }
+
+    Ok(U * Y * U.transpose())
+
+}
+
+
+// Start and size of the 1x1 and 2x2 diagonal blocks of a quasi-triangular matrix
+fn schur_blocks(T: &DMatrix<Float>) -> Vec<(usize, usize)> {
+
+    let n = T.shape().0;
+    let tolerance = Float::EPSILON * T.amax().max(1.0);
+
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < n {
+        let size = if i + 1 < n && T[(i + 1, i)].abs() > tolerance { 2 } else { 1 };
+        blocks.push((i, size));
+        i += size;
+    }
+
+    blocks
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_continuous_lyapunov() {
+
+        // Complex eigenvalues -1 ± 2i and a real eigenvalue -3
+        let A = DMatrix::from_row_slice(3, 3, &[-1., 2., 0.5, -2., -1., 1., 0., 0., -3.]);
+        let Q = DMatrix::from_row_slice(3, 3, &[2., 1., 0., 1., 2., 0., 0., 0., 1.]);
+
+        let X = solve_continuous_lyapunov(&A, &Q).unwrap();
+
+        assert_relative_eq!(&A * &X + &X * A.transpose() + &Q, DMatrix::<Float>::zeros(3, 3), epsilon = 1E-4);
+        assert_relative_eq!(X.clone(), X.transpose(), epsilon = 1E-5);
+
+        // Scalar solution x = q / 2a
+        let X = solve_continuous_lyapunov(&DMatrix::from_vec(1, 1, vec![-2.]), &DMatrix::from_vec(1, 1, vec![1.])).unwrap();
+        assert_relative_eq!(X[(0, 0)], 0.25);
+
+    }
+
+    #[test]
+    fn test_solve_discrete_lyapunov() {
+
+        let A = DMatrix::from_row_slice(3, 3, &[0.5, 0.4, 0.1, -0.4, 0.5, 0., 0., 0.2, -0.3]);
+        let Q = DMatrix::<Float>::identity(3, 3);
+
+        let X = solve_discrete_lyapunov(&A, &Q).unwrap();
+
+        assert_relative_eq!(&A * &X * A.transpose() - &X + &Q, DMatrix::<Float>::zeros(3, 3), epsilon = 1E-4);
+
+        // Scalar solution x = q / (1 - a^2)
+        let X = solve_discrete_lyapunov(&DMatrix::from_vec(1, 1, vec![0.5]), &DMatrix::from_vec(1, 1, vec![3.])).unwrap();
+        assert_relative_eq!(X[(0, 0)], 4.0, epsilon = 1E-5);
+
+    }
+
+    #[test]
+    fn test_solve_lyapunov_errors() {
+
+        // Double integrator, both eigenvalues at the origin
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+
+        assert_eq!(solve_continuous_lyapunov(&A, &Q).unwrap_err(), LyapunovError::SingularError);
+        assert_eq!(solve_discrete_lyapunov(&DMatrix::<Float>::identity(2, 2), &Q).unwrap_err(), LyapunovError::SingularError);
+        assert_eq!(solve_continuous_lyapunov(&A, &DMatrix::<Float>::identity(3, 3)).unwrap_err(), LyapunovError::DimensionError);
+        assert_eq!(controllability_gramian(&A, &DMatrix::from_row_slice(2, 1, &[0., 1.])).unwrap_err(), LyapunovError::NotStableError);
+
+    }
+}
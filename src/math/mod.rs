@@ -1,3 +1,7 @@
 pub mod integrate;
 pub mod riccati;
+pub mod lyapunov;
 pub mod frames;
+pub mod jacobian;
+pub mod qp;
+pub(crate) mod schur;
@@ -0,0 +1,263 @@
+use na::{Cholesky, DMatrix, DVector, Dynamic};
+use thiserror::Error;
+use crate::float::Float;
+
+/// Reasons a quadratic program solve can fail
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum QPError {
+
+    #[error("QP matrix dimensions are inconsistent")]
+    DimensionError,
+
+    #[error("QP cost is not convex")]
+    NonConvexError,
+
+    #[error("QP bounds are inconsistent, lower bound exceeds upper bound")]
+    BoundsError,
+
+    #[error("QP solver did not converge in {iterations} iterations, residuals {primal}, {dual}")]
+    ConvergenceError { iterations: usize, primal: Float, dual: Float },
+
+}
+
+/// Defines options for the ADMM quadratic program solver
+///
+/// rho: initial constraint penalty parameter \
+/// adaptive_rho: rebalance rho from the ratio of the primal and dual residuals \
+/// sigma: proximal regularization of the cost \
+/// alpha: over-relaxation parameter in (0, 2) \
+/// eps_abs: absolute tolerance of the primal and dual residuals \
+/// eps_rel: relative tolerance of the primal and dual residuals \
+/// max_iterations: largest number of ADMM iterations \
+#[derive(Debug, Clone, PartialEq)]
+pub struct QPOptions {
+    pub rho: Float,
+    pub adaptive_rho: bool,
+    pub sigma: Float,
+    pub alpha: Float,
+    pub eps_abs: Float,
+    pub eps_rel: Float,
+    pub max_iterations: usize,
+}
+
+impl Default for QPOptions {
+    fn default() -> Self {
+        Self {
+            rho: 0.1,
+            adaptive_rho: true,
+            sigma: 1E-6,
+            alpha: 1.6,
+            eps_abs: 1E-4,
+            eps_rel: 1E-4,
+            max_iterations: 4000,
+        }
+    }
+}
+
+/// Solution of a quadratic program
+///
+/// x: primal solution \
+/// y: Lagrange multipliers of the constraints \
+/// iterations: number of ADMM iterations taken \
+#[derive(Debug, Clone, PartialEq)]
+pub struct QPSolution {
+    pub x: DVector<Float>,
+    pub y: DVector<Float>,
+    pub iterations: usize,
+}
+
+/// Dense convex quadratic program with a fixed cost Hessian and constraint matrix
+///
+/// minimize 1/2 x^THx + f^Tx subject to l <= Gx <= u
+///
+/// Solved with the alternating direction method of multipliers. The KKT matrix is factored once
+/// and only refactored when rho is rebalanced, with the rebalanced rho kept between solves, so
+/// repeated solves with new f, l and u (e.g. receding horizon control) are cheap. Equality
+/// constraints have l = u, and unbounded constraints have infinite bounds.
+///
+/// Reference: "OSQP: An Operator Splitting Solver for Quadratic Programs" - B. Stellato et al.
+#[derive(Debug, Clone)]
+pub struct QuadraticProgram {
+    H: DMatrix<Float>,
+    G: DMatrix<Float>,
+    options: QPOptions,
+    rho: Float,
+    factor: Cholesky<Float, Dynamic>,
+}
+
+impl QuadraticProgram {
+
+    pub fn new(H: DMatrix<Float>, G: DMatrix<Float>, options: QPOptions) -> Result<Self, QPError> {
+
+        let n = H.shape().0;
+        if H.shape().1 != n || G.shape().1 != n {
+            return Err(QPError::DimensionError);
+        }
+
+        let factor = match factorize(&H, &G, options.sigma, options.rho) {
+            Some(factor) => factor,
+            None => return Err(QPError::NonConvexError),
+        };
+
+        let rho = options.rho;
+
+        Ok(Self { H, G, options, rho, factor })
+
+    }
+
+    /// Number of decision variables
+    pub fn variables(&self) -> usize { self.H.shape().0 }
+
+    /// Number of constraints
+    pub fn constraints(&self) -> usize { self.G.shape().0 }
+
+    /// Solves the program for the linear cost f and bounds l, u
+    /// An optional warm start (x, y) from a previous solution speeds up convergence
+    pub fn solve(
+        &mut self,
+        f: &DVector<Float>,
+        l: &DVector<Float>,
+        u: &DVector<Float>,
+        warm_start: Option<(&DVector<Float>, &DVector<Float>)>
+    ) -> Result<QPSolution, QPError> {
+
+        let n = self.variables();
+        let m = self.constraints();
+        if f.len() != n || l.len() != m || u.len() != m {
+            return Err(QPError::DimensionError);
+        }
+        if l.iter().zip(u.iter()).any(|(l, u)| l > u) {
+            return Err(QPError::BoundsError);
+        }
+
+        let QPOptions { rho: _, adaptive_rho, sigma, alpha, eps_abs, eps_rel, max_iterations } = self.options;
+        let GT = self.G.transpose();
+
+        let (mut x, mut y) = match warm_start {
+            Some((x, y)) if x.len() == n && y.len() == m => (x.clone(), y.clone()),
+            _ => (DVector::<Float>::zeros(n), DVector::<Float>::zeros(m)),
+        };
+        let mut z = (&self.G * &x).zip_zip_map(l, u, |z, l, u| z.clamp(l, u));
+
+        let mut primal = Float::INFINITY;
+        let mut dual = Float::INFINITY;
+
+        for iteration in 1..=max_iterations {
+
+            let rho = self.rho;
+            let x_tilde = self.factor.solve(&(&x * sigma - f + &GT * (&z * rho - &y)));
+            let z_tilde = &self.G * &x_tilde;
+
+            x = &x_tilde * alpha + &x * (1.0 - alpha);
+            let z_relaxed = &z_tilde * alpha + &z * (1.0 - alpha);
+            let z_next = (&z_relaxed + &y / rho).zip_zip_map(l, u, |z, l, u| z.clamp(l, u));
+            y += (&z_relaxed - &z_next) * rho;
+            z = z_next;
+
+            // Residuals of the KKT conditions
+            let Gx = &self.G * &x;
+            let Hx = &self.H * &x;
+            let GTy = &GT * &y;
+            primal = (&Gx - &z).amax();
+            dual = (&Hx + f + &GTy).amax();
+
+            let primal_scale = Gx.amax().max(z.amax());
+            let dual_scale = Hx.amax().max(GTy.amax()).max(f.amax());
+
+            if primal <= eps_abs + eps_rel * primal_scale && dual <= eps_abs + eps_rel * dual_scale {
+                return Ok(QPSolution { x, y, iterations: iteration });
+            }
+
+            // Rebalance the normalized residuals, skipping small changes to avoid refactoring
+            if adaptive_rho && iteration % ADAPTIVE_RHO_INTERVAL == 0 {
+                let ratio = ((primal / (primal_scale + Float::EPSILON)) / (dual / (dual_scale + Float::EPSILON) + Float::EPSILON)).sqrt();
+                let rho_next = (rho * ratio).clamp(1E-6, 1E6);
+                if rho_next > 5.0 * rho || rho_next < 0.2 * rho {
+                    self.factor = match factorize(&self.H, &self.G, sigma, rho_next) {
+                        Some(factor) => factor,
+                        None => return Err(QPError::NonConvexError),
+                    };
+                    self.rho = rho_next;
+                }
+            }
+
+        }
+
+        Err(QPError::ConvergenceError { iterations: max_iterations, primal, dual })
+
+    }
+
+}
+
+// Iterations between updates of an adaptive rho
+const ADAPTIVE_RHO_INTERVAL: usize = 25;
+
+// Factors the KKT matrix H + sigma I + rho G^TG, positive definite for a positive semi-definite H
+fn factorize(H: &DMatrix<Float>, G: &DMatrix<Float>, sigma: Float, rho: Float) -> Option<Cholesky<Float, Dynamic>> {
+
+    let n = H.shape().0;
+
+    Cholesky::new(H + DMatrix::<Float>::identity(n, n) * sigma + G.transpose() * G * rho)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_QuadraticProgram_box() {
+
+        // minimize (x0 - 1)^2 + (x1 - 2)^2 subject to 0 <= x <= 1.5
+        let H = DMatrix::<Float>::identity(2, 2) * 2.0;
+        let G = DMatrix::<Float>::identity(2, 2);
+        let mut qp = QuadraticProgram::new(H, G, QPOptions::default()).unwrap();
+
+        let f = DVector::from_vec(vec![-2.0, -4.0]);
+        let l = DVector::from_vec(vec![0.0, 0.0]);
+        let u = DVector::from_vec(vec![1.5, 1.5]);
+        let solution = qp.solve(&f, &l, &u, None).unwrap();
+
+        assert_relative_eq!(solution.x, DVector::from_vec(vec![1.0, 1.5]), epsilon = 1E-3);
+        // Only the upper bound on x1 is active, with multiplier -(H x + f) = 1
+        assert_relative_eq!(solution.y, DVector::from_vec(vec![0.0, 1.0]), epsilon = 1E-2);
+
+        // Warm starting from the solution converges immediately
+        let warm = qp.solve(&f, &l, &u, Some((&solution.x, &solution.y))).unwrap();
+        assert!(warm.iterations <= 2);
+
+    }
+
+    #[test]
+    fn test_QuadraticProgram_equality() {
+
+        // minimize x0^2 + x1^2 subject to x0 + x1 = 1 and unbounded x0 - x1
+        let H = DMatrix::<Float>::identity(2, 2) * 2.0;
+        let G = DMatrix::from_row_slice(2, 2, &[1., 1., 1., -1.]);
+        let mut qp = QuadraticProgram::new(H, G, QPOptions::default()).unwrap();
+
+        let f = DVector::<Float>::zeros(2);
+        let l = DVector::from_vec(vec![1.0, -Float::INFINITY]);
+        let u = DVector::from_vec(vec![1.0, Float::INFINITY]);
+        let solution = qp.solve(&f, &l, &u, None).unwrap();
+
+        assert_relative_eq!(solution.x, DVector::from_vec(vec![0.5, 0.5]), epsilon = 1E-3);
+
+    }
+
+    #[test]
+    fn test_QuadraticProgram_errors() {
+
+        let G = DMatrix::<Float>::identity(2, 2);
+
+        let H = DMatrix::from_row_slice(2, 2, &[1., 0., 0., -1.]);
+        assert_eq!(QuadraticProgram::new(H, G.clone(), QPOptions::default()).unwrap_err(), QPError::NonConvexError);
+
+        let mut qp = QuadraticProgram::new(DMatrix::<Float>::identity(2, 2), G, QPOptions::default()).unwrap();
+        let f = DVector::<Float>::zeros(2);
+        let bound = DVector::<Float>::zeros(2);
+        assert_eq!(qp.solve(&f, &DVector::from_vec(vec![1.0, 0.0]), &bound, None).unwrap_err(), QPError::BoundsError);
+        assert_eq!(qp.solve(&DVector::zeros(3), &bound, &bound, None).unwrap_err(), QPError::DimensionError);
+
+    }
+}
@@ -1,10 +1,19 @@
 use std::fmt;
-use lapack::{sgees, Select2F32};
-use na::DMatrix;
+// Real Schur decomposition at the precision of Float
+#[cfg(not(feature = "f64"))]
+use lapack::{sgees as gees, Select2F32 as Select2};
+#[cfg(feature = "f64")]
+use lapack::{dgees as gees, Select2F64 as Select2};
+// Generalized real Schur decomposition at the precision of Float
+#[cfg(not(feature = "f64"))]
+use lapack::{sgges as gges, Select3F32 as Select3};
+#[cfg(feature = "f64")]
+use lapack::{dgges as gges, Select3F64 as Select3};
+use na::{Complex, DMatrix};
 use thiserror::Error;
+use crate::math::schur::eigenvalues;
 use crate::util::matrix_util::{block, hcombine, vcombine, MatrixCompareError};
-
-// TODO: update error handling for riccati
+use crate::float::Float;
 
 #[derive(Debug, Clone)]
 pub struct LinAlgError;
@@ -15,22 +24,63 @@ impl fmt::Display for LinAlgError {
     }
 }
 
+/// Reasons a Riccati equation solve can fail
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RiccatiError {
+
+    #[error("Riccati matrix dimensions are inconsistent")]
+    DimensionError,
+
+    #[error("control weight R is singular")]
+    SingularWeightError,
+
+    #[error("(A, B) is not stabilizable")]
+    NotStabilizableError,
+
+    #[error("(A, Q) is not detectable")]
+    NotDetectableError,
+
+    #[error("Schur decomposition failed, LAPACK info = {0}")]
+    SchurError(i32),
+
+    #[error("stable invariant subspace has dimension {found}, expected {expected}")]
+    StableSubspaceError { expected: usize, found: usize },
+
+    #[error("stable invariant subspace basis is singular")]
+    SingularSubspaceError,
+
+    #[error("Riccati solution is not finite")]
+    NonFiniteSolutionError,
+
+    #[error("iterative Riccati solver did not converge in {iterations} iterations, last update {difference}")]
+    ConvergenceError { iterations: u32, difference: Float },
+
+}
+
+impl From<MatrixCompareError> for RiccatiError {
+    fn from(_: MatrixCompareError) -> Self {
+        RiccatiError::DimensionError
+    }
+}
+
 /// Solve algebraic riccati equation using Hamiltonian eigenvalue decomposition
 /// Reference: "A Schur Method for Solving Algebraic Riccati Equations" - Alan J. Laub
 pub fn solve_continuous_riccati_eigen(
-    A: &DMatrix<f32>,
-    B: &DMatrix<f32>,
-    Q: &DMatrix<f32>,
-    R: &DMatrix<f32>,
-) -> Result<DMatrix<f32>, LinAlgError> {
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+) -> Result<DMatrix<Float>, RiccatiError> {
+
+    check_riccati_inputs(A, B, Q, R)?;
 
     let dx = A.shape().0;
 
-    let P: DMatrix<f32>;
+    let P: DMatrix<Float>;
 
     let Rinv = match R.clone().try_inverse() {
         Some(inverse) => inverse,
-        None => return Err(LinAlgError),
+        None => return Err(RiccatiError::SingularWeightError),
     };
 
     let BRinvBT = B * Rinv * B.transpose(); // matrix operations perform a move
@@ -38,22 +88,11 @@ pub fn solve_continuous_riccati_eigen(
 
     // multiplication of DMatrix returns DMatrix, not a borrow
     // row-major listing matrices to be placed into hamiltonian
-    // let matrices: [&DMatrix<f32>; 4] = [A, &BRinvBT, &(-Q), &AT];
+    // let matrices: [&DMatrix<Float>; 4] = [A, &BRinvBT, &(-Q), &AT];
 
-    let upper_block = match hcombine(A, &(-BRinvBT)) {
-        Ok(concatenated) => concatenated, // assign concatenated to upper block
-        Err(MatrixCompareError) => return Err(LinAlgError),
-    };
-
-    let lower_block = match hcombine(&(-Q), &(-AT)) {
-        Ok(concatenated) => concatenated, // assign concatenated to lower block
-        Err(MatrixCompareError) => return Err(LinAlgError),
-    };
-
-    let mut hamiltonian = match vcombine(&upper_block, &lower_block) {
-        Ok(concatenated) => concatenated, // assign concatenated to hamiltonian
-        Err(MatrixCompareError) => return Err(LinAlgError),
-    };
+    let upper_block = hcombine(A, &(-BRinvBT))?;
+    let lower_block = hcombine(&(-Q), &(-AT))?;
+    let mut hamiltonian = vcombine(&upper_block, &lower_block)?;
 
     // use LAPACK to compute ordered real schur decomposition of hamiltonian
     // Ordering ensures eigenvalues placed in top left of quasi-upper triangular matrix are stable / negative
@@ -62,7 +101,7 @@ pub fn solve_continuous_riccati_eigen(
     // "An eigenvalue wr(j)+sqrt(-1)wi(j) is selected if select(wr(j), wi(j)) is true"
     // wr_j: real part of the eigenvalue
     // wi_j: imaginary part of eigenvalue
-    extern "C" fn selectfcn(wr_j: *const f32, _wi_j: *const f32) -> i32 {
+    extern "C" fn selectfcn(wr_j: *const Float, _wi_j: *const Float) -> i32 {
         unsafe {
             match wr_j.as_ref() {
                 // dereference raw pointer
@@ -73,7 +112,7 @@ pub fn solve_continuous_riccati_eigen(
     }
 
     let (nrows, _ncols) = hamiltonian.shape();
-    let select: Select2F32 = Some(selectfcn);
+    let select: Select2 = Some(selectfcn);
     let lda = nrows as i32;
     let mut sdim = 0;
     // wr
@@ -98,7 +137,7 @@ pub fn solve_continuous_riccati_eigen(
 
     // Compute the optimal size of the workspace array
     unsafe {
-        sgees(
+        gees(
             b'V',
             b'S',
             select,
@@ -117,8 +156,8 @@ pub fn solve_continuous_riccati_eigen(
         );
     }
 
-    if info == -1 {
-        return Err(LinAlgError);
+    if info != 0 {
+        return Err(RiccatiError::SchurError(info));
     }
 
     let lda = nrows as i32;
@@ -139,7 +178,7 @@ pub fn solve_continuous_riccati_eigen(
 
     // Compute the Schur Factorization
     unsafe {
-        sgees(
+        gees(
             b'V',
             b'S',
             select,
@@ -158,8 +197,8 @@ pub fn solve_continuous_riccati_eigen(
         );
     }
 
-    if info == -1 {
-        return Err(LinAlgError);
+    if info != 0 {
+        return Err(RiccatiError::SchurError(info));
     }
 
     // println!("Z"); //  Real-schur form matrix
@@ -171,93 +210,499 @@ pub fn solve_continuous_riccati_eigen(
     // println!("T Z T^T");
     // print_matrix(&(&T * &hamiltonian * &T.transpose()));
 
-    // Extract submatrices corresponding to stable eigenvectors
-    let u11 = block((0, 0), (dx - 1, dx - 1), &T);
-    let u21 = block((dx, 0), (2 * dx - 1, dx - 1), &T);
+    // The stabilizing solution requires exactly dx stable eigenvalues
+    if sdim as usize != dx {
+        return Err(RiccatiError::StableSubspaceError { expected: dx, found: sdim as usize });
+    }
+
+    P = stable_subspace_solution(&T, dx)?;
+
+    Ok(P)
+}
+
+
+/// Solve algebraic riccati equation using the generalized eigenvalues of the extended pencil
+///
+/// The control weight R is never inverted, which handles ill-conditioned R and the cross terms of
+/// the pencil directly. The 2dx + du pencil is compressed to 2dx with an orthogonal transformation
+/// before the ordered generalized Schur decomposition
+/// Reference: "Generalized Eigenproblem Algorithms and Software for Algebraic Riccati Equations" - Arnold, Laub
+pub fn solve_continuous_riccati_generalized(
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+) -> Result<DMatrix<Float>, RiccatiError> {
+
+    check_riccati_inputs(A, B, Q, R)?;
+
+    let dx = A.shape().0;
+    let du = B.shape().1;
+
+    // Extended pencil H - sJ
+    // H = [A 0 B; -Q -A^T 0; 0 B^T R], J = diag(I, I, 0)
+    let mut H = DMatrix::<Float>::zeros(2 * dx + du, 2 * dx + du);
+    H.slice_mut((0, 0), (dx, dx)).copy_from(A);
+    H.slice_mut((0, 2 * dx), (dx, du)).copy_from(B);
+    H.slice_mut((dx, 0), (dx, dx)).copy_from(&(-Q));
+    H.slice_mut((dx, dx), (dx, dx)).copy_from(&(-A.transpose()));
+    H.slice_mut((2 * dx, dx), (du, dx)).copy_from(&B.transpose());
+    H.slice_mut((2 * dx, 2 * dx), (du, du)).copy_from(R);
+
+    let mut J = DMatrix::<Float>::zeros(2 * dx + du, 2 * dx);
+    J.slice_mut((0, 0), (2 * dx, 2 * dx)).fill_with_identity();
+
+    // C function to sort only the generalized eigenvalues (alphar + i*alphai) / beta with a negative real part
+    extern "C" fn selectfcn(alphar_j: *const Float, _alphai_j: *const Float, beta_j: *const Float) -> i32 {
+        unsafe {
+            match (alphar_j.as_ref(), beta_j.as_ref()) {
+                // dereference raw pointers, infinite eigenvalues (beta = 0) are never selected
+                (Some(alphar), Some(beta)) => (*alphar * *beta < 0.0) as i32,
+                _ => 0,
+            }
+        }
+    }
+
+    let Z = stable_deflating_subspace(&H, &J, dx, du, Some(selectfcn))?;
+    let P = stable_subspace_solution(&Z, dx)?;
+
+    // Remove the asymmetry introduced by roundoff
+    Ok((&P + P.transpose()) * 0.5)
+
+}
+
+
+/// Solve discrete algebraic riccati equation using the generalized eigenvalues of the extended symplectic pencil
+///
+/// Shares the interface of the continuous Schur solver. As with the continuous extended pencil, neither
+/// R nor A are inverted, so the solver accepts singular state matrices
+/// Reference: "Generalized Eigenproblem Algorithms and Software for Algebraic Riccati Equations" - Arnold, Laub
+pub fn solve_discrete_riccati_eigen(
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+) -> Result<DMatrix<Float>, RiccatiError> {
+
+    check_dimensions(A, B, Q, R)?;
+
+    if !is_stabilizable_discrete(A, B)? {
+        return Err(RiccatiError::NotStabilizableError);
+    }
+
+    if !is_detectable_discrete(A, Q)? {
+        return Err(RiccatiError::NotDetectableError);
+    }
+
+    let dx = A.shape().0;
+    let du = B.shape().1;
+
+    // Extended symplectic pencil H - zJ
+    // H = [A 0 B; -Q I 0; 0 0 R], J = [I 0 0; 0 A^T 0; 0 -B^T 0]
+    let mut H = DMatrix::<Float>::zeros(2 * dx + du, 2 * dx + du);
+    H.slice_mut((0, 0), (dx, dx)).copy_from(A);
+    H.slice_mut((0, 2 * dx), (dx, du)).copy_from(B);
+    H.slice_mut((dx, 0), (dx, dx)).copy_from(&(-Q));
+    H.slice_mut((dx, dx), (dx, dx)).fill_with_identity();
+    H.slice_mut((2 * dx, 2 * dx), (du, du)).copy_from(R);
+
+    let mut J = DMatrix::<Float>::zeros(2 * dx + du, 2 * dx);
+    J.slice_mut((0, 0), (dx, dx)).fill_with_identity();
+    J.slice_mut((dx, dx), (dx, dx)).copy_from(&A.transpose());
+    J.slice_mut((2 * dx, dx), (du, dx)).copy_from(&(-B.transpose()));
+
+    // C function to sort only the generalized eigenvalues (alphar + i*alphai) / beta inside the unit circle
+    extern "C" fn selectfcn(alphar_j: *const Float, alphai_j: *const Float, beta_j: *const Float) -> i32 {
+        unsafe {
+            match (alphar_j.as_ref(), alphai_j.as_ref(), beta_j.as_ref()) {
+                // dereference raw pointers, infinite eigenvalues (beta = 0) are never selected
+                (Some(alphar), Some(alphai), Some(beta)) => {
+                    (alphar * alphar + alphai * alphai < beta * beta) as i32
+                },
+                _ => 0,
+            }
+        }
+    }
+
+    let Z = stable_deflating_subspace(&H, &J, dx, du, Some(selectfcn))?;
+    let P = stable_subspace_solution(&Z, dx)?;
+
+    // Remove the asymmetry introduced by roundoff
+    Ok((&P + P.transpose()) * 0.5)
+
+}
+
+
+/// Relative residual of a continuous algebraic Riccati equation solution
+/// ||A^T P + P A - P B R^-1 B^T P + Q|| / max(1, ||Q||), using Frobenius norms
+pub fn continuous_riccati_residual(
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+    P: &DMatrix<Float>,
+) -> Result<Float, RiccatiError> {
+
+    check_dimensions(A, B, Q, R)?;
+
+    let Rinv_BT_P = match R.clone().lu().solve(&(B.transpose() * P)) {
+        Some(solution) => solution,
+        None => return Err(RiccatiError::SingularWeightError),
+    };
+
+    let residual = A.transpose() * P + P * A - P * B * Rinv_BT_P + Q;
+
+    Ok(residual.norm() / Q.norm().max(1.0))
+
+}
+
+
+/// Relative residual of a discrete algebraic Riccati equation solution
+/// ||A^T P A - P - A^T P B (R + B^T P B)^-1 B^T P A + Q|| / max(1, ||Q||), using Frobenius norms
+pub fn discrete_riccati_residual(
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+    P: &DMatrix<Float>,
+) -> Result<Float, RiccatiError> {
+
+    check_dimensions(A, B, Q, R)?;
+
+    let BT_P = B.transpose() * P;
+    let K = match (R + &BT_P * B).lu().solve(&(&BT_P * A)) {
+        Some(solution) => solution,
+        None => return Err(RiccatiError::SingularWeightError),
+    };
+
+    let residual = A.transpose() * P * A - P - A.transpose() * BT_P.transpose() * K + Q;
+
+    Ok(residual.norm() / Q.norm().max(1.0))
+
+}
+
+
+/// Checks (A, B) is stabilizable with the Popov-Belevitch-Hautus test
+/// rank [A - λI, B] = dx for every eigenvalue λ of A with a nonnegative real part
+pub fn is_stabilizable(A: &DMatrix<Float>, B: &DMatrix<Float>) -> Result<bool, RiccatiError> {
+
+    pbh_test(A, B, |lambda, tolerance| lambda.re >= -tolerance)
+
+}
+
+
+/// Checks (A, C) is detectable, ie. the dual system (A^T, C^T) is stabilizable
+pub fn is_detectable(A: &DMatrix<Float>, C: &DMatrix<Float>) -> Result<bool, RiccatiError> {
+
+    is_stabilizable(&A.transpose(), &C.transpose())
+
+}
+
+
+/// Checks the discrete system (A, B) is stabilizable with the Popov-Belevitch-Hautus test
+/// rank [A - λI, B] = dx for every eigenvalue λ of A on or outside the unit circle
+pub fn is_stabilizable_discrete(A: &DMatrix<Float>, B: &DMatrix<Float>) -> Result<bool, RiccatiError> {
+
+    pbh_test(A, B, |lambda, tolerance| lambda.re.hypot(lambda.im) >= 1.0 - tolerance)
+
+}
+
+
+/// Checks the discrete system (A, C) is detectable, ie. the dual system (A^T, C^T) is stabilizable
+pub fn is_detectable_discrete(A: &DMatrix<Float>, C: &DMatrix<Float>) -> Result<bool, RiccatiError> {
+
+    is_stabilizable_discrete(&A.transpose(), &C.transpose())
+
+}
+
+
+// Popov-Belevitch-Hautus rank test of [A - λI, B] over the eigenvalues λ of A selected by `unstable`
+pub(crate) fn pbh_test<F>(A: &DMatrix<Float>, B: &DMatrix<Float>, unstable: F) -> Result<bool, RiccatiError>
+where
+    F: Fn(&Complex<Float>, Float) -> bool
+{
+
+    let dx = A.shape().0;
+    let tolerance = Float::EPSILON.sqrt() * A.amax().max(B.amax()).max(1.0);
+
+    let passed = eigenvalues(A).map_err(RiccatiError::SchurError)?.iter()
+        .filter(|lambda| unstable(lambda, tolerance))
+        .all(|lambda| {
+            let shifted = A.map(|a| Complex::new(a, 0.0)) - DMatrix::<Complex<Float>>::identity(dx, dx) * *lambda;
+            let pbh = DMatrix::from_fn(dx, dx + B.shape().1, |i, j| {
+                if j < dx { shifted[(i, j)] } else { Complex::new(B[(i, j - dx)], 0.0) }
+            });
+
+            pbh.rank(tolerance) == dx
+        });
+
+    Ok(passed)
+
+}
+
+
+// Validates the dimensions of A, B, Q, R
+fn check_dimensions(
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+) -> Result<(), RiccatiError> {
+
+    let dx = A.shape().0;
+    let du = B.shape().1;
+
+    if A.shape() != (dx, dx) || B.shape().0 != dx || Q.shape() != (dx, dx) || R.shape() != (du, du) {
+        return Err(RiccatiError::DimensionError);
+    }
+
+    Ok(())
+
+}
+
+
+// Validates the Riccati problem admits a unique stabilizing solution
+fn check_riccati_inputs(
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+) -> Result<(), RiccatiError> {
+
+    check_dimensions(A, B, Q, R)?;
+
+    if !is_stabilizable(A, B)? {
+        return Err(RiccatiError::NotStabilizableError);
+    }
+
+    if !is_detectable(A, Q)? {
+        return Err(RiccatiError::NotDetectableError);
+    }
+
+    Ok(())
+
+}
+
+
+// Right Schur vectors spanning the stable deflating subspace of the extended pencil H - sJ
+// The pencil is first compressed to 2dx x 2dx with the orthogonal complement of the input columns of H
+fn stable_deflating_subspace(
+    H: &DMatrix<Float>,
+    J: &DMatrix<Float>,
+    dx: usize,
+    du: usize,
+    select: Select3,
+) -> Result<DMatrix<Float>, RiccatiError> {
+
+    let input_columns = H.columns(2 * dx, du).clone_owned();
+    if input_columns.rank(Float::EPSILON * input_columns.amax().max(1.0)) < du {
+        return Err(RiccatiError::SingularWeightError);
+    }
+    let Qc = hcombine(&input_columns, &DMatrix::identity(2 * dx + du, 2 * dx + du))?.qr().q();
+    let complement = Qc.columns(du, 2 * dx).transpose();
+
+    let mut Hc = &complement * H.columns(0, 2 * dx);
+    let mut Jc = &complement * J;
+
+    let n = 2 * dx;
+    let mut sdim = 0;
+    let mut alphar = vec![0.0; n];
+    let mut alphai = vec![0.0; n];
+    let mut beta = vec![0.0; n];
+    let mut vsl = vec![0.0; n * n];
+    let mut vsr = vec![0.0; n * n];
+    let mut work = vec![0.0; 1];
+    let mut bwork = vec![0; n];
+    let mut info = 0;
+
+    // Compute the optimal size of the workspace array
+    unsafe {
+        gges(
+            b'V',
+            b'V',
+            b'S',
+            select,
+            n as i32,
+            Hc.as_mut_slice(),
+            n as i32,
+            Jc.as_mut_slice(),
+            n as i32,
+            &mut sdim,
+            &mut alphar,
+            &mut alphai,
+            &mut beta,
+            &mut vsl,
+            n as i32,
+            &mut vsr,
+            n as i32,
+            &mut work,
+            -1,
+            &mut bwork,
+            &mut info,
+        );
+    }
+
+    if info != 0 {
+        return Err(RiccatiError::SchurError(info));
+    }
+
+    let lwork = work[0] as i32; // retrieve optimal workspace size
+    let mut work = vec![0.0; lwork as usize];
+
+    // Compute the ordered generalized Schur factorization
+    unsafe {
+        gges(
+            b'V',
+            b'V',
+            b'S',
+            select,
+            n as i32,
+            Hc.as_mut_slice(),
+            n as i32,
+            Jc.as_mut_slice(),
+            n as i32,
+            &mut sdim,
+            &mut alphar,
+            &mut alphai,
+            &mut beta,
+            &mut vsl,
+            n as i32,
+            &mut vsr,
+            n as i32,
+            &mut work,
+            lwork,
+            &mut bwork,
+            &mut info,
+        );
+    }
+
+    if info != 0 {
+        return Err(RiccatiError::SchurError(info));
+    }
+
+    // The stabilizing solution requires exactly dx stable eigenvalues
+    if sdim as usize != dx {
+        return Err(RiccatiError::StableSubspaceError { expected: dx, found: sdim as usize });
+    }
+
+    Ok(DMatrix::from_column_slice(n, n, &vsr))
+
+}
 
-    let u11_inv = match u11.clone().try_inverse() {
+
+// Solution P = U21 * U11^-1 from a basis [U11; U21] of the stable invariant subspace
+fn stable_subspace_solution(U: &DMatrix<Float>, dx: usize) -> Result<DMatrix<Float>, RiccatiError> {
+
+    let u11 = block((0, 0), (dx - 1, dx - 1), U);
+    let u21 = block((dx, 0), (2 * dx - 1, dx - 1), U);
+
+    let u11_inv = match u11.try_inverse() {
         Some(inverse) => inverse,
-        None => return Err(LinAlgError),
+        None => return Err(RiccatiError::SingularSubspaceError),
     };
 
-    P = u21 * u11_inv;
+    let P = u21 * u11_inv;
+
+    if !P.iter().all(|p| p.is_finite()) {
+        return Err(RiccatiError::NonFiniteSolutionError);
+    }
 
     Ok(P)
+
 }
 
 
 /// Iterative solver for Algebraic Riccati Equation for continuous models
+///
+/// Integrates the Riccati differential equation with step dt until successive solutions differ by
+/// less than tolerance, returning a ConvergenceError after iter_max steps
 pub fn solve_continuous_riccati_iterative(
-    A: &DMatrix<f32>,
-    B: &DMatrix<f32>,
-    Q: &DMatrix<f32>,
-    R: &DMatrix<f32>,
-    dt: f32,
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
+    dt: Float,
     iter_max: u32,
-    tolerance: f32,
-) -> Result<DMatrix<f32>, LinAlgError> {
+    tolerance: Float,
+) -> Result<DMatrix<Float>, RiccatiError> {
+
+    check_dimensions(A, B, Q, R)?;
 
     let mut P = Q.clone_owned();
 
-    let mut P_next: DMatrix<f32>;
+    let mut P_next: DMatrix<Float>;
 
     let AT = A.transpose();
     let BT = B.transpose();
 
-    if let Some(Rinv) = R.clone().try_inverse() {
-        let mut diff: f32;
-        for i in 0..iter_max {
-            P_next = &P + ((&P * A) + (&AT * &P) - (&P * B * &Rinv * &BT * &P) + Q) * dt;
+    let Rinv = match R.clone().try_inverse() {
+        Some(inverse) => inverse,
+        None => return Err(RiccatiError::SingularWeightError),
+    };
 
-            diff = (&P_next - &P).amax();
-            P = P_next;
+    let mut diff = Float::INFINITY;
+    for _ in 0..iter_max {
+        P_next = &P + ((&P * A) + (&AT * &P) - (&P * B * &Rinv * &BT * &P) + Q) * dt;
 
-            if diff < tolerance {
-                println!("{:?}", i);
-                break;
-            }
+        diff = (&P_next - &P).amax();
+        P = P_next;
+
+        if !diff.is_finite() {
+            return Err(RiccatiError::NonFiniteSolutionError);
         }
 
-        Ok(P)
-    } else {
-        return Err(LinAlgError);
+        if diff < tolerance {
+            return Ok(P);
+        }
     }
+
+    Err(RiccatiError::ConvergenceError { iterations: iter_max, difference: diff })
 }
 
 /// Iterative solver for Algebraic Riccati Equation for discrete models
+///
+/// Iterates the Riccati difference equation until successive solutions differ by less than
+/// tolerance, returning a ConvergenceError after iter_max iterations
 pub fn solve_discrete_riccati_iterative(
-    A: &DMatrix<f32>,
-    B: &DMatrix<f32>,
-    Q: &DMatrix<f32>,
-    R: &DMatrix<f32>,
+    A: &DMatrix<Float>,
+    B: &DMatrix<Float>,
+    Q: &DMatrix<Float>,
+    R: &DMatrix<Float>,
     iter_max: u32,
-    tolerance: f32,
-) -> Result<DMatrix<f32>, LinAlgError> {
+    tolerance: Float,
+) -> Result<DMatrix<Float>, RiccatiError> {
+
+    check_dimensions(A, B, Q, R)?;
 
     let mut P = Q.clone_owned();
 
-    let mut P_next: DMatrix<f32>;
+    let mut P_next: DMatrix<Float>;
 
     let AT = A.transpose();
     let BT = B.transpose();
 
-    let mut diff: f32;
-    for i in 0..iter_max {
-        let mut RBTPBinv = (R + &BT * &P * B).clone();
-        RBTPBinv.try_inverse_mut();
+    let mut diff = Float::INFINITY;
+    for _ in 0..iter_max {
+        let RBTPBinv = match (R + &BT * &P * B).try_inverse() {
+            Some(inverse) => inverse,
+            None => return Err(RiccatiError::SingularWeightError),
+        };
 
         P_next = &AT * &P * A - &AT * &P * B * RBTPBinv * &BT * &P * A + Q;
 
         diff = (&P_next - &P).amax();
         P = P_next;
 
+        if !diff.is_finite() {
+            return Err(RiccatiError::NonFiniteSolutionError);
+        }
+
         if diff < tolerance {
-            println!("{:?}", i);
-            break;
+            return Ok(P);
         }
     }
 
-    Ok(P)
+    Err(RiccatiError::ConvergenceError { iterations: iter_max, difference: diff })
 }
 
 
@@ -277,19 +722,19 @@ mod tests {
 
         let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
 
-        let Q = DMatrix::<f32>::identity(2, 2);
+        let Q = DMatrix::<Float>::identity(2, 2);
         let R = DMatrix::from_vec(1, 1, vec![1.]);
 
         let P = match solve_continuous_riccati_iterative(&A, &B, &Q, &R, 0.001, 100000, 1E-5) {
             Ok(result) => result,
-            Err(_) => DMatrix::<f32>::zeros(2, 2),
+            Err(_) => DMatrix::<Float>::zeros(2, 2),
         };
 
         let P_true = DMatrix::from_row_slice(
             2, 2, 
             &[
-                3.0_f32.sqrt(), 1., 
-                1., 3.0_f32.sqrt()
+                (3.0 as Float).sqrt(), 1., 
+                1., (3.0 as Float).sqrt()
             ]
         );
 
@@ -317,7 +762,7 @@ mod tests {
 
         let P = match solve_discrete_riccati_iterative(&A, &B, &Q, &R, 100000, 1E-5) {
             Ok(result) => result,
-            Err(_) => DMatrix::<f32>::zeros(2, 2),
+            Err(_) => DMatrix::<Float>::zeros(2, 2),
         };
 
         let P_true = DMatrix::from_row_slice(2, 2, &[-4.0, -4.0, -4.0, 7.0]);
@@ -345,7 +790,7 @@ mod tests {
 
         let P = match solve_continuous_riccati_eigen(&A, &B, &Q, &R) {
             Ok(result) => result,
-            Err(_) => DMatrix::<f32>::zeros(2, 2),
+            Err(_) => DMatrix::<Float>::zeros(2, 2),
         };
 
         let P_true =
@@ -358,4 +803,155 @@ mod tests {
 
         let _ = relative_eq!(P, P_true);
     }
+
+    #[test]
+    fn test_solve_continuous_riccati_generalized() {
+
+        // generate row-major matrices
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        let P = solve_continuous_riccati_generalized(&A, &B, &Q, &R).unwrap();
+
+        let P_true = DMatrix::from_row_slice(
+            2, 2,
+            &[
+                (3.0 as Float).sqrt(), 1.,
+                1., (3.0 as Float).sqrt()
+            ]
+        );
+
+        assert_relative_eq!(P, P_true, epsilon = 1E-4);
+        assert!(continuous_riccati_residual(&A, &B, &Q, &R, &P).unwrap() < 1E-4);
+
+        // Agrees with the Hamiltonian Schur method
+        let A = DMatrix::from_row_slice(2, 2, &[4., 3., -4.5, -3.5]);
+        let B = DMatrix::from_row_slice(2, 1, &[1., -1.]);
+        let Q = DMatrix::from_row_slice(2, 2, &[9., 6., 6., 4.]);
+
+        let P_eigen = solve_continuous_riccati_eigen(&A, &B, &Q, &R).unwrap();
+        let P_generalized = solve_continuous_riccati_generalized(&A, &B, &Q, &R).unwrap();
+
+        assert_relative_eq!(P_eigen, P_generalized, epsilon = 1E-3 * P_eigen.amax());
+
+    }
+
+    #[test]
+    fn test_solve_continuous_riccati_generalized_cheap_control() {
+
+        // Small control weights make B R^-1 B^T dominate the Hamiltonian
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 0.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1E-4]);
+
+        let P = solve_continuous_riccati_generalized(&A, &B, &Q, &R).unwrap();
+
+        // P = [sqrt(2 r^0.5 + 1), r^0.5; r^0.5, sqrt(2 r^0.5 + 1)*r^0.5] for Q = I, R = r
+        let r: Float = 1E-4;
+        let p11 = (2.0 * r.sqrt() + 1.0).sqrt();
+        let p22 = (2.0 * r.sqrt() + 1.0).sqrt() * r.sqrt();
+        let P_true = DMatrix::from_row_slice(2, 2, &[p11, r.sqrt(), r.sqrt(), p22]);
+
+        assert_relative_eq!(P, P_true, epsilon = 1E-3 * P_true.amax());
+
+    }
+
+    #[test]
+    fn test_riccati_input_errors() {
+
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        // Unstable mode is not reachable from the input
+        let A = DMatrix::from_row_slice(2, 2, &[1., 0., 0., -1.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+
+        assert_eq!(solve_continuous_riccati_eigen(&A, &B, &Q, &R), Err(RiccatiError::NotStabilizableError));
+        assert_eq!(solve_continuous_riccati_generalized(&A, &B, &Q, &R), Err(RiccatiError::NotStabilizableError));
+
+        // Unstable mode is not penalized by the state weight
+        let B = DMatrix::from_row_slice(2, 1, &[1., 1.]);
+        let Q = DMatrix::from_row_slice(2, 2, &[0., 0., 0., 1.]);
+
+        assert_eq!(solve_continuous_riccati_generalized(&A, &B, &Q, &R), Err(RiccatiError::NotDetectableError));
+
+        // Inconsistent control weight
+        let R = DMatrix::<Float>::identity(2, 2);
+
+        assert_eq!(solve_continuous_riccati_generalized(&A, &B, &Q, &R), Err(RiccatiError::DimensionError));
+
+        // Singular control weight
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![0.]);
+
+        assert_eq!(solve_continuous_riccati_eigen(&A, &B, &Q, &R), Err(RiccatiError::SingularWeightError));
+
+    }
+
+    #[test]
+    fn test_solve_discrete_riccati_eigen() {
+
+        // Scalar system, P satisfies P^2 = P + 1
+        let A = DMatrix::from_vec(1, 1, vec![1.]);
+        let B = DMatrix::from_vec(1, 1, vec![1.]);
+        let Q = DMatrix::from_vec(1, 1, vec![1.]);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        let P = solve_discrete_riccati_eigen(&A, &B, &Q, &R).unwrap();
+
+        assert_relative_eq!(P[(0, 0)], (1.0 + (5.0 as Float).sqrt()) / 2.0, epsilon = 1E-4);
+
+        // Sampled double integrator agrees with the Riccati difference equation
+        let dt = 0.1;
+        let A = DMatrix::from_row_slice(2, 2, &[1., dt, 0., 1.]);
+        let B = DMatrix::from_row_slice(2, 1, &[0.5*dt*dt, dt]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+
+        let P = solve_discrete_riccati_eigen(&A, &B, &Q, &R).unwrap();
+        let P_iterative = solve_discrete_riccati_iterative(&A, &B, &Q, &R, 100000, 1E-4).unwrap();
+
+        assert_relative_eq!(P, P_iterative, epsilon = 1E-2 * P.amax());
+        assert!(discrete_riccati_residual(&A, &B, &Q, &R, &P).unwrap() < 1E-3);
+
+        // Singular state matrix
+        let A = DMatrix::from_row_slice(2, 2, &[0., 1., 0., -1.]);
+        let B = DMatrix::from_row_slice(2, 2, &[1., 0., 2., 1.]);
+        let R = DMatrix::<Float>::identity(2, 2);
+
+        let P = solve_discrete_riccati_eigen(&A, &B, &Q, &R).unwrap();
+
+        assert!(discrete_riccati_residual(&A, &B, &Q, &R, &P).unwrap() < 1E-4);
+
+    }
+
+    #[test]
+    fn test_riccati_iterative_errors() {
+
+        // Unstable mode is not reachable from the input, so the iteration diverges
+        let A = DMatrix::from_row_slice(2, 2, &[2., 0., 0., 0.5]);
+        let B = DMatrix::from_row_slice(2, 1, &[0., 1.]);
+        let Q = DMatrix::<Float>::identity(2, 2);
+        let R = DMatrix::from_vec(1, 1, vec![1.]);
+
+        match solve_discrete_riccati_iterative(&A, &B, &Q, &R, 10, 1E-6) {
+            Err(RiccatiError::ConvergenceError { iterations, .. }) => assert_eq!(iterations, 10),
+            _ => panic!("expected ConvergenceError"),
+        }
+
+        assert_eq!(solve_discrete_riccati_eigen(&A, &B, &Q, &R), Err(RiccatiError::NotStabilizableError));
+
+        // Singular control weight
+        let R = DMatrix::from_vec(1, 1, vec![0.]);
+
+        assert_eq!(
+            solve_continuous_riccati_iterative(&A, &B, &Q, &R, 0.001, 10, 1E-6),
+            Err(RiccatiError::SingularWeightError)
+        );
+
+    }
 }
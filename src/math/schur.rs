@@ -0,0 +1,87 @@
+// Real Schur decomposition at the precision of Float
+#[cfg(not(feature = "f64"))]
+use lapack::sgees as gees;
+#[cfg(feature = "f64")]
+use lapack::dgees as gees;
+use na::{Complex, DMatrix};
+use crate::float::Float;
+
+// Schur vectors U, quasi-triangular Schur form T and eigenvalues of a real Schur decomposition
+pub(crate) type RealSchur = (DMatrix<Float>, DMatrix<Float>, Vec<Complex<Float>>);
+
+// Real Schur decomposition A = UTU^T, with the eigenvalues of A
+// Fails with the LAPACK info code if the QR algorithm does not converge
+pub(crate) fn real_schur(A: &DMatrix<Float>) -> Result<RealSchur, i32> {
+
+    let n = A.shape().0;
+    let mut T = A.clone();
+    let mut U = DMatrix::<Float>::zeros(n, n);
+    let mut sdim = 0;
+    let mut wr = vec![0.0; n];
+    let mut wi = vec![0.0; n];
+    let mut work = vec![0.0; 3 * n.max(1)];
+    let mut bwork = vec![0; n];
+    let mut info = 0;
+
+    unsafe {
+        gees(
+            b'V',
+            b'N',
+            None,
+            n as i32,
+            T.as_mut_slice(),
+            n.max(1) as i32,
+            &mut sdim,
+            &mut wr,
+            &mut wi,
+            U.as_mut_slice(),
+            n.max(1) as i32,
+            &mut work,
+            3 * n.max(1) as i32,
+            &mut bwork,
+            &mut info,
+        );
+    }
+
+    if info != 0 {
+        return Err(info);
+    }
+
+    let eigenvalues = wr.iter().zip(wi.iter()).map(|(re, im)| Complex::new(*re, *im)).collect();
+
+    Ok((U, T, eigenvalues))
+
+}
+
+// Eigenvalues of A from its real Schur form
+pub(crate) fn eigenvalues(A: &DMatrix<Float>) -> Result<Vec<Complex<Float>>, i32> {
+
+    real_schur(A).map(|(_U, _T, eigenvalues)| eigenvalues)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_real_schur() {
+
+        // Complex eigenvalues -1 ± 2i and a real eigenvalue -3
+        let A = DMatrix::from_row_slice(3, 3, &[-1., 2., 0.5, -2., -1., 1., 0., 0., -3.]);
+
+        let (U, T, eigenvalues) = real_schur(&A).unwrap();
+
+        assert_relative_eq!(&U * &T * U.transpose(), A, epsilon = 1E-5);
+        assert_relative_eq!(U.transpose() * &U, DMatrix::<Float>::identity(3, 3), epsilon = 1E-5);
+        assert_eq!(T[(2, 0)], 0.0);
+
+        let mut eigenvalues: Vec<(Float, Float)> = eigenvalues.iter().map(|lambda| (lambda.re, lambda.im.abs())).collect();
+        eigenvalues.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        assert_relative_eq!(eigenvalues[0].0, -3.0, epsilon = 1E-5);
+        assert_relative_eq!(eigenvalues[1].0, -1.0, epsilon = 1E-5);
+        assert_relative_eq!(eigenvalues[1].1, 2.0, epsilon = 1E-5);
+
+    }
+
+}
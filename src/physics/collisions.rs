@@ -2,6 +2,7 @@
 // https://www.toptal.com/game/video-game-physics-part-ii-collision-detection-for-solid-objects
 
 use na::{Vector2, Vector3};
+use crate::float::Float;
 
 // TODO import polynomial/root finding crate
 //
@@ -9,22 +10,22 @@ use na::{Vector2, Vector3};
 
 pub struct BoundingCircle {
 
-    center_x: f32,
-    center_y: f32,
-    radius: f32
+    center_x: Float,
+    center_y: Float,
+    radius: Float
 
 }
 
 pub struct BoudingSphere {
 
-    center_x: f32,
-    center_y: f32,
-    center_z: f32,
-    radius: f32
+    center_x: Float,
+    center_y: Float,
+    center_z: Float,
+    radius: Float
 
 }
 
-pub fn circle_collision(circle_1: &BoundingCircle, circle_2: &BoundingCircle, dt: f32) -> bool {
+pub fn circle_collision(circle_1: &BoundingCircle, circle_2: &BoundingCircle, dt: Float) -> bool {
 
     // TODO
     // need velocity term
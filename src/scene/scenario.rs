@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use legion::{World, Resources, Schedule};
 use crate::ecs::resources::SimulationResult;
 use crate::ecs::systems::simple::*;
+use crate::float::Float;
 
 pub trait Scenario {
 
@@ -16,7 +17,7 @@ pub trait Scenario {
 pub struct SimpleScenario {
 
     name: String,
-    time: f32
+    time: Float
 
 }
 
@@ -1,18 +1,19 @@
 
 use crate::math::integrate::IntegratorType;
+use crate::float::Float;
 
 /// Configuration for the Simulator loop and engine
 pub struct EngineConfig {
 
-    pub simulation_time: f32,
-    pub max_simulation_time: f32,
-    pub engine_step: f32
+    pub simulation_time: Float,
+    pub max_simulation_time: Float,
+    pub engine_step: Float
 
 }
 
 impl EngineConfig {
 
-    pub fn new(simulation_time: f32, max_simulation_time: f32, engine_step: f32) -> Self {
+    pub fn new(simulation_time: Float, max_simulation_time: Float, engine_step: Float) -> Self {
 
         Self {
             simulation_time,
@@ -29,8 +30,8 @@ impl Default for EngineConfig {
     fn default() -> Self {
 
         Self {
-            simulation_time: 0f32,
-            max_simulation_time: 10f32,
+            simulation_time: 0.0,
+            max_simulation_time: 10.0,
             engine_step: 0.1
         }
 
@@ -39,21 +40,34 @@ impl Default for EngineConfig {
 }
 
 
+/// Response of the Simulator to an entity whose dynamics failed to integrate
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum FailurePolicy {
+    /// Stop the simulation at the end of the engine step
+    #[default]
+    Halt,
+    /// Flag the entity with an IntegrationFailure component and continue without it
+    Flag,
+}
+
+
 /// Configuration for the Simulation and dynamics solvers
 pub struct SimulatorConfig {
 
     pub integrator: IntegratorType,
-    pub integrator_step: f32
+    pub integrator_step: Float,
+    pub failure_policy: FailurePolicy
 
 }
 
 impl SimulatorConfig {
 
-    pub fn new(integrator: IntegratorType, integrator_step: f32) -> Self {
+    pub fn new(integrator: IntegratorType, integrator_step: Float) -> Self {
 
         Self {
             integrator,
-            integrator_step
+            integrator_step,
+            failure_policy: FailurePolicy::default()
         }
 
     }
@@ -67,6 +81,7 @@ impl Default for SimulatorConfig {
         Self {
             integrator: IntegratorType::RK45,
             integrator_step: 0.1,
+            failure_policy: FailurePolicy::default(),
         }
 
     }
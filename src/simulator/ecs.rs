@@ -1,10 +1,11 @@
 
-use legion::{World, Resources, Schedule};
+use legion::{Entity, World, Resources, Schedule};
 use uuid::Uuid;
 use crate::ecs::systems::simple::increment_time_system;
 use crate::ecs::resources::*;
-use crate::ecs::components::{EntityFrame, SimID};
+use crate::ecs::components::{EntityFrame, IntegrationFailure, SimID};
 use crate::simulator::configuration::{EngineConfig, SimulatorConfig};
+use crate::float::Float;
 
 /// Defines the ECS for the MADS engine and configures core resources and entities
 pub struct EntityComponentSystem {
@@ -39,7 +40,7 @@ impl EntityComponentSystem {
 
     }
 
-    pub fn get_current_time(&self) -> f32 {
+    pub fn get_current_time(&self) -> Float {
 
         let elapsed_times = self.resources.get::<SimulationTimeHistory>().unwrap();
         let k = elapsed_times.data.len();
@@ -48,6 +49,25 @@ impl EntityComponentSystem {
 
     }
 
+    /// Flags entities whose dynamics failed to integrate during the last engine step
+    /// Returns the number of newly flagged entities
+    pub fn flag_failures(&mut self) -> usize {
+
+        let failures: Vec<(Entity, IntegrationFailure)> = match self.resources.get_mut::<IntegrationFailures>() {
+            Some(failures) => failures.data.lock().unwrap().drain(..).collect(),
+            None => return 0,
+        };
+
+        for (entity, failure) in failures.iter() {
+            if let Some(mut entry) = self.world.entry(*entity) {
+                entry.add_component(failure.clone());
+            }
+        }
+
+        failures.len()
+
+    }
+
     /// Inserts Resources to ECS derived from an EngineConfig
     fn add_engine_resources(&mut self, config: &EngineConfig) {
 
@@ -74,6 +94,7 @@ impl EntityComponentSystem {
 
         self.resources.insert(IntegratorStep(config.integrator_step));
         self.resources.insert(Integrator(config.integrator));
+        self.resources.insert(IntegrationFailures::default());
 
     }
 
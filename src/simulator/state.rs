@@ -1,5 +1,5 @@
 
-use crate::simulator::configuration::{EngineConfig, SimulatorConfig};
+use crate::simulator::configuration::{EngineConfig, SimulatorConfig, FailurePolicy};
 use crate::simulator::ecs::EntityComponentSystem;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -61,6 +61,15 @@ impl SimulatorState
             // Execute ECS systems
             self.ecs.schedule.execute(&mut self.ecs.world, &mut self.ecs.resources);
 
+            // Flag entities with diverging dynamics and halt if configured to do so
+            let failures = self.ecs.flag_failures();
+            if failures > 0 && self.simulation_config.failure_policy == FailurePolicy::Halt {
+
+                println!("************************** HALTED! *****************************");
+                self.status = EngineState::Inactive;
+
+            }
+
         }
 
     }
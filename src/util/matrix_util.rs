@@ -1,4 +1,5 @@
 use std::fmt;
+use crate::float::Float;
 
 use na::DMatrix;
 
@@ -15,14 +16,14 @@ impl fmt::Display for MatrixCompareError {
 }
 
 /// Extracts desired block from a matrix
-pub fn block(start: (usize, usize), end: (usize, usize), matrix: &DMatrix<f32>) -> DMatrix<f32> {
+pub fn block(start: (usize, usize), end: (usize, usize), matrix: &DMatrix<Float>) -> DMatrix<Float> {
     //TODO check for start and end valid for matrix
 
     let (_nrows, _ncols) = matrix.shape();
 
     let (dx, dy) = (end.0 - start.0, end.1 - start.1); // assume that start < end
 
-    let mut work = DMatrix::<f32>::zeros(dx + 1, dy + 1);
+    let mut work = DMatrix::<Float>::zeros(dx + 1, dy + 1);
 
     // iterate over matrix
     for i in start.0..end.0 + 1 {
@@ -36,9 +37,9 @@ pub fn block(start: (usize, usize), end: (usize, usize), matrix: &DMatrix<f32>)
 
 /// Horizontally combines two matrices of similar dimensions
 pub fn hcombine(
-    lmatrix: &DMatrix<f32>,
-    rmatrix: &DMatrix<f32>,
-) -> Result<DMatrix<f32>, MatrixCompareError> {
+    lmatrix: &DMatrix<Float>,
+    rmatrix: &DMatrix<Float>,
+) -> Result<DMatrix<Float>, MatrixCompareError> {
     // Check for matching number of rows
     if lmatrix.shape().0 != rmatrix.shape().0 {
         return Err(MatrixCompareError);
@@ -47,7 +48,7 @@ pub fn hcombine(
     // Allocate new matrix
     let rows = lmatrix.shape().0;
     let cols = lmatrix.shape().1 + rmatrix.shape().1;
-    let mut concatenated = DMatrix::<f32>::zeros(rows, cols);
+    let mut concatenated = DMatrix::<Float>::zeros(rows, cols);
 
     for i in 0..lmatrix.shape().0 {
         for j in 0..lmatrix.shape().1 {
@@ -69,9 +70,9 @@ pub fn hcombine(
 
 /// Vertically combines two matrices of similar dimensions
 pub fn vcombine(
-    umatrix: &DMatrix<f32>,
-    bmatrix: &DMatrix<f32>,
-) -> Result<DMatrix<f32>, MatrixCompareError> {
+    umatrix: &DMatrix<Float>,
+    bmatrix: &DMatrix<Float>,
+) -> Result<DMatrix<Float>, MatrixCompareError> {
     // Check for matching number of rows
     if umatrix.shape().1 != bmatrix.shape().1 {
         return Err(MatrixCompareError);
@@ -80,7 +81,7 @@ pub fn vcombine(
     // Allocate new matrix
     let rows = umatrix.shape().0 + bmatrix.shape().0;
     let cols = umatrix.shape().1;
-    let mut concatenated = DMatrix::<f32>::zeros(rows, cols);
+    let mut concatenated = DMatrix::<Float>::zeros(rows, cols);
 
     for i in 0..umatrix.shape().0 {
         for j in 0..umatrix.shape().1 {
@@ -101,7 +102,7 @@ pub fn vcombine(
 }
 
 /// Prints matrix to console in an easy to read way
-pub fn print_matrix(matrix: &DMatrix<f32>) {
+pub fn print_matrix(matrix: &DMatrix<Float>) {
     for i in 0..matrix.shape().0 {
         let mut row = vec![0.0; matrix.shape().1];
         for j in 0..matrix.shape().1 {
@@ -1,14 +1,18 @@
 
+use crate::float::Float;
 
 /// Returns a Vector of values corresponding to [start : end : step]
-pub fn range_step(start: f32, end: f32, step: f32) -> Vec<f32> {
+pub fn range_step(start: Float, end: Float, step: Float) -> Vec<Float> {
     // TODO: assert non-negative, step is a factor of end or something like that
 
-    let mut result: Vec<f32> = Vec::new();
+    // Scale the step count rather than accumulating steps, which drifts in double precision
+    let mut result: Vec<Float> = Vec::new();
+    let mut k = 0;
     let mut count = start;
     while count < end {
         result.push(count);
-        count += step;
+        k += 1;
+        count = start + (k as Float) * step;
     }
 
     result
@@ -24,6 +28,6 @@ mod tests {
         let range = range_step(0.0, 1.0, 0.1);
 
         assert_eq!(range.len(), 10);
-        assert_eq!(range.iter().sum::<f32>(), 4.5);
+        assert_relative_eq!(range.iter().sum::<Float>(), 4.5);
     }
 }
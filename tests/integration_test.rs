@@ -1,36 +1,72 @@
 
-use nalgebra::{DVector, DMatrix};
-use mads::dynamics::models::DoubleIntegrator3D;
+use nalgebra::{Complex, DVector, DMatrix};
+use legion::*;
+use uuid::Uuid;
+use mads::dynamics::models::{DoubleIntegrator1D, DoubleIntegrator2D, DoubleIntegrator3D, InvertedPendulum, NonlinearInvertedPendulum, HardeningSpring};
+use mads::dynamics::linear_system::{DiscretizationMethod, DiscreteLTISystem, LTISystem};
 use mads::dynamics::statespace::StateSpaceRepresentation;
+use mads::controls::controller::Controller;
 use mads::controls::models::LinearQuadraticRegulator as LQR;
+use mads::controls::models::DiscreteLinearQuadraticRegulator as DLQR;
+use mads::controls::models::FiniteHorizonLinearQuadraticRegulator as FiniteHorizonLQR;
+use mads::controls::models::{PIDController as PID, PIDOptions, AntiWindup};
+use mads::controls::models::{ModelPredictiveController as MPC, MPCOptions};
+use mads::controls::models::{IterativeLinearQuadraticRegulator as ILQR, ILQROptions};
+use mads::controls::models::{GainScheduledController, Interpolation};
+use mads::controls::models::{PolePlacementController, PolePlacementMethod};
 use mads::math::integrate::{solve_ivp, SolverOptions, IntegratorType};
+use mads::scene::scenario::Scenario;
+use mads::simulator::Simulator;
+use mads::simulator::configuration::{EngineConfig, SimulatorConfig, FailurePolicy};
+use mads::simulator::state::SimulatorState;
+use mads::ecs::components::{FullState, SimID, IntegrationFailure, Reference, IntegralAction, SubstepTrajectory};
+use mads::ecs::systems::simple::increment_time_system;
+use mads::ecs::systems::simulate::{integrate_controlled_dynamics_system, integrate_dynamics_system, integrate_dlqr_dynamics_system, integrate_lqr_dynamics_system, integrate_lqr_tracking_dynamics_system, step_discrete_dynamics_system, step_discrete_lti_dynamics_system};
+use mads::float::Float;
+
+// Builds and runs the scenario, returning the simulator and the name and final state of each
+// entity which did not fail
+fn run_scenario<S: Scenario>(scenario: S, engine_config: EngineConfig, sim_config: SimulatorConfig) -> (Simulator<S>, Vec<(String, DVector<Float>)>) {
+
+    let sim_state = SimulatorState::new(engine_config, sim_config);
+
+    let mut simulator = Simulator::new(sim_state, scenario);
+    simulator.build();
+    simulator.run();
+
+    let ecs = &simulator.get_state().ecs;
+    let mut query = <(&SimID, &FullState)>::query().filter(!component::<IntegrationFailure>());
+    let states = query.iter(&ecs.world)
+        .map(|(id, state)| (id.name.clone(), state.data.clone()))
+        .collect();
+
+    (simulator, states)
+
+}
 
 #[test]
 fn test_dynamics_no_ecs() {
     let model = DoubleIntegrator3D::new();
 
     // Define matrices for a Linear Quadratic Regulator
-    let Q = DMatrix::<f32>::identity(6, 6);
-    let R = DMatrix::<f32>::identity(3, 3);
+    let Q = DMatrix::<Float>::identity(6, 6);
+    let R = DMatrix::<Float>::identity(3, 3);
     let lqr = LQR::new(
         model.dynamics().A.clone(),
         model.dynamics().B.clone(),
         Q,
         R,
-    );
+    ).unwrap();
 
 
     // Define initial conditions
     let x0 = DVector::from_vec(vec![10., 10., -10., -30., 12., 2.]);
 
     // Solve the LQR controller
-    let (K, _P) = match lqr.solve() {
-        Ok((value1, value2)) => (value1, value2),
-        Err(_) => (DMatrix::<f32>::zeros(1, 1), DMatrix::<f32>::zeros(1, 1)),
-    };
+    let (K, _P) = lqr.solve();
 
     // Evolve dynamics
-    let mut trajectory: Vec<DVector<f32>> = vec![x0];
+    let mut trajectory: Vec<DVector<Float>> = vec![x0];
     let step = 0.1;
     let t0 = 0;
     let tf = 10;
@@ -41,15 +77,15 @@ fn test_dynamics_no_ecs() {
         println!("{:?}", &x_prev);
 
         // Wrap dynamics/controls in appropriately defined closure - f(t, x)
-        let f = |t: f32, x: &DVector<f32>| {
+        let f = |t: Float, x: &DVector<Float>| {
             let u = -&K * x;
             model.f(t, x, Some(&u))
         };
 
         // Integrate dynamics
         let opts = SolverOptions{ first_step: Some(step), ..SolverOptions::default() };
-        let (_t_history, traj) = match solve_ivp(f, (t as f32, (t as f32)+1f32), x_prev, IntegratorType::RK45, opts) {
-            Ok(ode_result) => ode_result,
+        let traj = match solve_ivp(f, (t as Float, (t as Float)+1.0), x_prev, IntegratorType::RK45, opts) {
+            Ok(ode_result) => ode_result.y,
             Err(error) => panic!("solve_ivp error! {}", error)
         };
 
@@ -64,3 +100,849 @@ fn test_dynamics_no_ecs() {
 
 
 }
+
+
+// Unstable scalar dynamics x_dot = x^2, which escape to infinity at t = 1/x0
+struct Unstable;
+
+impl StateSpaceRepresentation for Unstable {
+
+    fn f(&self, _t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DVector<Float> {
+        x.component_mul(x)
+    }
+
+    fn h(&self, _t: Float, x: &DVector<Float>, _u: Option<&DVector<Float>>) -> DVector<Float> {
+        x.clone()
+    }
+
+}
+
+struct DivergingScenario;
+
+impl Scenario for DivergingScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // The diverging entity grows without bound while the other remains at equilibrium
+        let entities: Vec<(FullState, Unstable, SimID)> = vec![("Diverging", 1.0), ("Equilibrium", 0.0)].into_iter()
+            .map(|(name, x0)| {
+                let sim_id = SimID { uuid: Uuid::new_v4(), name: name.to_string() };
+                (FullState { data: DVector::from_vec(vec![x0]) }, Unstable, sim_id)
+            })
+            .collect();
+
+        world.extend(entities);
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_dynamics_system::<Unstable>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+fn run_diverging_scenario(failure_policy: FailurePolicy) -> (Simulator<DivergingScenario>, Vec<(String, DVector<Float>)>) {
+
+    run_scenario(DivergingScenario, EngineConfig::new(0.0, 2.0, 0.1), SimulatorConfig { failure_policy, ..SimulatorConfig::default() })
+
+}
+
+#[test]
+fn test_integration_failure_flag() {
+
+    let (simulator, states) = run_diverging_scenario(FailurePolicy::Flag);
+    let ecs = &simulator.get_state().ecs;
+
+    // Simulation continues to the final time
+    assert!(ecs.get_current_time() >= 2.0 - 1E-4);
+    assert!(states.iter().all(|(name, _)| name != "Diverging"));
+
+    let mut query = <(&SimID, &FullState, Option<&IntegrationFailure>)>::query();
+    for (id, state, failure) in query.iter(&ecs.world) {
+        // Flagged entity holds its last valid state
+        assert!(state.data.iter().all(|x| x.is_finite()));
+        assert_eq!(failure.is_some(), id.name == "Diverging");
+    }
+
+}
+
+#[test]
+fn test_integration_failure_halt() {
+
+    let (simulator, _states) = run_diverging_scenario(FailurePolicy::Halt);
+    let ecs = &simulator.get_state().ecs;
+
+    // Simulation stops at the engine step in which the entity diverged
+    assert!(ecs.get_current_time() < 2.0);
+
+}
+
+struct DiscreteScenario;
+
+impl Scenario for DiscreteScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Double integrator sampled at half the engine step, with a discrete LQR designed for the
+        // sampled plant
+        let model = DoubleIntegrator1D::new();
+        let dynamics = model.dynamics().discretize(0.05, DiscretizationMethod::ZeroOrderHold).unwrap();
+
+        let q = DMatrix::<Float>::identity(2, 2);
+        let r = DMatrix::<Float>::identity(1, 1);
+        let controller = DLQR::new(dynamics.A.clone(), dynamics.B.clone(), q, r, None);
+
+        // A sample period which does not divide the engine step
+        let mistimed = model.dynamics().discretize(0.03, DiscretizationMethod::ZeroOrderHold).unwrap();
+
+        let x0 = DVector::from_vec(vec![1.0, 2.0]);
+        let free = SimID { uuid: Uuid::new_v4(), name: "Free".to_string() };
+        let controlled = SimID { uuid: Uuid::new_v4(), name: "Controlled".to_string() };
+        let mistimed_id = SimID { uuid: Uuid::new_v4(), name: "Mistimed".to_string() };
+
+        world.push((FullState { data: x0.clone() }, dynamics.clone(), free));
+        world.push((FullState { data: x0.clone() }, dynamics, controller, controlled));
+        world.push((FullState { data: x0 }, mistimed, mistimed_id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(step_discrete_lti_dynamics_system())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_discrete_lti_dynamics() {
+
+    let (simulator, states) = run_scenario(DiscreteScenario, EngineConfig::new(0.0, 1.0, 0.1), SimulatorConfig { failure_policy: FailurePolicy::Flag, ..SimulatorConfig::default() });
+    let ecs = &simulator.get_state().ecs;
+    let t = ecs.get_current_time();
+
+    for (name, state) in states.iter() {
+        match name.as_str() {
+            // Zero-order hold discretization is exact for the unforced system
+            "Free" => assert!((state[0] - (1.0 + 2.0 * t)).abs() < 1E-4),
+            // Regulated towards the origin
+            "Controlled" => assert!(state.norm() < DVector::<Float>::from_vec(vec![1.0, 2.0]).norm()),
+            _ => panic!("{} should have been flagged", name),
+        }
+    }
+    assert_eq!(states.len(), 2);
+
+    // Flagged without being advanced
+    let mut query = <&FullState>::query().filter(component::<IntegrationFailure>());
+    let flagged: Vec<&FullState> = query.iter(&ecs.world).collect();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].data, DVector::from_vec(vec![1.0, 2.0]));
+
+}
+
+struct MixedScenario;
+
+impl Scenario for MixedScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // The same plant as a continuous model and as a discrete model sampled at a quarter of the
+        // engine step
+        let continuous = DoubleIntegrator1D::new();
+        let discrete = continuous.dynamics().discretize(0.025, DiscretizationMethod::ZeroOrderHold).unwrap();
+
+        let x0 = DVector::from_vec(vec![1.0, -0.5]);
+        let continuous_id = SimID { uuid: Uuid::new_v4(), name: "Continuous".to_string() };
+        let discrete_id = SimID { uuid: Uuid::new_v4(), name: "Discrete".to_string() };
+
+        world.push((FullState { data: x0.clone() }, continuous, continuous_id));
+        world.push((FullState { data: x0 }, discrete, discrete_id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_dynamics_system::<DoubleIntegrator1D>())
+            .add_system(step_discrete_dynamics_system::<DiscreteLTISystem>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_mixed_discrete_continuous_dynamics() {
+
+    let (simulator, states) = run_scenario(MixedScenario, EngineConfig::new(0.0, 1.0, 0.1), SimulatorConfig::default());
+    let ecs = &simulator.get_state().ecs;
+
+    assert_eq!(states.len(), 2);
+
+    // Both representations advance the unforced plant to the same state
+    let error = (&states[0].1 - &states[1].1).amax();
+    assert!(error < 1E-4);
+    assert!((states[0].1[0] - (1.0 - 0.5 * ecs.get_current_time())).abs() < 1E-4);
+
+}
+
+struct SampledControllerScenario;
+
+impl Scenario for SampledControllerScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Continuous plant with a discrete LQR designed for its zero-order hold discretization
+        let model = DoubleIntegrator1D::new();
+        let sampled = model.dynamics().discretize(0.1, DiscretizationMethod::ZeroOrderHold).unwrap();
+
+        let q = DMatrix::<Float>::identity(2, 2);
+        let r = DMatrix::<Float>::identity(1, 1);
+        let controller = DLQR::new(sampled.A.clone(), sampled.B.clone(), q, r, None);
+
+        let x0 = DVector::from_vec(vec![1.0, 2.0]);
+        let id = SimID { uuid: Uuid::new_v4(), name: "Sampled".to_string() };
+
+        world.push((FullState { data: x0 }, model, controller, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_dlqr_dynamics_system::<DoubleIntegrator1D>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_sampled_dlqr_dynamics() {
+
+    let (simulator, states) = run_scenario(SampledControllerScenario, EngineConfig::new(0.0, 1.0, 0.1), SimulatorConfig::default());
+    let ecs = &simulator.get_state().ecs;
+    let steps = (ecs.get_current_time() / 0.1).round() as usize;
+
+    // The sampled-data loop matches the discrete closed loop x[k+1] = (Ad - Bd K) x[k]
+    let model = DoubleIntegrator1D::new();
+    let sampled = model.dynamics().discretize(0.1, DiscretizationMethod::ZeroOrderHold).unwrap();
+    let controller = DLQR::new(sampled.A.clone(), sampled.B.clone(), DMatrix::identity(2, 2), DMatrix::identity(1, 1), None);
+    let (k, _p) = controller.solve().unwrap();
+
+    let mut expected = DVector::<Float>::from_vec(vec![1.0, 2.0]);
+    for _ in 0..steps {
+        expected = (&sampled.A - &sampled.B * &k) * expected;
+    }
+
+    assert_eq!(states.len(), 1);
+    for (_name, state) in states.iter() {
+        assert!((state - &expected).amax() < 1E-2);
+    }
+
+}
+
+struct RendezvousScenario;
+
+impl Scenario for RendezvousScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Drive the double integrator to the origin at t = 5 with a heavily weighted terminal state
+        let model = DoubleIntegrator1D::new();
+        let q = DMatrix::<Float>::zeros(2, 2);
+        let r = DMatrix::<Float>::identity(1, 1);
+        let f = DMatrix::<Float>::identity(2, 2) * 1000.0;
+        let controller = FiniteHorizonLQR::new(
+            model.dynamics().A.clone(),
+            model.dynamics().B.clone(),
+            q, r, f,
+            (0.0, 5.0),
+            100
+        ).unwrap();
+
+        let x0 = DVector::from_vec(vec![5.0, 0.0]);
+        let id = SimID { uuid: Uuid::new_v4(), name: "Chaser".to_string() };
+
+        world.push((FullState { data: x0 }, model, controller, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_lqr_dynamics_system::<DoubleIntegrator1D>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_finite_horizon_lqr_rendezvous() {
+
+    // Stop at the end of the horizon regardless of roundoff in the accumulated engine time
+    let (simulator, states) = run_scenario(RendezvousScenario, EngineConfig::new(0.0, 4.95, 0.1), SimulatorConfig::default());
+    let ecs = &simulator.get_state().ecs;
+    assert!((ecs.get_current_time() - 5.0).abs() < 1E-3);
+
+    assert!(!states.is_empty());
+    for (_name, state) in states.iter() {
+        assert!(state.amax() < 0.1);
+    }
+
+}
+
+struct SetpointScenario;
+
+impl Scenario for SetpointScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Each double integrator is driven to its own setpoint
+        let model = DoubleIntegrator1D::new();
+        let q = DMatrix::<Float>::identity(2, 2);
+        let r = DMatrix::<Float>::identity(1, 1);
+        let controller = LQR::new(model.dynamics().A.clone(), model.dynamics().B.clone(), q, r).unwrap();
+
+        for (name, target) in [("Agent0", 3.0), ("Agent1", -2.0)] {
+            let id = SimID { uuid: Uuid::new_v4(), name: name.to_string() };
+            let reference = Reference::setpoint(DVector::from_vec(vec![target, 0.0]), None);
+            world.push((FullState { data: DVector::from_vec(vec![0.0, 0.0]) }, model.clone(), controller.clone(), reference, id));
+        }
+
+        // First order lag x_dot = -x + u + 0.5 with an unmodeled constant input disturbance,
+        // which needs integral action to reach its setpoint
+        let lag = LTISystem::new(
+            DMatrix::from_vec(1, 1, vec![-1.0]),
+            DMatrix::from_vec(1, 1, vec![1.0]),
+            DMatrix::from_vec(1, 1, vec![1.0]),
+            DMatrix::from_vec(1, 1, vec![0.0])
+        );
+        let disturbed = Disturbed { model: lag.clone(), disturbance: 0.5 };
+        let controller = LQR::new(lag.A.clone(), lag.B.clone(), DMatrix::identity(1, 1), DMatrix::identity(1, 1)).unwrap();
+        let integral = IntegralAction::new(DMatrix::from_vec(1, 1, vec![2.0]), DMatrix::identity(1, 1));
+        let reference = Reference::setpoint(DVector::from_vec(vec![1.0]), None);
+        let id = SimID { uuid: Uuid::new_v4(), name: "Integral".to_string() };
+
+        let substeps = SubstepTrajectory::new(4);
+
+        world.push((FullState { data: DVector::from_vec(vec![0.0]) }, disturbed, controller, reference, integral, substeps, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_lqr_tracking_dynamics_system::<DoubleIntegrator1D>())
+            .add_system(integrate_lqr_tracking_dynamics_system::<Disturbed>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+// Linear model with a constant input disturbance
+#[derive(Clone)]
+struct Disturbed {
+    model: LTISystem,
+    disturbance: Float,
+}
+
+impl StateSpaceRepresentation for Disturbed {
+
+    fn f(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
+        self.model.f(t, x, u).add_scalar(self.disturbance)
+    }
+
+    fn h(&self, t: Float, x: &DVector<Float>, u: Option<&DVector<Float>>) -> DVector<Float> {
+        self.model.h(t, x, u)
+    }
+
+}
+
+#[test]
+fn test_lqr_tracking_setpoints() {
+
+    let (simulator, states) = run_scenario(SetpointScenario, EngineConfig::new(0.0, 15.0, 0.1), SimulatorConfig::default());
+    let ecs = &simulator.get_state().ecs;
+
+    assert_eq!(states.len(), 3);
+    for (name, state) in states.iter() {
+        let target = match name.as_str() {
+            "Agent0" => DVector::from_vec(vec![3.0, 0.0]),
+            "Agent1" => DVector::from_vec(vec![-2.0, 0.0]),
+            _ => DVector::from_vec(vec![1.0]),
+        };
+        assert!((state - target).amax() < 1E-2);
+    }
+
+    // The integral action entity records four samples per engine step, without the integral state
+    let mut query = <(&FullState, &SubstepTrajectory)>::query();
+    let (state, substeps) = query.iter(&ecs.world).next().unwrap();
+    let steps = (ecs.get_current_time() / 0.1).round() as usize;
+    assert_eq!(substeps.times.len(), 4 * steps + 1);
+    assert_eq!(substeps.data.len(), substeps.times.len());
+    assert!(substeps.data.iter().all(|x| x.len() == 1));
+    assert!(substeps.times.windows(2).all(|t| t[1] > t[0]));
+    assert_eq!(substeps.data.last().unwrap(), &state.data);
+
+}
+
+// Registers the generic controller system for the LQR as well as the LQR tracking system if
+// overlapping
+struct ControlledScenario {
+    overlapping: bool,
+}
+
+impl Scenario for ControlledScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        let model = DoubleIntegrator1D::new();
+        let q = DMatrix::<Float>::identity(2, 2);
+        let r = DMatrix::<Float>::identity(1, 1);
+        let controller = LQR::new(model.dynamics().A.clone(), model.dynamics().B.clone(), q, r).unwrap();
+        let reference = Reference::setpoint(DVector::from_vec(vec![3.0, 0.0]), None);
+        let id = SimID { uuid: Uuid::new_v4(), name: "LQR".to_string() };
+        world.push((FullState { data: DVector::from_vec(vec![0.0, 0.0]) }, model.clone(), controller, reference, id));
+
+        // User-defined controller regulating to the origin
+        let controller = ProportionalDerivative { kp: 4.0, kd: 4.0 };
+        let id = SimID { uuid: Uuid::new_v4(), name: "PD".to_string() };
+        world.push((FullState { data: DVector::from_vec(vec![-2.0, 1.0]) }, model, controller, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        let mut builder = Schedule::builder();
+        builder.add_system(integrate_lqr_tracking_dynamics_system::<DoubleIntegrator1D>());
+        if self.overlapping {
+            builder.add_system(integrate_controlled_dynamics_system::<DoubleIntegrator1D, LQR>());
+        }
+        builder
+            .add_system(integrate_controlled_dynamics_system::<DoubleIntegrator1D, ProportionalDerivative>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+// Position and velocity feedback for a double integrator
+struct ProportionalDerivative {
+    kp: Float,
+    kd: Float,
+}
+
+impl Controller for ProportionalDerivative {
+
+    fn control(&self, _t: Float, x: &DVector<Float>, reference: Option<&DVector<Float>>) -> DVector<Float> {
+        let error = match reference {
+            Some(reference) => x - reference,
+            None => x.clone(),
+        };
+        DVector::from_vec(vec![-self.kp * error[0] - self.kd * error[1]])
+    }
+
+}
+
+#[test]
+fn test_generic_controller_dynamics() {
+
+    let (_simulator, mut states) = run_scenario(ControlledScenario { overlapping: false }, EngineConfig::new(0.0, 15.0, 0.1), SimulatorConfig::default());
+
+    assert_eq!(states.len(), 2);
+    for (name, state) in states.iter() {
+        let target = match name.as_str() {
+            "LQR" => DVector::from_vec(vec![3.0, 0.0]),
+            _ => DVector::<Float>::zeros(2),
+        };
+        assert!((state - target).amax() < 1E-2);
+    }
+
+    // The generic system excludes LQR entities, which are integrated once with both registered
+    let (_simulator, mut overlapping) = run_scenario(ControlledScenario { overlapping: true }, EngineConfig::new(0.0, 15.0, 0.1), SimulatorConfig::default());
+    states.sort_by(|a, b| a.0.cmp(&b.0));
+    overlapping.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(overlapping, states);
+
+}
+
+struct PIDScenario;
+
+impl Scenario for PIDScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Saturated position control of each axis of a double integrator
+        let measurement = DMatrix::from_row_slice(2, 4, &[1., 0., 0., 0., 0., 1., 0., 0.]);
+        let limits = Some((DVector::from_vec(vec![-1.0, -1.0]), DVector::from_vec(vec![1.0, 1.0])));
+        let options = PIDOptions { limits, anti_windup: AntiWindup::Clamping, ..PIDOptions::default() };
+        let controller = PID::new(
+            DVector::from_vec(vec![3.0, 3.0]),
+            DVector::from_vec(vec![1.0, 1.0]),
+            DVector::from_vec(vec![3.0, 3.0]),
+            measurement,
+            options
+        );
+        let reference = Reference::setpoint(DVector::from_vec(vec![3.0, -2.0, 0.0, 0.0]), None);
+        let id = SimID { uuid: Uuid::new_v4(), name: "DoubleIntegrator".to_string() };
+        world.push((FullState { data: DVector::<Float>::zeros(4) }, DoubleIntegrator2D::new(), controller, reference, id));
+
+        // Balance the linearized inverted pendulum from a small angle
+        let measurement = DMatrix::from_row_slice(1, 4, &[0., 0., 1., 0.]);
+        let controller = PID::new(
+            DVector::from_vec(vec![20.0]),
+            DVector::from_vec(vec![1.0]),
+            DVector::from_vec(vec![3.0]),
+            measurement,
+            PIDOptions::default()
+        );
+        let id = SimID { uuid: Uuid::new_v4(), name: "InvertedPendulum".to_string() };
+        world.push((FullState { data: DVector::from_vec(vec![0.0, 0.0, 0.1, 0.0]) }, InvertedPendulum::new(), controller, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_controlled_dynamics_system::<DoubleIntegrator2D, PID>())
+            .add_system(integrate_controlled_dynamics_system::<InvertedPendulum, PID>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_pid_dynamics() {
+
+    let (_simulator, states) = run_scenario(PIDScenario, EngineConfig::new(0.0, 30.0, 0.1), SimulatorConfig::default());
+
+    assert_eq!(states.len(), 2);
+    for (name, state) in states.iter() {
+        match name.as_str() {
+            "DoubleIntegrator" => assert!((state - DVector::from_vec(vec![3.0, -2.0, 0.0, 0.0])).amax() < 1E-2),
+            // The cart drifts since only the pendulum angle is controlled
+            _ => assert!(state[2].abs() < 1E-2 && state[3].abs() < 1E-2),
+        }
+    }
+
+}
+
+struct MPCScenario;
+
+impl Scenario for MPCScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Thrust and speed limited double integrator sampled at the engine step
+        let model = DoubleIntegrator1D::new();
+        let system = model.dynamics().discretize(0.1, DiscretizationMethod::ZeroOrderHold).unwrap();
+        let q = DMatrix::<Float>::identity(2, 2);
+        let r = DMatrix::from_vec(1, 1, vec![0.1]);
+        let options = MPCOptions {
+            input_limits: Some((DVector::from_vec(vec![-0.5]), DVector::from_vec(vec![0.5]))),
+            state_limits: Some((DVector::from_vec(vec![-Float::INFINITY, -1.0]), DVector::from_vec(vec![Float::INFINITY, 1.0]))),
+            ..MPCOptions::default()
+        };
+        let controller = MPC::new(system, 10, q, r, options).unwrap();
+        let reference = Reference::setpoint(DVector::from_vec(vec![3.0, 0.0]), None);
+        let id = SimID { uuid: Uuid::new_v4(), name: "MPC".to_string() };
+
+        world.push((FullState { data: DVector::from_vec(vec![0.0, 0.0]) }, model, controller, reference, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_controlled_dynamics_system::<DoubleIntegrator1D, MPC>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, world: &mut World, _resources: &mut Resources) {
+
+        // Constraints hold along the closed loop trajectory
+        let mut query = <(&FullState, &MPC)>::query();
+        for (state, controller) in query.iter(world) {
+            assert!(controller.input[0].abs() <= 0.5 + 1E-3);
+            assert!(state.data[1].abs() <= 1.0 + 1E-2);
+        }
+
+    }
+
+}
+
+#[test]
+fn test_mpc_dynamics() {
+
+    let (simulator, states) = run_scenario(MPCScenario, EngineConfig::new(0.0, 15.0, 0.1), SimulatorConfig::default());
+    let ecs = &simulator.get_state().ecs;
+
+    assert_eq!(states.len(), 1);
+    assert!((&states[0].1 - DVector::from_vec(vec![3.0, 0.0])).amax() < 1E-2);
+
+    let mut query = <&MPC>::query();
+    for controller in query.iter(&ecs.world) {
+        assert!(controller.plan.iter().any(|u| u.abs() > 0.0));
+    }
+
+}
+
+struct RecedingHorizonScenario;
+
+impl Scenario for RecedingHorizonScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Re-plan the nonlinear pendulum from the sampled state each engine step
+        let q = DMatrix::from_diagonal(&DVector::from_vec(vec![10.0, 1.0, 1.0, 1.0]));
+        let r = DMatrix::from_vec(1, 1, vec![0.01]);
+        let qf = &q * 10.0;
+        let inputs = vec![DVector::<Float>::zeros(1); 20];
+        let options = ILQROptions { max_iterations: 10, ..ILQROptions::default() };
+        let controller = ILQR::new(NonlinearInvertedPendulum::new(), 0.1, q, r, qf, inputs, options);
+        let id = SimID { uuid: Uuid::new_v4(), name: "Pendulum".to_string() };
+
+        world.push((FullState { data: DVector::from_vec(vec![0.5, 0.0, 0.0, 0.0]) }, NonlinearInvertedPendulum::new(), controller, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_controlled_dynamics_system::<NonlinearInvertedPendulum, ILQR<NonlinearInvertedPendulum>>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_receding_horizon_ilqr_dynamics() {
+
+    let (simulator, states) = run_scenario(RecedingHorizonScenario, EngineConfig::new(0.0, 8.0, 0.1), SimulatorConfig::default());
+    let ecs = &simulator.get_state().ecs;
+
+    // The short horizon balances the pendulum while the cart is free to drift
+    assert_eq!(states.len(), 1);
+    let state = &states[0].1;
+    assert!(state[0].abs() < 1E-2 && state[1].abs() < 1E-2);
+
+    let mut query = <&ILQR<NonlinearInvertedPendulum>>::query();
+    for controller in query.iter(&ecs.world) {
+        assert!(controller.solution.is_some());
+    }
+
+}
+
+struct LinearizedScenario;
+
+impl Scenario for LinearizedScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // LQR designed on the nonlinear pendulum linearized about the upright equilibrium
+        let pendulum = NonlinearInvertedPendulum::new();
+        let model = pendulum.linearize(0.0, &DVector::zeros(4), &DVector::zeros(1));
+        let q = DMatrix::<Float>::identity(4, 4);
+        let r = DMatrix::<Float>::identity(1, 1);
+        let controller = LQR::new(model.A, model.B, q, r).unwrap();
+        let id = SimID { uuid: Uuid::new_v4(), name: "Pendulum".to_string() };
+
+        world.push((FullState { data: DVector::from_vec(vec![0.3, 0.0, 0.0, 0.0]) }, pendulum, controller, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_lqr_dynamics_system::<NonlinearInvertedPendulum>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_linearized_lqr_dynamics() {
+
+    let (_simulator, states) = run_scenario(LinearizedScenario, EngineConfig::new(0.0, 30.0, 0.1), SimulatorConfig::default());
+
+    assert_eq!(states.len(), 1);
+    assert!(states[0].1.amax() < 1E-2);
+
+}
+
+struct GainScheduleScenario;
+
+impl Scenario for GainScheduleScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Operating points every 0.25 in position, scheduled on the position
+        let trim = (-12..=12)
+            .map(|k| HardeningSpring::trim(k as Float * 0.25))
+            .collect();
+        let q = DMatrix::<Float>::identity(2, 2) * 10.0;
+        let r = DMatrix::<Float>::identity(1, 1);
+        let scheduling = DVector::from_vec(vec![1.0, 0.0]);
+        let controller = GainScheduledController::from_model(&HardeningSpring::new(), trim, q, r, scheduling, Interpolation::Linear).unwrap();
+
+        let reference = Reference::setpoint(DVector::from_vec(vec![2.2, 0.0]), None);
+        let id = SimID { uuid: Uuid::new_v4(), name: "HardeningSpring".to_string() };
+        world.push((FullState { data: DVector::from_vec(vec![-2.0, 0.0]) }, HardeningSpring::new(), controller, reference, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        Schedule::builder()
+            .add_system(integrate_controlled_dynamics_system::<HardeningSpring, GainScheduledController>())
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_gain_scheduled_dynamics() {
+
+    let (_simulator, states) = run_scenario(GainScheduleScenario, EngineConfig::new(0.0, 20.0, 0.1), SimulatorConfig::default());
+
+    // Small offset from interpolating the trim input between operating points
+    assert_eq!(states.len(), 1);
+    let state = &states[0].1;
+    assert!((state[0] - 2.2).abs() < 1E-2);
+    assert!(state[1].abs() < 1E-2);
+
+}
+
+// Registers the generic controller system for the pole placement controllers as well as the LQR
+// systems if overlapping
+struct PolePlacementScenario {
+    overlapping: bool,
+}
+
+impl Scenario for PolePlacementScenario {
+
+    fn setup(&self, world: &mut World, _resources: &mut Resources) {
+
+        // Single input, placed with Ackermann's formula
+        let pendulum = InvertedPendulum::new();
+        let poles = vec![
+            Complex::new(-2.0, 0.0),
+            Complex::new(-3.0, 0.0),
+            Complex::new(-2.0, 1.0),
+            Complex::new(-2.0, -1.0),
+        ];
+        let controller = PolePlacementController::new(pendulum.dynamics().clone(), poles, PolePlacementMethod::Ackermann).unwrap();
+        let id = SimID { uuid: Uuid::new_v4(), name: "InvertedPendulum".to_string() };
+        world.push((FullState { data: DVector::from_vec(vec![0.5, 0.0, 0.2, 0.0]) }, pendulum, controller, id));
+
+        // Multiple inputs, placed with the Kautsky-Nichols method
+        let model = DoubleIntegrator2D::new();
+        let poles = vec![
+            Complex::new(-1.0, 0.5),
+            Complex::new(-1.0, -0.5),
+            Complex::new(-1.5, 0.0),
+            Complex::new(-1.5, 0.0),
+        ];
+        let controller = PolePlacementController::new(model.dynamics().clone(), poles, PolePlacementMethod::KautskyNichols).unwrap();
+        let id = SimID { uuid: Uuid::new_v4(), name: "DoubleIntegrator".to_string() };
+        world.push((FullState { data: DVector::from_vec(vec![3.0, -2.0, 0.0, 1.0]) }, model.clone(), controller.clone(), id));
+
+        // Tracking a setpoint with the same gain
+        let reference = Reference::setpoint(DVector::from_vec(vec![3.0, -2.0, 0.0, 0.0]), None);
+        let id = SimID { uuid: Uuid::new_v4(), name: "Tracking".to_string() };
+        world.push((FullState { data: DVector::<Float>::zeros(4) }, model, controller, reference, id));
+
+    }
+
+    fn build(&self) -> Schedule {
+
+        let mut builder = Schedule::builder();
+        builder
+            .add_system(integrate_lqr_dynamics_system::<InvertedPendulum>())
+            .add_system(integrate_lqr_dynamics_system::<DoubleIntegrator2D>())
+            .add_system(integrate_lqr_tracking_dynamics_system::<DoubleIntegrator2D>());
+        if self.overlapping {
+            builder
+                .add_system(integrate_controlled_dynamics_system::<InvertedPendulum, PolePlacementController>())
+                .add_system(integrate_controlled_dynamics_system::<DoubleIntegrator2D, PolePlacementController>());
+        }
+        builder
+            .add_system(increment_time_system())
+            .build()
+
+    }
+
+    fn update(&mut self, _world: &mut World, _resources: &mut Resources) {}
+
+}
+
+#[test]
+fn test_pole_placement_dynamics() {
+
+    let (_simulator, mut states) = run_scenario(PolePlacementScenario { overlapping: false }, EngineConfig::new(0.0, 15.0, 0.1), SimulatorConfig::default());
+
+    assert_eq!(states.len(), 3);
+    for (name, state) in states.iter() {
+        let target = match name.as_str() {
+            "Tracking" => DVector::from_vec(vec![3.0, -2.0, 0.0, 0.0]),
+            _ => DVector::<Float>::zeros(4),
+        };
+        assert!((state - target).amax() < 1E-3);
+    }
+
+    // The generic system excludes pole placement entities, which are integrated once with both
+    // registered
+    let (_simulator, mut overlapping) = run_scenario(PolePlacementScenario { overlapping: true }, EngineConfig::new(0.0, 15.0, 0.1), SimulatorConfig::default());
+    states.sort_by(|a, b| a.0.cmp(&b.0));
+    overlapping.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(overlapping, states);
+
+}